@@ -0,0 +1,272 @@
+// timed scenarios for the filtering hot paths, run with `cargo bench` and printed as the mean
+// time per iteration. the crate is a binary, so the filter modules are compiled in directly
+#![allow(dead_code)]
+
+// `cargo clippy --all-targets` builds this with cfg(test) but without the test harness, which
+// leaves the imports of the modules' tests unused
+#[allow(unused_imports)]
+#[path = "../src/table_filter.rs"]
+mod table_filter;
+#[allow(unused_imports)]
+#[path = "../src/column_filters.rs"]
+mod column_filters;
+#[allow(unused_imports)]
+#[path = "../src/data_source.rs"]
+mod data_source;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use column_filters::{BoolColumnFilter, StringColumnFilter, U32ColumnFilter};
+use table_filter::{ColumnFilter, ScalarValue, TableFilter};
+
+// the system allocator, counting allocations for the borrowed string scenario
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+struct Row {
+    number: u32,
+    orig: String,
+    dest: String,
+    mileage: u32,
+    cancelled: bool
+}
+
+const AIRPORTS: [&str; 12] = ["ATL", "DFW", "DEN", "ORD", "LAX", "JFK", "LAS", "MCO", "MIA", "PHX", "SEA", "SFO"];
+
+// rows from a fixed xorshift seed, so runs are comparable. `numbers` distinct flight numbers
+fn rows(n: usize, numbers: u32) -> Vec<Row> {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    (0..n).map(|i| Row {
+        number: i as u32 % numbers,
+        orig: AIRPORTS[(next() % 12) as usize].to_string(),
+        dest: AIRPORTS[(next() % 12) as usize].to_string(),
+        mileage: (next() % 3000) as u32,
+        cancelled: next() % 10 == 0
+    }).collect()
+}
+
+// a column filter per field, left out of the table filter so each can be evaluated on its own
+fn flight_columns(table_filter: &Rc<TableFilter<Row>>) -> Vec<Box<dyn ColumnFilter<Row>>> {
+    vec![
+        Box::new(U32ColumnFilter::new("number", Rc::clone(table_filter), Box::new(|r: &Row| r.number), Box::new(|r: &Row| r.number.to_string()))),
+        Box::new(StringColumnFilter::new("orig", Rc::clone(table_filter), Box::new(|r: &Row| r.orig.clone()))),
+        Box::new(StringColumnFilter::new("dest", Rc::clone(table_filter), Box::new(|r: &Row| r.dest.clone()))),
+        Box::new(U32ColumnFilter::new("mileage", Rc::clone(table_filter), Box::new(|r: &Row| r.mileage), Box::new(|r: &Row| r.mileage.to_string()))),
+        Box::new(BoolColumnFilter::new("cancelled", Rc::clone(table_filter), Box::new(|r: &Row| r.cancelled), Box::new(|r: &Row| r.cancelled.to_string()))),
+    ]
+}
+
+fn and_arrays<'a>(len: usize, evals: impl Iterator<Item = &'a [bool]>) -> Vec<bool> {
+    let mut result = vec![true; len];
+    for eval in evals {
+        for (r, &b) in result.iter_mut().zip(eval) {
+            *r &= b;
+        }
+    }
+    result
+}
+
+fn time<R>(label: &str, iterations: u32, mut f: impl FnMut() -> R) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    println!("{:<56} {:>12.2?}", label, start.elapsed() / iterations);
+}
+
+fn main() {
+    popup_value_list();
+    excluded_arrays();
+    parallel_evaluation();
+    idle_frames();
+    evaluate_hot_path();
+    borrowed_strings();
+}
+
+// a popup listing 50k unique values: the sorted unique rows are cached between frames,
+// and show_rows() lays out only the checkboxes in view
+fn popup_value_list() {
+    let data = Rc::new(RefCell::new(rows(50_000, 50_000)));
+    let table_filter = TableFilter::new(&data);
+    table_filter.column_filter(Box::new(U32ColumnFilter::new("number", Rc::clone(&table_filter), Box::new(|r: &Row| r.number), Box::new(|r: &Row| r.number.to_string()))));
+    table_filter.column_filter(Box::new(U32ColumnFilter::new("mileage", Rc::clone(&table_filter), Box::new(|r: &Row| r.mileage), Box::new(|r: &Row| r.mileage.to_string()))));
+
+    let ctx = egui::Context::default();
+    egui::Popup::open_id(&ctx, egui::Id::new("number"));
+    let popup_frame = || ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let response = ui.button("number");
+            table_filter.bind_for_id("number", response);
+        });
+    });
+    // a change in the data length makes the popup rebuild its list
+    let mut extra_row = rows(1, 1).pop();
+    time("popup frame, unique values rebuilt every frame", 5, || {
+        match extra_row.take() {
+            Some(row) => data.borrow_mut().push(row),
+            None => extra_row = data.borrow_mut().pop()
+        }
+        popup_frame()
+    });
+    time("popup frame, unique values cached", 5, popup_frame);
+
+    let labels = data.borrow().iter().map(|r| r.number.to_string()).collect::<Vec<_>>();
+    let ctx = egui::Context::default();
+    let frame = |virtualized: bool| ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let mut checked = true;
+            let scroll_area = egui::ScrollArea::vertical().max_height(400.0);
+            if virtualized {
+                let row_height = ui.spacing().interact_size.y;
+                scroll_area.show_rows(ui, row_height, labels.len(), |ui, row_range| {
+                    labels[row_range].iter().for_each(|label| { ui.checkbox(&mut checked, label.as_str()); });
+                });
+            } else {
+                scroll_area.show(ui, |ui| {
+                    labels.iter().for_each(|label| { ui.checkbox(&mut checked, label.as_str()); });
+                });
+            }
+        });
+    });
+    time("value list frame, every checkbox laid out", 5, || frame(false));
+    time("value list frame, show_rows", 5, || frame(true));
+}
+
+// every header asking for the rows its popup can select, as each frame does, on 10k rows.
+// evaluating each other column's rows for every header is the quadratic cost the cached
+// per-column eval arrays avoid. the cached run marks the data dirty each frame, so every
+// column is still evaluated once per frame rather than reused from the first
+fn excluded_arrays() {
+    let data = Rc::new(RefCell::new(rows(10_000, 500)));
+    let table_filter = TableFilter::new(&data);
+    let columns = flight_columns(&table_filter);
+    let len = data.borrow().len();
+
+    time("excluded arrays, each column evaluated per header", 20, || columns.iter()
+        .map(|excluded| {
+            let evals = columns.iter()
+                .filter(|cf| cf.id() != excluded.id())
+                .map(|cf| data.borrow().iter().map(|r| cf.evaluate(r)).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            and_arrays(len, evals.iter().map(|eval| eval.as_slice()))
+        })
+        .count());
+    time("excluded arrays, each column evaluated per frame", 20, || {
+        table_filter.mark_dirty();
+        columns.iter()
+            .map(|excluded| {
+                let evals = columns.iter()
+                    .filter(|cf| cf.id() != excluded.id())
+                    .map(|cf| cf.get_eval_bool_array())
+                    .collect::<Vec<_>>();
+                and_arrays(len, evals.iter().map(|eval| eval.as_slice()))
+            })
+            .count()
+    });
+}
+
+// one column evaluated over 100k rows, serially and, with the `rayon` feature, through a
+// parallel mapper
+fn parallel_evaluation() {
+    let data = Rc::new(RefCell::new(rows(100_000, 5_000)));
+    let table_filter = TableFilter::new(&data);
+    table_filter.column_filter(Box::new(U32ColumnFilter::new("number", Rc::clone(&table_filter), Box::new(|r: &Row| r.number), Box::new(|r: &Row| r.number.to_string()))));
+    time("evaluate 100k rows, serial", 50, || {
+        table_filter.mark_dirty();
+        table_filter.evaluate_array()
+    });
+    #[cfg(feature = "rayon")]
+    {
+        table_filter.parallel_mapper_for_id("number", |r: &Row| table_filter::ScalarValue::U32(r.number));
+        time("evaluate 100k rows, rayon", 50, || {
+            table_filter.mark_dirty();
+            table_filter.evaluate_array()
+        });
+    }
+}
+
+// evaluate_array() on 100k rows once per frame: an idle frame reuses the composite result,
+// while a frame after an edit re-evaluates every column
+fn idle_frames() {
+    let data = Rc::new(RefCell::new(rows(100_000, 5_000)));
+    let table_filter = TableFilter::new(&data);
+    flight_columns(&table_filter).into_iter().for_each(|cf| table_filter.column_filter(cf));
+    table_filter.evaluate_array();
+    assert!(!table_filter.is_dirty());
+    time("frame after an edit, every column re-evaluated", 20, || {
+        table_filter.mark_dirty();
+        table_filter.evaluate_array()
+    });
+    time("idle frame", 20, || table_filter.evaluate_array());
+}
+
+// the lookup evaluate() does for every row, on 100k rows of a numeric and a string column
+// with half their values unselected: the Fx-hashed set it uses against a std SipHash set
+fn evaluate_hot_path() {
+    let data = rows(100_000, 5_000);
+    let table_filter = TableFilter::new(&Rc::new(RefCell::new(Vec::new())));
+    let number = U32ColumnFilter::new("number", Rc::clone(&table_filter), Box::new(|r: &Row| r.number), Box::new(|r: &Row| r.number.to_string()));
+    let orig = StringColumnFilter::new("orig", Rc::clone(&table_filter), Box::new(|r: &Row| r.orig.clone()));
+
+    for cf in [&number as &dyn ColumnFilter<Row>, &orig] {
+        let values = data.iter().map(|r| cf.get_value(r)).collect::<Vec<ScalarValue>>();
+        let unselected = values.iter().unique().step_by(2).cloned().collect::<Vec<_>>();
+        let sip = unselected.iter().cloned().collect::<HashSet<_>>();
+        let fx = unselected.iter().cloned().collect::<FxHashSet<_>>();
+        time(&format!("evaluate {}, std HashSet", cf.id()), 20, || values.iter()
+            .filter(|v| !sip.contains(*v))
+            .count());
+        time(&format!("evaluate {}, FxHashSet", cf.id()), 20, || values.iter()
+            .filter(|v| !fx.contains(*v))
+            .count());
+    }
+}
+
+// a string column evaluated over 100k rows through a mapper returning an owned String
+// against one borrowing the &str from the row, with the allocations each pass makes
+fn borrowed_strings() {
+    let data = Rc::new(RefCell::new(rows(100_000, 5_000)));
+    let table_filter = TableFilter::new(&data);
+    let owned = StringColumnFilter::new("orig", Rc::clone(&table_filter), Box::new(|r: &Row| r.orig.clone()));
+    let borrowed = StringColumnFilter::new_borrowed("orig", Rc::clone(&table_filter), Box::new(|r: &Row| r.orig.as_str()));
+    let selected = AIRPORTS[..6].iter().map(|a| ScalarValue::Str(a.to_string())).collect::<Vec<_>>();
+
+    for (label, cf) in [("owned", &owned), ("borrowed", &borrowed)] {
+        cf.set_selected_values(selected.clone());
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        table_filter.mark_dirty();
+        cf.get_eval_bool_array();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        time(&format!("evaluate orig, {} strings ({} allocations)", label, allocations), 20, || {
+            table_filter.mark_dirty();
+            cf.get_eval_bool_array()
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Token};
+
+// #[derive(TableFilter)] generates a `table_filter(&data)` constructor registering a column
+// filter for every field marked with #[filter(...)], using the field name as the column id.
+//
+//     #[derive(TableFilter)]
+//     struct Flight {
+//         #[filter(string)] orig: String,
+//         #[filter(u32)] mileage: u32,
+//         #[filter(date = "%m/%d/%Y")] dep_date: NaiveDate,
+//     }
+//
+// supported types are string, u8, u32, usize, i32, bool and date (with an optional format)
+#[proc_macro_derive(TableFilter, attributes(filter))]
+pub fn derive_table_filter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(ident, "TableFilter can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(ident, "TableFilter requires named fields"));
+    };
+
+    let mut registrations = vec![];
+    for field in &fields.named {
+        let Some(field_ident) = &field.ident else { continue };
+        let id = field_ident.to_string();
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("filter")) {
+            let mut kind = None;
+            let mut date_format = None;
+            attr.parse_nested_meta(|meta| {
+                let Some(name) = meta.path.get_ident() else {
+                    return Err(meta.error("expected a filter type"));
+                };
+                if name == "date" && meta.input.peek(Token![=]) {
+                    date_format = Some(meta.value()?.parse::<LitStr>()?.value());
+                }
+                kind = Some(name.to_string());
+                Ok(())
+            })?;
+
+            let numeric = |filter: TokenStream2| quote! {
+                #filter::new(
+                    #id,
+                    ::std::rc::Rc::clone(&table_filter),
+                    Box::new(|x: &Self| x.#field_ident),
+                    Box::new(|x: &Self| x.#field_ident.to_string())
+                )
+            };
+            let column_filter = match kind.as_deref() {
+                Some("string") => quote! {
+                    crate::column_filters::StringColumnFilter::new(
+                        #id,
+                        ::std::rc::Rc::clone(&table_filter),
+                        Box::new(|x: &Self| x.#field_ident.to_string())
+                    )
+                },
+                Some("u8") => numeric(quote! { crate::column_filters::U8ColumnFilter }),
+                Some("u32") => numeric(quote! { crate::column_filters::U32ColumnFilter }),
+                Some("usize") => numeric(quote! { crate::column_filters::USizeColumnFilter }),
+                Some("i32") => numeric(quote! { crate::column_filters::I32ColumnFilter }),
+                Some("bool") => numeric(quote! { crate::column_filters::BoolColumnFilter }),
+                Some("date") => {
+                    let date_format = date_format.unwrap_or_else(|| "%-m/%-d/%Y".to_string());
+                    quote! {
+                        crate::column_filters::NaiveDateColumnFilter::new(
+                            #id,
+                            ::std::rc::Rc::clone(&table_filter),
+                            #date_format.to_string(),
+                            Box::new(|x: &Self| x.#field_ident)
+                        )
+                    }
+                },
+                _ => return Err(syn::Error::new_spanned(
+                    attr,
+                    "expected one of string, u8, u32, usize, i32, bool or date"
+                )),
+            };
+            registrations.push(quote! {
+                table_filter.column_filter(Box::new(#column_filter));
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            pub fn table_filter(
+                data: &::std::rc::Rc<::std::cell::RefCell<Vec<Self>>>
+            ) -> ::std::rc::Rc<crate::table_filter::TableFilter<Self>> {
+                let table_filter = crate::table_filter::TableFilter::new(data);
+                #(#registrations)*
+                table_filter
+            }
+        }
+    })
+}
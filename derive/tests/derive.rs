@@ -0,0 +1,8 @@
+// expansions that must compile against the real filter modules, and the errors
+// reported for structs the derive can't handle
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/pass/*.rs");
+    t.compile_fail("tests/ui/fail/*.rs");
+}
@@ -0,0 +1,9 @@
+use egui_table_filter_derive::TableFilter;
+
+#[derive(TableFilter)]
+enum Status {
+    OnTime,
+    Delayed,
+}
+
+fn main() {}
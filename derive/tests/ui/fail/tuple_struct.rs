@@ -0,0 +1,6 @@
+use egui_table_filter_derive::TableFilter;
+
+#[derive(TableFilter)]
+struct Flight(#[filter(string)] String);
+
+fn main() {}
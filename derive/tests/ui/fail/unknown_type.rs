@@ -0,0 +1,9 @@
+use egui_table_filter_derive::TableFilter;
+
+#[derive(TableFilter)]
+struct Flight {
+    #[filter(f64)]
+    fare: f64,
+}
+
+fn main() {}
@@ -0,0 +1,54 @@
+// the derive refers to crate::table_filter and crate::column_filters, so the
+// modules are compiled in directly, like in the benches
+#[path = "../../../../src/table_filter.rs"]
+mod table_filter;
+#[path = "../../../../src/column_filters.rs"]
+mod column_filters;
+#[path = "../../../../src/data_source.rs"]
+mod data_source;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use chrono::NaiveDate;
+use egui_table_filter_derive::TableFilter;
+
+#[derive(TableFilter)]
+struct Flight {
+    #[filter(string)]
+    orig: String,
+    #[filter(u8)]
+    gate: u8,
+    #[filter(u32)]
+    mileage: u32,
+    #[filter(usize)]
+    seats: usize,
+    #[filter(i32)]
+    delay: i32,
+    #[filter(bool)]
+    cancelled: bool,
+    #[filter(date)]
+    dep_date: NaiveDate,
+    #[filter(date = "%Y-%m-%d")]
+    arr_date: NaiveDate,
+    // fields without #[filter] get no column
+    notes: String,
+}
+
+fn main() {
+    let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+    let flights = Rc::new(RefCell::new(vec![Flight {
+        orig: "PHX".to_string(),
+        gate: 4,
+        mileage: 1500,
+        seats: 180,
+        delay: -5,
+        cancelled: false,
+        dep_date: day,
+        arr_date: day,
+        notes: String::new(),
+    }]));
+    let table_filter = Flight::table_filter(&flights);
+    assert!(table_filter.evaluate(&flights.borrow()[0]));
+    assert!(!table_filter.is_active_for_id("orig"));
+    let _ = &flights.borrow()[0].notes;
+}
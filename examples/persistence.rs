@@ -0,0 +1,86 @@
+// a small app whose column selections and searches survive restarts, run with
+// `cargo run --example persistence --features persistence`. filter a column, close the
+// window and run it again. the crate is a binary, so the filter modules are compiled in directly
+#![allow(dead_code)]
+
+#[allow(unused_imports)]
+#[path = "../src/table_filter.rs"]
+mod table_filter;
+#[allow(unused_imports)]
+#[path = "../src/column_filters.rs"]
+mod column_filters;
+#[allow(unused_imports)]
+#[path = "../src/data_source.rs"]
+mod data_source;
+#[path = "../src/filterable_table.rs"]
+mod filterable_table;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use eframe::egui;
+use egui_extras::Column;
+use crate::filterable_table::FilterableTable;
+use crate::table_filter::TableFilter;
+
+struct Airport {
+    code: &'static str,
+    state: &'static str,
+    runways: u32,
+}
+
+struct PersistenceApp {
+    table_filter: Rc<TableFilter<Airport>>,
+}
+
+impl Default for PersistenceApp {
+    fn default() -> Self {
+        let airports = Rc::new(RefCell::new(vec![
+            Airport { code: "ATL", state: "GA", runways: 5 },
+            Airport { code: "DFW", state: "TX", runways: 7 },
+            Airport { code: "IAH", state: "TX", runways: 5 },
+            Airport { code: "LAX", state: "CA", runways: 4 },
+            Airport { code: "ORD", state: "IL", runways: 8 },
+            Airport { code: "PHX", state: "AZ", runways: 3 },
+            Airport { code: "SFO", state: "CA", runways: 4 },
+        ]));
+        // each column restores its saved state the first time its header is bound,
+        // dropping saved values the rows no longer hold
+        let table_filter = TableFilter::builder(&airports)
+            .string_ref("code", |x| x.code)
+            .string_ref("state", |x| x.state)
+            .u32("runways", |x| x.runways)
+            .build();
+
+        Self { table_filter }
+    }
+}
+
+impl eframe::App for PersistenceApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Airports");
+            ui.label("Filter a column, then restart the app to see it restored.");
+
+            FilterableTable::new(&self.table_filter)
+                .column("CODE", "code", Column::auto(), |ui, airport| {
+                    ui.label(airport.code);
+                })
+                .column("STATE", "state", Column::auto(), |ui, airport| {
+                    ui.label(airport.state);
+                })
+                .column("RUNWAYS", "runways", Column::remainder(), |ui, airport| {
+                    ui.label(airport.runways.to_string());
+                })
+                .show(ui);
+        });
+    }
+}
+
+fn main() -> eframe::Result {
+    // egui's memory, holding the columns' states, is saved under the app name
+    eframe::run_native(
+        "Table Filter Persistence",
+        eframe::NativeOptions::default(),
+        Box::new(|_cc| Ok(Box::<PersistenceApp>::default())),
+    )
+}
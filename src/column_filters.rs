@@ -1,40 +1,329 @@
-use std::cell::{LazyCell};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::ops::Range;
 use std::rc::Rc;
+use std::sync::LazyLock;
 use chrono::NaiveDate;
 use regex::Regex;
-use crate::table_filter::{ColumnFilter, ColumnFilterState, ScalarValue, TableFilter};
+use rustc_hash::FxHashSet;
+use crate::data_source::CachedValues;
+use crate::table_filter::{search_terms, split_terms, ColumnFilter, ColumnFilterState, ScalarValue, TableFilter, FIELD_SEPARATOR};
+
+// how a plain search term (no =, globs or fuzzy matching) is matched against a string value
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MatchMode {
+    // contains for a single term, prefix when there are several. the default, since it's how
+    // string columns matched before the other modes existed, where Prefix would stop a single
+    // term from finding values containing it
+    #[default]
+    Auto,
+    Prefix,
+    Contains,
+    Suffix,
+    Exact,
+}
+
+impl MatchMode {
+    // byte range of `target` matched by `term`, if any
+    fn find(&self, term: &str, target: &str, multiple: bool) -> Option<Range<usize>> {
+        match self {
+            MatchMode::Auto if multiple => MatchMode::Prefix.find(term, target, multiple),
+            MatchMode::Auto | MatchMode::Contains => target.find(term).map(|idx| idx..idx + term.len()),
+            MatchMode::Prefix => target.starts_with(term).then_some(0..term.len()),
+            MatchMode::Suffix => target.ends_with(term).then(|| target.len() - term.len()..target.len()),
+            MatchMode::Exact => (target == term).then_some(0..target.len()),
+        }
+    }
+}
+
+// owned mappers compute a value per row, borrowed ones read a field in place
+enum StringMapper<T> {
+    Owned(Box<dyn Fn(&T) -> String>),
+    Borrowed(Box<dyn for<'a> Fn(&'a T) -> &'a str>)
+}
 
 pub struct StringColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
-    mapper: Box<dyn Fn(&T) -> String>
+    mapper: StringMapper<T>,
+    fuzzy: bool,
+    match_mode: MatchMode,
+    // NFC-normalizes and case-folds before matching, see with_unicode_matching()
+    #[cfg(feature = "unicode")]
+    unicode_matching: bool
 }
 
 impl <T> StringColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self::with_mapper(id, table_filter, StringMapper::Owned(mapper))
+    }
+
+    // same as new(), but the mapper borrows the string from the row instead of allocating one
+    pub fn new_borrowed(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn for<'a> Fn(&'a T) -> &'a str>) -> Self {
+        Self::with_mapper(id, table_filter, StringMapper::Borrowed(mapper))
+    }
+
+    fn with_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: StringMapper<T>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
-            mapper
+            mapper,
+            fuzzy: false,
+            match_mode: MatchMode::default(),
+            #[cfg(feature = "unicode")]
+            unicode_matching: false
+        }
+    }
+
+    pub fn with_match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.match_mode = match_mode;
+        self
+    }
+
+    // matches each plain search term as a case-insensitive subsequence, so "lx" finds "LAX",
+    // and lists the popup's matches by descending score. `=`, glob and negated terms
+    // keep their usual meaning
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    // matches search terms ignoring case and Unicode normal form, so "cafe\u{301}" is found by
+    // "CAFÉ". off by default since folding every value is slower than comparing bytes,
+    // and matches aren't highlighted while it is on
+    #[cfg(feature = "unicode")]
+    pub fn with_unicode_matching(mut self, unicode_matching: bool) -> Self {
+        self.unicode_matching = unicode_matching;
+        self
+    }
+
+    // search_pattern() on the possibly folded pattern and target
+    fn plain_search(&self, pattern: &str, target: &str) -> bool {
+        // search for multiple values separated by commas, each matched per the match mode
+        let multiple = split_terms(pattern).len() > 1;
+        search_terms(pattern, false, |term| if self.fuzzy && is_plain_term(term) {
+            fuzzy_score(term, target).is_some()
+        } else {
+            string_term_matches(term, target, self.match_mode, multiple)
+        })
+    }
+}
+
+// one term of a string search. a leading = requires an exact match, terms with * or ? are
+// matched as globs anchored at both ends, and anything else per the match mode
+fn string_term_matches(term: &str, target: &str, match_mode: MatchMode, multiple: bool) -> bool {
+    if let Some(exact) = term.strip_prefix("=") {
+        target == exact
+    } else if term.contains(['*', '?']) {
+        glob_matches(&glob_tokens(term), &target.chars().collect::<Vec<_>>())
+    } else {
+        match_mode.find(term, target, multiple).is_some()
+    }
+}
+
+// a string column made of several fields, e.g. a flight number and its codeshare number.
+// the popup lists each distinct combination as `field / field`, and a search term matches
+// a row when it matches any one of the fields
+pub struct MultiFieldStringColumnFilter<T> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    mappers: Vec<StrMapper<T>>,
+    match_mode: MatchMode
+}
+
+impl <T> MultiFieldStringColumnFilter<T> {
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mappers: Vec<StrMapper<T>>) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            mappers,
+            match_mode: MatchMode::default()
         }
     }
+
+    pub fn with_match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.match_mode = match_mode;
+        self
+    }
+}
+
+impl <T> ColumnFilter<T> for MultiFieldStringColumnFilter<T> {
+    fn id(&self) -> &str { self.id.as_str() }
+    // every field, so rows are deduplicated by the combination
+    fn get_value(&self, t: &T) -> ScalarValue {
+        ScalarValue::Fields(self.mappers.iter().map(|mapper| mapper(t)).collect())
+    }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn search_pattern(&self, pattern: &String, target: &String) -> bool {
+        let fields = target.split(FIELD_SEPARATOR).collect::<Vec<_>>();
+        let multiple = split_terms(pattern).len() > 1;
+        search_terms(pattern, false, |term| fields.iter().any(|field| string_term_matches(term, field, self.match_mode, multiple)))
+    }
+}
+
+// NFC form of the full case folding of `s`, which is equal for strings differing only in case
+// or in composed vs decomposed accents
+#[cfg(feature = "unicode")]
+fn unicode_fold(s: &str) -> String {
+    use unicode_normalization::UnicodeNormalization;
+    caseless::default_case_fold_str(&s.nfc().collect::<String>()).nfc().collect()
 }
 
 impl <T> ColumnFilter<T> for StringColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
-    fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::Str((self.mapper)(t)) }
+    fn get_value(&self, t: &T) -> ScalarValue {
+        match &self.mapper {
+            StringMapper::Owned(mapper) => ScalarValue::Str(mapper(t)),
+            StringMapper::Borrowed(mapper) => ScalarValue::Str(mapper(t).to_string())
+        }
+    }
+    fn get_str_ref<'a>(&self, t: &'a T) -> Option<&'a str> {
+        match &self.mapper {
+            StringMapper::Owned(_) => None,
+            StringMapper::Borrowed(mapper) => Some(mapper(t))
+        }
+    }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        // search for multiple values separated by commas
-        // otherwise just do contains() logic
-        if pattern.contains(",") {
-            pattern.split(",").any(|pattern| {
-                target.starts_with(pattern)
+        #[cfg(feature = "unicode")]
+        if self.unicode_matching {
+            return self.plain_search(&unicode_fold(pattern), &unicode_fold(target));
+        }
+        self.plain_search(pattern, target)
+    }
+    // the best score of the plain terms when fuzzy, 0 for any other match
+    fn search_score(&self, pattern: &String, target: &String) -> Option<i32> {
+        if !self.search_pattern(pattern, target) {
+            return None;
+        }
+        let scores = split_terms(pattern).into_iter()
+            .filter(|term| self.fuzzy && is_plain_term(term))
+            .filter_map(|term| fuzzy_score(&term, target));
+        Some(scores.max().unwrap_or(0))
+    }
+    fn is_fuzzy(&self) -> bool { self.fuzzy }
+    fn match_spans(&self, pattern: &str, target: &str) -> Vec<Range<usize>> {
+        // folding moves byte offsets, so there is nothing to map spans back onto
+        #[cfg(feature = "unicode")]
+        if self.unicode_matching {
+            return vec![];
+        }
+        // mirrors search_pattern(), globs and negated terms are not highlighted
+        let terms = split_terms(pattern);
+        let multiple = terms.len() > 1;
+        terms.iter()
+            .filter(|term| !term.is_empty() && !term.starts_with('!') && !term.contains(['*', '?']))
+            .flat_map(|term| {
+                if let Some(exact) = term.strip_prefix("=") {
+                    (target == exact).then_some(0..target.len()).into_iter().collect()
+                } else if self.fuzzy {
+                    fuzzy_match(term, target).map(|(_, spans)| spans).unwrap_or_default()
+                } else {
+                    self.match_mode.find(term, target, multiple).into_iter().collect::<Vec<_>>()
+                }
             })
-        } else {
-            target.contains(pattern)
+            .collect()
+    }
+}
+
+// a term matched per the match mode or fuzzily, rather than by = or as a glob
+fn is_plain_term(term: &str) -> bool {
+    !term.starts_with('=') && !term.contains(['*', '?'])
+}
+
+// case-insensitive subsequence scorer, so "lx" matches "LAX".
+// consecutive matches and matches at the start of the target score higher.
+// matching is greedy: each character takes its first occurrence after the previous one, so
+// "ax" scores "LAHAX" by its first A and misses the consecutive "AX". a target matches
+// whenever any alignment exists, only the score can fall short of the best one
+fn fuzzy_score(pattern: &str, target: &str) -> Option<i32> {
+    fuzzy_match(pattern, target).map(|(score, _)| score)
+}
+
+// fuzzy score along with the byte ranges of the matched characters in `target`
+fn fuzzy_match(pattern: &str, target: &str) -> Option<(i32, Vec<Range<usize>>)> {
+    let target = target.char_indices().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+    let mut spans = vec![];
+
+    for p in pattern.chars() {
+        let idx = pos + target[pos..].iter().position(|(_, c)| c.to_lowercase().eq(p.to_lowercase()))?;
+        score += 1;
+        if idx == 0 {
+            score += 3;
+        }
+        if let Some(last) = last_match && last + 1 == idx {
+            score += 2;
+        }
+        // penalize gaps between matched characters
+        score -= (idx - pos) as i32;
+        last_match = Some(idx);
+        pos = idx + 1;
+
+        let (start, c) = target[idx];
+        spans.push(start..start + c.len_utf8());
+    }
+    Some((score, spans))
+}
+
+enum GlobToken {
+    Star,
+    AnyChar,
+    Literal(char),
+}
+
+// `*` matches any run of characters, `?` a single character, and `\` escapes the next character
+fn glob_tokens(glob: &str) -> Vec<GlobToken> {
+    let mut tokens = vec![];
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => GlobToken::Star,
+            '?' => GlobToken::AnyChar,
+            '\\' => GlobToken::Literal(chars.next().unwrap_or('\\')),
+            c => GlobToken::Literal(c),
+        });
+    }
+    tokens
+}
+
+fn glob_matches(tokens: &[GlobToken], target: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // last star seen, and the target position it is currently consuming up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < target.len() {
+        match tokens.get(p) {
+            Some(GlobToken::Star) => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(GlobToken::AnyChar) => {
+                p += 1;
+                t += 1;
+            }
+            Some(GlobToken::Literal(c)) if *c == target[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // backtrack, letting the last star swallow one more character
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
         }
     }
+    tokens[p..].iter().all(|token| matches!(token, GlobToken::Star))
 }
 
 #[macro_export]
@@ -53,72 +342,197 @@ macro_rules! string_filters {
     };
 }
 
+#[macro_export]
+macro_rules! fuzzy_string_filters {
+    // same as string_filters!, but the search box uses fuzzy subsequence matching
+    ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
+        $(
+            $table.column_filter(Box::new(
+                StringColumnFilter::new(
+                    $id,
+                    std::rc::Rc::clone(&$table),
+                    Box::new(|$arg| $mapper)
+                ).fuzzy(true)
+            ));
+        )*
+    };
+}
+
+static LESS_THAN_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^<-?[0-9]+(\.[0-9]+)?$"#).unwrap());
+static LESS_THAN_EQUAL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^<=-?[0-9]+(\.[0-9]+)?$"#).unwrap());
+static GREATER_THAN_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^>-?[0-9]+(\.[0-9]+)?$"#).unwrap());
+static GREATER_THAN_EQUAL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^>=-?[0-9]+(\.[0-9]+)?$"#).unwrap());
+static EQUAL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^=-?[0-9]+(\.[0-9]+)?$"#).unwrap());
+
+// inclusive `a..b` range term, e.g. `100..500`. either bound may be omitted (`100..`, `..500`)
+// and reversed bounds are swapped. returns None when the term is not a range
+fn range_term_matches<N: PartialOrd>(term: &str, target: Option<N>, parse: impl Fn(&str) -> Option<N>) -> Option<bool> {
+    let (lower, upper) = term.split_once("..")?;
+    let bound = |s: &str| if s.is_empty() { Ok(None) } else { parse(s).map(Some).ok_or(()) };
+
+    let (Ok(mut lower), Ok(mut upper), Some(x)) = (bound(lower), bound(upper), target) else {
+        return Some(false);
+    };
+    if lower.is_some() && upper.is_some() && lower > upper {
+        std::mem::swap(&mut lower, &mut upper);
+    }
+    Some(lower.is_none_or(|l| x >= l) && upper.is_none_or(|u| x <= u))
+}
+
+// drops thousands separators (commas, spaces and underscores) and the column's currency or
+// unit affixes, so `$1,234` and `1 234` read as 1234. the comparison operators are kept
+fn normalize_number(s: &str, affixes: &[String]) -> String {
+    let mut s = s.to_string();
+    for affix in affixes.iter().filter(|affix| !affix.is_empty()) {
+        s = s.replace(affix.as_str(), "");
+    }
+    s.chars().filter(|c| !matches!(c, ',' | ' ' | '_')).collect()
+}
+
+// a number as its floor and whether it has a fractional part, so `2.5` is (2, true) and `-2.5`
+// is (-3, true). these order like the numbers themselves against whole values, which is all the
+// targets ever are, so `>2.5` keeps 3 and up while `=2.5` keeps nothing
+fn parse_number(s: &str) -> Option<(i128, bool)> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if s.contains('.') && (fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let whole = whole.parse::<i128>().ok()?;
+    let fractional = fraction.bytes().any(|b| b != b'0');
+    Some(if fractional && s.starts_with('-') { (whole - 1, true) } else { (whole, fractional) })
+}
+
+// the operands of an `in(a,b,c)` set term, in either case, or None for any other term
+pub(crate) fn in_operands(term: &str) -> Option<Vec<&str>> {
+    let inner = term.get(..3).filter(|head| head.eq_ignore_ascii_case("in("))
+        .and_then(|_| term[3..].strip_suffix(')'))?;
+    Some(inner.split(',').map(str::trim).filter(|operand| !operand.is_empty()).collect())
+}
+
+// shared term logic for the numeric filters: `in(a,b,c)` sets, a..b ranges, then <=, >=, =, < and >
+// comparisons (checked in that order), anything else falls back to prefix matching. values and
+// operands are compared as i128, so operands outside the column's type still compare by value,
+// e.g. `>-1` keeps every value of an unsigned column and `<300` every value of a u8 one.
+// operands may have a decimal part, see parse_number()
+fn numeric_term_matches(term: &str, target: &str, affixes: &[String]) -> bool {
+    if let Some(operands) = in_operands(term) {
+        let x = parse_number(&normalize_number(target, affixes));
+        return x.is_some_and(|x| operands.iter().any(|operand| parse_number(&normalize_number(operand, affixes)) == Some(x)));
+    }
+    let term = &normalize_number(term, affixes);
+    let target = &normalize_number(target, affixes);
+    let compare = |op: &str, cmp: fn(&(i128, bool), &(i128, bool)) -> bool| {
+        if let Some(x) = parse_number(target) && let Some(y) = parse_number(&term.replace(op, "")) {
+            cmp(&x, &y)
+        } else {
+            false
+        }
+    };
+    if let Some(in_range) = range_term_matches(term, parse_number(target), parse_number) {
+        in_range
+    } else if LESS_THAN_EQUAL_REGEX.is_match(term) {
+        compare("<=", PartialOrd::le)
+    } else if GREATER_THAN_EQUAL_REGEX.is_match(term) {
+        compare(">=", PartialOrd::ge)
+    } else if EQUAL_REGEX.is_match(term) {
+        compare("=", PartialEq::eq)
+    } else if LESS_THAN_REGEX.is_match(term) {
+        compare("<", PartialOrd::lt)
+    } else if GREATER_THAN_REGEX.is_match(term) {
+        compare(">", PartialOrd::gt)
+    } else {
+        target.starts_with(term)
+    }
+}
+
+// terms of a numeric pattern whose comparison, range or set operands aren't numbers,
+// e.g. `>abc`, `100..x` or `in()`, which numeric_term_matches() would silently match against nothing.
+// an unquoted comparison written with a thousands separator, like `>1,000`, is flagged too, since
+// the comma splits it into `>1` and a prefix term `000`
+fn numeric_invalid_terms(pattern: &str, affixes: &[String]) -> Vec<String> {
+    let terms = split_terms(pattern);
+    let split_thousands = terms.windows(2)
+        .map(|pair| (&pair[0], &pair[1]))
+        .filter(|(comparison, group)| {
+            let comparison = normalize_number(comparison.strip_prefix('!').unwrap_or(comparison), affixes);
+            let operand = ["<=", ">=", "=", "<", ">"].iter()
+                .find_map(|op| comparison.strip_prefix(op))
+                .map(|operand| operand.strip_prefix('-').unwrap_or(operand));
+            operand.is_some_and(|operand| (1..=3).contains(&operand.len()) && operand.bytes().all(|b| b.is_ascii_digit()))
+                && group.len() == 3 && group.bytes().all(|b| b.is_ascii_digit())
+        })
+        .map(|(comparison, group)| format!("{},{}", comparison, group))
+        .filter(|term| pattern.contains(term.as_str()))
+        .collect::<Vec<_>>();
+    terms.into_iter()
+        .filter(|term| {
+            let is_number = |s: &str| parse_number(s).is_some();
+            let term = term.strip_prefix('!').unwrap_or(term);
+            if let Some(operands) = in_operands(term) {
+                return operands.is_empty() || !operands.iter().all(|operand| is_number(&normalize_number(operand, affixes)));
+            }
+            let term = normalize_number(term, affixes);
+            if let Some((lower, upper)) = term.split_once("..") {
+                !(lower.is_empty() || is_number(lower)) || !(upper.is_empty() || is_number(upper))
+            } else {
+                ["<=", ">=", "=", "<", ">"].iter()
+                    .find_map(|op| term.strip_prefix(op))
+                    .is_some_and(|operand| !is_number(operand))
+            }
+        })
+        .chain(split_thousands)
+        .collect()
+}
+
+// the text a typed column displays a row's value as, see get_string_value()
+pub type StrMapper<T> = Box<dyn Fn(&T) -> String>;
+
 pub struct U8ColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
     mapper: Box<dyn Fn(&T) -> u8>,
-    str_mapper: Box<dyn Fn(&T) -> String>
+    // None displays the value with its to_string()
+    str_mapper: Option<StrMapper<T>>
 }
 
 impl <T> U8ColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> u8>, str_mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self {
+            str_mapper: Some(str_mapper),
+            ..Self::from_mapper(id, table_filter, mapper)
+        }
+    }
+
+    pub fn from_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> u8>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
             mapper,
-            str_mapper
+            str_mapper: None
         }
     }
-    const LESS_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<[0-9]+$"#).unwrap());
-    const LESS_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<=[0-9]+$"#).unwrap());
-    const GREATER_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>[0-9]+$"#).unwrap());
-    const GREATER_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>=[0-9]+$"#).unwrap());
 }
 
 impl <T> ColumnFilter<T> for U8ColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
     fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::U8((self.mapper)(t)) }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        pattern.split(",").into_iter().all(|pattern| {
-            if pattern.contains("<=") && Self::LESS_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u8, _> = target.parse();
-                let y: Result<u8, _> = pattern.replace("<=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x <= y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">=") && Self::GREATER_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u8, _> = target.parse();
-                let y: Result<u8, _> = pattern.replace(">=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x >= y
-                } else {
-                    false
-                }
-            } else if pattern.contains("<") && Self::LESS_THAN_REGEX.is_match(pattern) {
-                let x: Result<u8, _> = target.parse();
-                let y: Result<u8, _> = pattern.replace("<", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x < y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">") && Self::GREATER_THAN_REGEX.is_match(pattern) {
-                let x: Result<u8, _> = target.parse();
-                let y: Result<u8, _> = pattern.replace(">", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x > y
-                } else {
-                    false
-                }
-            } else {
-                target.starts_with(pattern)
-            }
-        })
+        let affixes = self.column_filter_state().number_affixes();
+        search_terms(pattern, true, |term| numeric_term_matches(term, target, &affixes))
+    }
+    fn supports_range_ui(&self) -> bool { true }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        numeric_invalid_terms(pattern, &self.column_filter_state().number_affixes())
+    }
+    fn invalid_terms_label(&self) -> &str { "Invalid number" }
+    fn get_string_value(&self, t: &T) -> String {
+        match &self.str_mapper {
+            Some(str_mapper) => str_mapper(t),
+            None => self.get_value(t).to_string()
+        }
     }
-    fn get_string_value(&self, t: &T) -> String { (self.str_mapper)(t) }
 }
 
 #[macro_export]
@@ -127,11 +541,10 @@ macro_rules! u8_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                U8ColumnFilter::new(
+                U8ColumnFilter::from_mapper(
                     $id,
                     std::rc::Rc::clone(&$table),
-                    Box::new(|$arg| $mapper),
-                    Box::new(|$arg| $mapper.to_string())
+                    Box::new(|$arg| $mapper)
                 )
             ));
         )*
@@ -156,68 +569,48 @@ pub struct U32ColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
     mapper: Box<dyn Fn(&T) -> u32>,
-    str_mapper: Box<dyn Fn(&T) -> String>
+    // None displays the value with its to_string()
+    str_mapper: Option<StrMapper<T>>
 }
 
 impl <T> U32ColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> u32>, str_mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self {
+            str_mapper: Some(str_mapper),
+            ..Self::from_mapper(id, table_filter, mapper)
+        }
+    }
+
+    pub fn from_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> u32>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
             mapper,
-            str_mapper
+            str_mapper: None
         }
     }
-    const LESS_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<[0-9]+$"#).unwrap());
-    const LESS_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<=[0-9]+$"#).unwrap());
-    const GREATER_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>[0-9]+$"#).unwrap());
-    const GREATER_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>=[0-9]+$"#).unwrap());
 }
 
 impl <T> ColumnFilter<T> for U32ColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
     fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::U32((self.mapper)(t)) }
-    fn get_string_value(&self, t: &T) -> String { (self.str_mapper)(t) }
+    fn get_string_value(&self, t: &T) -> String {
+        match &self.str_mapper {
+            Some(str_mapper) => str_mapper(t),
+            None => self.get_value(t).to_string()
+        }
+    }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        pattern.split(",").into_iter().all(|pattern| {
-            if pattern.contains("<=") && Self::LESS_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace("<=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x <= y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">=") && Self::GREATER_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace(">=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x >= y
-                } else {
-                    false
-                }
-            } else if pattern.contains("<") && Self::LESS_THAN_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace("<", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x < y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">") && Self::GREATER_THAN_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace(">", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x > y
-                } else {
-                    false
-                }
-            } else {
-                target.starts_with(pattern)
-            }
-        })
+        let affixes = self.column_filter_state().number_affixes();
+        search_terms(pattern, true, |term| numeric_term_matches(term, target, &affixes))
     }
+    fn supports_range_ui(&self) -> bool { true }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        numeric_invalid_terms(pattern, &self.column_filter_state().number_affixes())
+    }
+    fn invalid_terms_label(&self) -> &str { "Invalid number" }
 }
 
 #[macro_export]
@@ -226,11 +619,10 @@ macro_rules! u32_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                ColumnFilter::new(
+                U32ColumnFilter::from_mapper(
                     $id,
                     std::rc::Rc::clone(&$table),
-                    Box::new(|$arg| $mapper),
-                    Box::new(|$arg| $mapper.to_string())
+                    Box::new(|$arg| $mapper)
                 )
             ));
         )*
@@ -253,68 +645,48 @@ pub struct USizeColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
     mapper: Box<dyn Fn(&T) -> usize>,
-    str_mapper: Box<dyn Fn(&T) -> String>
+    // None displays the value with its to_string()
+    str_mapper: Option<StrMapper<T>>
 }
 
 impl <T> USizeColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> usize>, str_mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self {
+            str_mapper: Some(str_mapper),
+            ..Self::from_mapper(id, table_filter, mapper)
+        }
+    }
+
+    pub fn from_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> usize>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
             mapper,
-            str_mapper
+            str_mapper: None
         }
     }
-    const LESS_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<[0-9]+$"#).unwrap());
-    const LESS_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<=[0-9]+$"#).unwrap());
-    const GREATER_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>[0-9]+$"#).unwrap());
-    const GREATER_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>=[0-9]+$"#).unwrap());
 }
 
 impl <T> ColumnFilter<T> for USizeColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
     fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::USize((self.mapper)(t)) }
-    fn get_string_value(&self, t: &T) -> String { (self.str_mapper)(t) }
+    fn get_string_value(&self, t: &T) -> String {
+        match &self.str_mapper {
+            Some(str_mapper) => str_mapper(t),
+            None => self.get_value(t).to_string()
+        }
+    }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        pattern.split(",").into_iter().all(|pattern| {
-            if pattern.contains("<=") && Self::LESS_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<usize, _> = target.parse();
-                let y: Result<usize, _> = pattern.replace("<=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x <= y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">=") && Self::GREATER_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<usize, _> = target.parse();
-                let y: Result<usize, _> = pattern.replace(">=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x >= y
-                } else {
-                    false
-                }
-            } else if pattern.contains("<") && Self::LESS_THAN_REGEX.is_match(pattern) {
-                let x: Result<usize, _> = target.parse();
-                let y: Result<usize, _> = pattern.replace("<", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x < y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">") && Self::GREATER_THAN_REGEX.is_match(pattern) {
-                let x: Result<usize, _> = target.parse();
-                let y: Result<usize, _> = pattern.replace(">", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x > y
-                } else {
-                    false
-                }
-            } else {
-                target.starts_with(pattern)
-            }
-        })
+        let affixes = self.column_filter_state().number_affixes();
+        search_terms(pattern, true, |term| numeric_term_matches(term, target, &affixes))
+    }
+    fn supports_range_ui(&self) -> bool { true }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        numeric_invalid_terms(pattern, &self.column_filter_state().number_affixes())
     }
+    fn invalid_terms_label(&self) -> &str { "Invalid number" }
 }
 
 #[macro_export]
@@ -323,11 +695,10 @@ macro_rules! usize_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                ColumnFilter::new(
+                USizeColumnFilter::from_mapper(
                     $id,
                     std::rc::Rc::clone(&$table),
-                    Box::new(|$arg| $mapper),
-                    Box::new(|$arg| $mapper.to_string())
+                    Box::new(|$arg| $mapper)
                 )
             ));
         )*
@@ -351,22 +722,26 @@ pub struct I32ColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
     mapper: Box<dyn Fn(&T) -> i32>,
-    str_mapper: Box<dyn Fn(&T) -> String>,
+    // None displays the value with its to_string()
+    str_mapper: Option<StrMapper<T>>,
 }
 
 impl <T> I32ColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> i32>, str_mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self {
+            str_mapper: Some(str_mapper),
+            ..Self::from_mapper(id, table_filter, mapper)
+        }
+    }
+
+    pub fn from_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> i32>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
             mapper,
-            str_mapper
+            str_mapper: None
         }
     }
-    const LESS_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<[0-9]+$"#).unwrap());
-    const LESS_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^<=[0-9]+$"#).unwrap());
-    const GREATER_THAN_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>[0-9]+$"#).unwrap());
-    const GREATER_THAN_EQUAL_REGEX: LazyCell<Regex> = LazyCell::new(|| Regex::new(r#"^>=[0-9]+$"#).unwrap());
 }
 
 #[macro_export]
@@ -375,10 +750,10 @@ macro_rules! i32_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                $crate::I32ColumnFilter::new(
+                I32ColumnFilter::from_mapper(
                     $id,
                     std::rc::Rc::clone(&$table),
-                    Box::new(|$arg| $mapper.to_string())
+                    Box::new(|$arg| $mapper)
                 )
             ));
         )*
@@ -386,7 +761,7 @@ macro_rules! i32_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr, |$str_arg:ident| $str_mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                $crate::I32ColumnFilter::new(
+                I32ColumnFilter::new(
                     $id,
                     std::rc::Rc::clone(&$table),
                     Box::new(|$arg| $mapper),
@@ -400,48 +775,24 @@ macro_rules! i32_filters {
 impl <T> ColumnFilter<T> for I32ColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
     fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::I32((self.mapper)(t)) }
-    fn get_string_value(&self, t: &T) -> String { (self.str_mapper)(t) }
+    fn get_string_value(&self, t: &T) -> String {
+        match &self.str_mapper {
+            Some(str_mapper) => str_mapper(t),
+            None => self.get_value(t).to_string()
+        }
+    }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
 
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        pattern.split(",").into_iter().all(|pattern| {
-            if pattern.contains("<=") && Self::LESS_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace("<=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x <= y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">=") && Self::GREATER_THAN_EQUAL_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace(">=", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x >= y
-                } else {
-                    false
-                }
-            } else if pattern.contains("<") && Self::LESS_THAN_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace("<", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x < y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">") && Self::GREATER_THAN_REGEX.is_match(pattern) {
-                let x: Result<u32, _> = target.parse();
-                let y: Result<u32, _> = pattern.replace(">", "").parse();
-                if let Ok(x) = x && let Ok(y) = y {
-                    x > y
-                } else {
-                    false
-                }
-            } else {
-                target.starts_with(pattern)
-            }
-        })
+        let affixes = self.column_filter_state().number_affixes();
+        search_terms(pattern, true, |term| numeric_term_matches(term, target, &affixes))
+    }
+    fn supports_range_ui(&self) -> bool { true }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        numeric_invalid_terms(pattern, &self.column_filter_state().number_affixes())
     }
+    fn invalid_terms_label(&self) -> &str { "Invalid number" }
 }
 
 
@@ -461,6 +812,56 @@ impl <T> NaiveDateColumnFilter<T> {
             mapper
         }
     }
+
+    // parses with the display pattern, and failing that with its padding flags dropped,
+    // so `1/1/2026` and `01/01/2026` both parse whether the pattern is %m/%d/%Y or %-m/%-d/%Y.
+    // the SearchLocale's date pattern is tried last
+    fn parse_date(&self, s: &str) -> Option<NaiveDate> {
+        let s = s.trim();
+        let locale_pattern = self.column_filter_state.table_filter().locale().date_pattern;
+        NaiveDate::parse_from_str(s, &self.date_str_pattern)
+            .or_else(|_| NaiveDate::parse_from_str(s, &self.date_str_pattern.replace("%-", "%")))
+            .ok()
+            .or_else(|| locale_pattern.and_then(|pattern| NaiveDate::parse_from_str(s, pattern).ok()))
+    }
+
+    // the dates a comparison, exact or range term compares against, None for prefix terms
+    fn date_operands(term: &str) -> Option<Vec<&str>> {
+        let term = term.strip_prefix('!').unwrap_or(term);
+        if let Some((lower, upper)) = term.split_once("..") {
+            Some([lower, upper].into_iter().filter(|s| !s.is_empty()).collect())
+        } else {
+            ["<=", ">=", "=", "<", ">"].iter()
+                .find_map(|op| term.strip_prefix(op))
+                .map(|operand| vec![operand])
+        }
+    }
+
+    fn date_term_matches(&self, term: &str, target: &str) -> bool {
+        let compare = |op: &str, cmp: fn(&NaiveDate, &NaiveDate) -> bool| {
+            if let Some(x) = self.parse_date(target) && let Some(y) = self.parse_date(&term.replace(op, "")) {
+                cmp(&x, &y)
+            } else {
+                false
+            }
+        };
+        let parse = |s: &str| self.parse_date(s);
+        if let Some(in_range) = range_term_matches(term, parse(target), parse) {
+            in_range
+        } else if term.contains("<=") {
+            compare("<=", NaiveDate::le)
+        } else if term.contains(">=") {
+            compare(">=", NaiveDate::ge)
+        } else if term.starts_with("=") {
+            compare("=", NaiveDate::eq)
+        } else if term.contains("<") {
+            compare("<", NaiveDate::lt)
+        } else if term.contains(">") {
+            compare(">", NaiveDate::gt)
+        } else {
+            target.starts_with(term)
+        }
+    }
 }
 
 impl <T> ColumnFilter<T> for NaiveDateColumnFilter<T> {
@@ -477,45 +878,23 @@ impl <T> ColumnFilter<T> for NaiveDateColumnFilter<T> {
     }
 
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
 
     fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        pattern.split(",").into_iter().all(|pattern| {
-            if pattern.contains("<=") {
-                let x: Result<NaiveDate, _> =NaiveDate::parse_from_str(&target, self.date_str_pattern.as_str());
-                let y: Result<NaiveDate, _> = NaiveDate::parse_from_str(pattern.replace("<=", "").as_str(), self.date_str_pattern.as_str());
-                if let Ok(x) = x && let Ok(y) = y {
-                    x <= y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">=") {
-                let x: Result<NaiveDate, _> = NaiveDate::parse_from_str(&target, self.date_str_pattern.as_str());
-                let y: Result<NaiveDate, _> = NaiveDate::parse_from_str(pattern.replace(">=", "").as_str(), self.date_str_pattern.as_str());
-                if let Ok(x) = x && let Ok(y) = y {
-                    x >= y
-                } else {
-                    false
-                }
-            } else if pattern.contains("<") {
-                let x: Result<NaiveDate, _> = NaiveDate::parse_from_str(&target, self.date_str_pattern.as_str());
-                let y: Result<NaiveDate, _> = NaiveDate::parse_from_str(pattern.replace("<", "").as_str(), self.date_str_pattern.as_str());
-                if let Ok(x) = x && let Ok(y) = y {
-                    x < y
-                } else {
-                    false
-                }
-            } else if pattern.contains(">") {
-                let x: Result<NaiveDate, _> = NaiveDate::parse_from_str(&target, self.date_str_pattern.as_str());
-                let y: Result<NaiveDate, _> = NaiveDate::parse_from_str(pattern.replace(">", "").as_str(), self.date_str_pattern.as_str());
-                if let Ok(x) = x && let Ok(y) = y {
-                    x > y
-                } else {
-                    false
-                }
-            } else {
-                target.starts_with(pattern)
-            }
-        })
+        search_terms(pattern, true, |term| self.date_term_matches(term, target))
+    }
+    fn date_pattern(&self) -> Option<&str> { Some(&self.date_str_pattern) }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        split_terms(pattern).into_iter()
+            .filter(|term| Self::date_operands(term)
+                .is_some_and(|operands| operands.iter().any(|operand| self.parse_date(operand).is_none())))
+            .collect()
+    }
+    fn invalid_terms_label(&self) -> &str { "Invalid date" }
+    // dates are exported as formatted strings rather than epoch days
+    #[cfg(feature = "serde")]
+    fn json_value(&self, t: &T) -> serde_json::Value {
+        serde_json::Value::from(self.get_string_value(t))
     }
 }
 
@@ -552,25 +931,40 @@ pub struct BoolColumnFilter<T> {
     id: String,
     column_filter_state: ColumnFilterState<T>,
     mapper: Box<dyn Fn(&T) -> bool>,
-    str_mapper: Box<dyn Fn(&T) -> String>,
+    // None displays the value with its to_string()
+    str_mapper: Option<StrMapper<T>>,
 }
 
 impl <T> BoolColumnFilter<T> {
     pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> bool>, str_mapper: Box<dyn Fn(&T) -> String>) -> Self {
+        Self {
+            str_mapper: Some(str_mapper),
+            ..Self::from_mapper(id, table_filter, mapper)
+        }
+    }
+
+    pub fn from_mapper(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> bool>) -> Self {
         Self {
             id: id.to_string(),
             column_filter_state: ColumnFilterState::new(&table_filter),
             mapper,
-            str_mapper
+            str_mapper: None
         }
     }
 }
 
 impl <T> ColumnFilter<T> for BoolColumnFilter<T> {
     fn id(&self) -> &str { self.id.as_str() }
+    fn shows_search(&self) -> bool { false }
     fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::Bool((self.mapper)(t)) }
-    fn get_string_value(&self, t: &T) -> String { (self.str_mapper)(t) }
+    fn get_string_value(&self, t: &T) -> String {
+        match &self.str_mapper {
+            Some(str_mapper) => str_mapper(t),
+            None => self.get_value(t).to_string()
+        }
+    }
     fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluates_by_value(&self) -> bool { true }
 }
 
 #[macro_export]
@@ -578,11 +972,10 @@ macro_rules! bool_filters {
     ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
         $(
             $table.column_filter(Box::new(
-                BoolColumnFilter::new(
+                BoolColumnFilter::from_mapper(
                     $id,
                     std::rc::Rc::clone(&$table),
-                    Box::new(|$arg| $mapper),
-                    Box::new(|$arg| $mapper.to_string())
+                    Box::new(|$arg| $mapper)
                 )
             ));
         )*
@@ -601,13 +994,850 @@ macro_rules! bool_filters {
     };
 }
 
+// a yes/no column derived from the row rather than a field, e.g. long-haul as mileage > 1500.
+// the predicate runs every time the column is evaluated, so it must be deterministic and only
+// depend on the row, or cached results go stale
+pub struct ComputedBoolColumnFilter<T> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    predicate: Box<dyn Fn(&T) -> bool>,
+    labels: (String, String)
+}
 
-#[macro_export]
-macro_rules! col_with_filter {
-    ($header:expr, $table_filter:expr, $id:expr, |$ui:ident| $body:expr) => {{
-        let (_, resp) = $header.col(|$ui| {
-            $body
-        });
-        $table_filter.bind_for_id($id, resp);
+impl <T> ComputedBoolColumnFilter<T> {
+    // `labels` are the displayed (true, false) values, e.g. ("Yes", "No")
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, predicate: Box<dyn Fn(&T) -> bool>, labels: (&str, &str)) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            predicate,
+            labels: (labels.0.to_string(), labels.1.to_string())
+        }
+    }
+}
+
+impl <T> ColumnFilter<T> for ComputedBoolColumnFilter<T> {
+    fn id(&self) -> &str { self.id.as_str() }
+    fn shows_search(&self) -> bool { false }
+    fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::Bool((self.predicate)(t)) }
+    fn get_string_value(&self, t: &T) -> String {
+        if (self.predicate)(t) { self.labels.0.clone() } else { self.labels.1.clone() }
+    }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    // both choices are listed even when every row falls on one side
+    fn all_values(&self) -> Vec<(ScalarValue, String)> {
+        vec![(ScalarValue::Bool(false), self.labels.1.clone()), (ScalarValue::Bool(true), self.labels.0.clone())]
+    }
+}
+
+// a single character column, e.g. a fare class. each comma-separated term is one character,
+// so `A,B,C` matches any of the three. longer terms match nothing and are flagged as invalid
+pub struct CharColumnFilter<T> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    mapper: Box<dyn Fn(&T) -> char>
+}
+
+impl <T> CharColumnFilter<T> {
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> char>) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            mapper
+        }
+    }
+}
+
+impl <T> ColumnFilter<T> for CharColumnFilter<T> {
+    fn id(&self) -> &str { self.id.as_str() }
+    fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::Str((self.mapper)(t).to_string()) }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn search_pattern(&self, pattern: &String, target: &String) -> bool {
+        search_terms(pattern, false, |term| term.chars().count() == 1 && term == target.as_str())
+    }
+    fn match_spans(&self, pattern: &str, target: &str) -> Vec<Range<usize>> {
+        if split_terms(pattern).iter().any(|term| term == target) { std::iter::once(0..target.len()).collect() } else { vec![] }
+    }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        split_terms(pattern).into_iter()
+            .filter(|term| term.strip_prefix('!').unwrap_or(term).chars().count() > 1)
+            .collect()
+    }
+    fn invalid_terms_label(&self) -> &str { "Invalid character" }
+}
+
+#[macro_export]
+macro_rules! char_filters {
+    ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
+        $(
+            $table.column_filter(Box::new(
+                CharColumnFilter::new(
+                    $id,
+                    std::rc::Rc::clone(&$table),
+                    Box::new(|$arg| $mapper)
+                )
+            ));
+        )*
+    };
+}
+
+pub struct IpAddrColumnFilter<T> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    mapper: Box<dyn Fn(&T) -> IpAddr>
+}
+
+impl <T> IpAddrColumnFilter<T> {
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> IpAddr>) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            mapper
+        }
+    }
+}
+
+// a parsed search term of an IpAddrColumnFilter
+enum IpTerm {
+    Exact(IpAddr),
+    // network address and prefix length, e.g. 192.168.0.0/16
+    Cidr(IpAddr, u32),
+    // inclusive, with the lower bound first
+    Range(IpAddr, IpAddr),
+    // the start of an address being typed, e.g. `192.168.`
+    Prefix(String),
+}
+
+impl IpTerm {
+    // None for a malformed term, e.g. a bad address, a prefix length past the address width or letters
+    fn parse(term: &str) -> Option<IpTerm> {
+        let term = term.trim();
+        if let Some(exact) = term.strip_prefix('=') {
+            return exact.parse().ok().map(IpTerm::Exact);
+        }
+        if let Some((network, prefix_len)) = term.split_once('/') {
+            let network = network.parse::<IpAddr>().ok()?;
+            let prefix_len = prefix_len.parse::<u32>().ok()?;
+            let width = if network.is_ipv4() { 32 } else { 128 };
+            return (prefix_len <= width).then_some(IpTerm::Cidr(network, prefix_len));
+        }
+        if let Some((lower, upper)) = term.split_once("..").or_else(|| term.split_once('-')) {
+            let (lower, upper) = (lower.trim().parse::<IpAddr>().ok()?, upper.trim().parse::<IpAddr>().ok()?);
+            return Some(IpTerm::Range(lower.min(upper), lower.max(upper)));
+        }
+        if let Ok(ip) = term.parse() {
+            Some(IpTerm::Exact(ip))
+        } else if term.chars().all(|c| c.is_ascii_hexdigit() || c == '.' || c == ':') {
+            Some(IpTerm::Prefix(term.to_string()))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, ip: IpAddr, target: &str) -> bool {
+        match self {
+            IpTerm::Exact(exact) => ip == *exact,
+            IpTerm::Cidr(network, prefix_len) => cidr_contains(*network, *prefix_len, ip),
+            IpTerm::Range(lower, upper) => (*lower..=*upper).contains(&ip),
+            IpTerm::Prefix(prefix) => target.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+// whether the first `prefix_len` bits of `ip` equal those of `network`.
+// IPv4 addresses are never inside IPv6 networks and the other way around
+fn cidr_contains(network: IpAddr, prefix_len: u32, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        },
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        },
+        _ => false
+    }
+}
+
+impl <T> ColumnFilter<T> for IpAddrColumnFilter<T> {
+    fn id(&self) -> &str { self.id.as_str() }
+    fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::IpAddr((self.mapper)(t)) }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    // exact addresses, CIDR blocks (`10.0.0.0/8`), inclusive ranges (`10.0.0.1..10.0.0.9`
+    // or `10.0.0.1-10.0.0.9`) and partly typed addresses matched as prefixes
+    fn search_pattern(&self, pattern: &String, target: &String) -> bool {
+        let Ok(ip) = target.parse::<IpAddr>() else {
+            return false;
+        };
+        search_terms(pattern, false, |term| IpTerm::parse(term).is_some_and(|term| term.matches(ip, target)))
+    }
+    fn invalid_terms(&self, pattern: &String) -> Vec<String> {
+        split_terms(pattern).into_iter()
+            .filter(|term| IpTerm::parse(term.strip_prefix('!').unwrap_or(term)).is_none())
+            .collect()
+    }
+    fn invalid_terms_label(&self) -> &str { "Invalid address" }
+}
+
+#[macro_export]
+macro_rules! ip_addr_filters {
+    ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
+        $(
+            $table.column_filter(Box::new(
+                IpAddrColumnFilter::new(
+                    $id,
+                    std::rc::Rc::clone(&$table),
+                    Box::new(|$arg| $mapper)
+                )
+            ));
+        )*
+    };
+}
+
+// a column backed by an enum. every variant is listed in the popup, including the ones no row
+// holds yet, which the other filters can't do since they only list values found in the data.
+// values are compared by their position in `variants` and displayed with Display
+pub struct EnumColumnFilter<T, E> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    mapper: Box<dyn Fn(&T) -> E>,
+    variants: Vec<E>
+}
+
+impl <T, E: PartialEq + Display> EnumColumnFilter<T, E> {
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: Box<dyn Fn(&T) -> E>, variants: Vec<E>) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            mapper,
+            variants
+        }
+    }
+
+    fn variant_index(&self, e: &E) -> usize {
+        self.variants.iter().position(|v| v == e).unwrap_or(self.variants.len())
+    }
+}
+
+impl <T, E: PartialEq + Display> ColumnFilter<T> for EnumColumnFilter<T, E> {
+    fn id(&self) -> &str { self.id.as_str() }
+    fn get_value(&self, t: &T) -> ScalarValue { ScalarValue::USize(self.variant_index(&(self.mapper)(t))) }
+    fn get_string_value(&self, t: &T) -> String { (self.mapper)(t).to_string() }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn all_values(&self) -> Vec<(ScalarValue, String)> {
+        self.variants.iter()
+            .enumerate()
+            .map(|(i, v)| (ScalarValue::USize(i), v.to_string()))
+            .collect()
+    }
+}
+
+#[macro_export]
+macro_rules! enum_filters {
+    ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr, $variants:expr) ),* $(,)?) => {
+        $(
+            $table.column_filter(Box::new(
+                EnumColumnFilter::new(
+                    $id,
+                    std::rc::Rc::clone(&$table),
+                    Box::new(|$arg| $mapper),
+                    $variants.into_iter().collect()
+                )
+            ));
+        )*
+    };
+}
+
+// how a multi-valued row is kept against the column's selection
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MultiMatch {
+    // any of the row's values is selected. rows without values are hidden while the column is active
+    #[default]
+    Any,
+    // every one of the row's values is selected. rows without values are always kept
+    All,
+}
+
+// the values of a MultiValueColumnFilter's row
+pub type ValuesMapper<T, V> = Box<dyn Fn(&T) -> Vec<V>>;
+
+// a column holding several values per row, e.g. a flight's connecting airports. the popup
+// lists the union of the values across all rows, and the cell text joins a row's values with ", "
+pub struct MultiValueColumnFilter<T, V> {
+    id: String,
+    column_filter_state: ColumnFilterState<T>,
+    mapper: ValuesMapper<T, V>,
+    multi_match: MultiMatch,
+    // all_values() along with the data revision and length it was collected at
+    all_values_cache: RefCell<Option<CachedValues>>
+}
+
+impl <T, V: Display> MultiValueColumnFilter<T, V> {
+    pub fn new(id: &str, table_filter: Rc<TableFilter<T>>, mapper: ValuesMapper<T, V>, multi_match: MultiMatch) -> Self {
+        Self {
+            id: id.to_string(),
+            column_filter_state: ColumnFilterState::new(&table_filter),
+            mapper,
+            multi_match,
+            all_values_cache: RefCell::new(None)
+        }
+    }
+
+    fn values(&self, t: &T) -> Vec<ScalarValue> {
+        (self.mapper)(t).iter().map(|v| ScalarValue::Str(v.to_string())).collect()
+    }
+
+    // the union of the values across the rows, sorted. collected again once the data changes
+    fn cached_all_values(&self) -> Rc<Vec<(ScalarValue, String)>> {
+        let table_filter = self.column_filter_state().table_filter();
+        let data = table_filter.backing_data();
+        let data = data.borrow();
+        let data_revision = table_filter.data_revision();
+        let mut cache = self.all_values_cache.borrow_mut();
+        if let Some((revision, len, all_values)) = &*cache && *revision == data_revision && *len == data.len() {
+            return Rc::clone(all_values);
+        }
+        let all_values = Rc::new(data.iter()
+            .flat_map(|t| self.values(t))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|v| {
+                let text = v.to_string();
+                (v, text)
+            })
+            .collect::<Vec<_>>());
+        *cache = Some((data_revision, data.len(), Rc::clone(&all_values)));
+        all_values
+    }
+}
+
+impl <T, V: Display> ColumnFilter<T> for MultiValueColumnFilter<T, V> {
+    fn id(&self) -> &str { self.id.as_str() }
+    fn get_value(&self, t: &T) -> ScalarValue {
+        ScalarValue::Str((self.mapper)(t).iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
+    }
+    fn column_filter_state(&self) -> &ColumnFilterState<T> { &self.column_filter_state }
+    fn evaluate(&self, t: &T) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let values = self.values(t);
+        match self.multi_match {
+            MultiMatch::Any => values.iter().any(|v| self.contains(v)),
+            MultiMatch::All => values.iter().all(|v| self.contains(v)),
+        }
+    }
+    fn evaluate_against(&self, t: &T, unselected_values: &FxHashSet<ScalarValue>) -> bool {
+        if unselected_values.is_empty() {
+            return true;
+        }
+        let values = self.values(t);
+        match self.multi_match {
+            MultiMatch::Any => values.iter().any(|v| !unselected_values.contains(v)),
+            MultiMatch::All => values.iter().all(|v| !unselected_values.contains(v)),
+        }
+    }
+    // the rows are listed through all_values() instead, one entry per value
+    fn unique_rows(&self) -> Rc<Vec<usize>> {
+        Rc::new(vec![])
+    }
+    fn row_values(&self, t: &T) -> Vec<ScalarValue> {
+        self.values(t)
+    }
+    fn all_values(&self) -> Vec<(ScalarValue, String)> {
+        self.cached_all_values().to_vec()
+    }
+    fn present_values(&self) -> HashSet<ScalarValue> {
+        self.cached_all_values().iter().map(|(v, _)| v.clone()).collect()
+    }
+    fn distinct_values(&self) -> Vec<ScalarValue> {
+        self.cached_all_values().iter().map(|(v, _)| v.clone()).collect()
+    }
+    fn search_matches(&self, pattern: &String) -> Vec<(ScalarValue, bool)> {
+        self.cached_all_values().iter()
+            .map(|(v, text)| (v.clone(), self.matches_search(pattern, text)))
+            .collect()
+    }
+    fn describe_active(&self) -> String {
+        let all_values = self.cached_all_values();
+        let selected = all_values.iter().filter(|(v, _)| self.contains(v)).count();
+        let mut description = format!("{} of {} values selected", selected, all_values.len());
+        let search_field = self.column_filter_state().search_field();
+        if !search_field.is_empty() {
+            description.push_str(&format!("\nsearch: {}", search_field));
+        }
+        description
+    }
+}
+
+#[macro_export]
+macro_rules! multi_value_filters {
+    ($table:expr, $( ($id:expr, |$arg:ident| $mapper:expr) ),* $(,)?) => {
+        $(
+            $table.column_filter(Box::new(
+                MultiValueColumnFilter::new(
+                    $id,
+                    std::rc::Rc::clone(&$table),
+                    Box::new(|$arg| $mapper),
+                    MultiMatch::Any
+                )
+            ));
+        )*
+    };
+}
+
+// compares runs of digits by their numeric value, so "A9" < "A10" and "2" < "10"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = natural_chunks(a);
+    let mut b_chunks = natural_chunks(b);
+    loop {
+        let ordering = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) if x.starts_with(|c: char| c.is_ascii_digit()) && y.starts_with(|c: char| c.is_ascii_digit()) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            },
+            (Some(x), Some(y)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+// splits a string into alternating runs of digits and non-digits
+fn natural_chunks(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let digit = rest.chars().next()?.is_ascii_digit();
+        let end = rest.find(|c: char| c.is_ascii_digit() != digit).unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+#[macro_export]
+macro_rules! col_with_filter {
+    ($header:expr, $table_filter:expr, $id:expr, |$ui:ident| $body:expr) => {{
+        let (_, resp) = $header.col(|$ui| {
+            $body;
+            $table_filter.active_indicator_for_id($ui, $id);
+            $table_filter.sort_button_for_id($ui, $id);
+        });
+        $table_filter.bind_for_id($id, resp);
     }};
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use super::*;
+
+    // a table filter without rows, for exercising a column's search_pattern() on its own
+    fn unit_table() -> Rc<TableFilter<()>> {
+        TableFilter::new(&Rc::new(RefCell::new(vec![])))
+    }
+
+    fn string_filter() -> StringColumnFilter<()> {
+        StringColumnFilter::new("s", unit_table(), Box::new(|_: &()| String::new()))
+    }
+
+    fn u32_filter() -> U32ColumnFilter<()> {
+        U32ColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()))
+    }
+
+    fn date_filter(pattern: &str) -> NaiveDateColumnFilter<()> {
+        let day = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        NaiveDateColumnFilter::new("d", unit_table(), pattern.to_string(), Box::new(move |_: &()| day))
+    }
+
+    // the targets the column's search_pattern() matches
+    fn search<'a>(cf: &dyn ColumnFilter<()>, pattern: &str, targets: &[&'a str]) -> Vec<&'a str> {
+        targets.iter()
+            .copied()
+            .filter(|target| cf.search_pattern(&pattern.to_string(), &target.to_string()))
+            .collect()
+    }
+
+
+    #[test]
+    fn negated_terms_are_and_ed() {
+        let airports = ["ATL", "ORD", "LAX"];
+        assert_eq!(search(&string_filter(), "!ATL", &airports), ["ORD", "LAX"]);
+        assert_eq!(search(&string_filter(), "!ATL,!ORD", &airports), ["LAX"]);
+        // positives are OR-ed, then every negation must hold
+        assert_eq!(search(&string_filter(), "ORD,LAX,!LAX", &airports), ["ORD"]);
+
+        let miles = ["100", "500", "900", "5000"];
+        assert_eq!(search(&u32_filter(), "!>500", &miles), ["100", "500"]);
+        assert_eq!(search(&u32_filter(), ">100,!500", &miles), ["900"]);
+    }
+
+    #[test]
+    fn lone_negation_is_ignored() {
+        let airports = ["ATL", "ORD"];
+        assert_eq!(search(&string_filter(), "!", &airports), ["ATL", "ORD"]);
+        assert_eq!(search(&string_filter(), "ATL,!", &airports), ["ATL"]);
+        assert_eq!(search(&u32_filter(), "!", &["1", "2"]), ["1", "2"]);
+    }
+
+    #[test]
+    fn exact_terms_match_only_equal_values() {
+        assert_eq!(search(&string_filter(), "=ORD", &["ORD", "ORDX", "XORD"]), ["ORD"]);
+        assert_eq!(search(&string_filter(), "=ATL,=ORD", &["ATL", "ATLX", "ORD"]), ["ATL", "ORD"]);
+
+        let miles = ["5", "50", "500", "5000"];
+        assert_eq!(search(&u32_filter(), "5", &miles), miles);
+        assert_eq!(search(&u32_filter(), "=500", &miles), ["500"]);
+        let u8_filter = U8ColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()));
+        assert_eq!(search(&u8_filter, "=5", &["5", "50"]), ["5"]);
+        let usize_filter = USizeColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()));
+        assert_eq!(search(&usize_filter, "=10", &["1", "10", "100"]), ["10"]);
+        let i32_filter = I32ColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()));
+        assert_eq!(search(&i32_filter, "=50", &["5", "50", "500"]), ["50"]);
+    }
+
+    #[test]
+    fn globs_are_anchored_at_both_ends() {
+        assert_eq!(search(&string_filter(), "L*", &["LAX", "LAS", "ATL", "L"]), ["LAX", "LAS", "L"]);
+        assert_eq!(search(&string_filter(), "?AX", &["LAX", "DAX", "AX", "LAXX"]), ["LAX", "DAX"]);
+        assert_eq!(search(&string_filter(), "*X", &["LAX", "XL"]), ["LAX"]);
+        // stars may match nothing
+        assert_eq!(search(&string_filter(), "*N*", &["DEN", "N", "ORD", "MSN"]), ["DEN", "N", "MSN"]);
+        assert_eq!(search(&string_filter(), "*", &["", "ATL"]), ["", "ATL"]);
+    }
+
+    #[test]
+    fn escaped_glob_characters_are_literal() {
+        assert_eq!(search(&string_filter(), "A\\*", &["A*", "AB", "A"]), ["A*"]);
+        assert_eq!(search(&string_filter(), "\\?*", &["?X", "AX"]), ["?X"]);
+    }
+
+    #[test]
+    fn plain_numeric_terms_are_or_ed_and_comparisons_and_ed() {
+        let i32_filter = I32ColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()));
+        for cf in [&u32_filter() as &dyn ColumnFilter<()>, &i32_filter] {
+            assert_eq!(search(cf, "100,200", &["100", "150", "200", "300"]), ["100", "200"]);
+            assert_eq!(search(cf, ">100,<500", &["50", "100", "250", "500", "900"]), ["250"]);
+            // the range or any plain alternative
+            assert_eq!(search(cf, ">500,250", &["100", "250", "600"]), ["250", "600"]);
+            assert_eq!(search(cf, ">100,250", &["50", "100", "250", "900"]), ["250", "900"]);
+        }
+    }
+
+    #[test]
+    fn ranges_are_inclusive_open_ended_and_swapped_when_reversed() {
+        let miles = ["50", "100", "300", "500", "501"];
+        assert_eq!(search(&u32_filter(), "100..500", &miles), ["100", "300", "500"]);
+        assert_eq!(search(&u32_filter(), "500..100", &miles), ["100", "300", "500"]);
+        assert_eq!(search(&u32_filter(), "100..", &miles), ["100", "300", "500", "501"]);
+        assert_eq!(search(&u32_filter(), "..500", &miles), ["50", "100", "300", "500"]);
+
+        let days = ["12/31/2025", "01/01/2026", "02/15/2026", "03/01/2026", "03/02/2026"];
+        let date_filter = date_filter("%m/%d/%Y");
+        assert_eq!(search(&date_filter, "01/01/2026..03/01/2026", &days), ["01/01/2026", "02/15/2026", "03/01/2026"]);
+        assert_eq!(search(&date_filter, "03/01/2026..01/01/2026", &days), ["01/01/2026", "02/15/2026", "03/01/2026"]);
+        assert_eq!(search(&date_filter, "02/01/2026..", &days), ["02/15/2026", "03/01/2026", "03/02/2026"]);
+        assert_eq!(search(&date_filter, "..01/01/2026", &days), ["12/31/2025", "01/01/2026"]);
+    }
+
+    #[test]
+    fn signed_comparisons_read_negative_operands() {
+        let i32_filter = I32ColumnFilter::new("n", unit_table(), Box::new(|_: &()| 0), Box::new(|_: &()| String::new()));
+        assert_eq!(search(&i32_filter, "<-5", &["-10", "-6", "-5", "0", "5"]), ["-10", "-6"]);
+        assert_eq!(search(&i32_filter, ">=-10", &["-11", "-10", "0"]), ["-10", "0"]);
+        assert_eq!(search(&i32_filter, "-3..3", &["-4", "-3", "0", "3", "4"]), ["-3", "0", "3"]);
+        assert_eq!(search(&i32_filter, "=-3", &["-3", "3", "-30"]), ["-3"]);
+        assert_eq!(search(&u32_filter(), "<5", &["3", "5", "10"]), ["3"]);
+
+        // operands outside an unsigned column's type compare by value rather than matching nothing
+        assert_eq!(search(&u32_filter(), ">-1", &["0", "5"]), ["0", "5"]);
+        assert_eq!(search(&u32_filter(), "<=-1", &["0", "5"]), Vec::<&str>::new());
+        assert_eq!(search(&u32_filter(), "-5..3", &["0", "3", "5"]), ["0", "3"]);
+        assert_eq!(search(&u32_filter(), "<5000000000", &["0", "4000000000"]), ["0", "4000000000"]);
+        assert_eq!(search(&u32_filter(), "in(-1,5)", &["0", "5"]), ["5"]);
+    }
+
+    #[test]
+    fn dates_parse_with_or_without_padding() {
+        let date_filter = date_filter("%-m/%-d/%Y");
+        let days = ["1/1/2026", "1/2/2026", "12/31/2025"];
+        assert_eq!(search(&date_filter, "=1/1/2026", &days), ["1/1/2026"]);
+        assert_eq!(search(&date_filter, "=01/01/2026", &days), ["1/1/2026"]);
+        assert_eq!(search(&date_filter, "01/01/2026..1/2/2026", &days), ["1/1/2026", "1/2/2026"]);
+        assert!(date_filter.invalid_terms(&"=01/01/2026".to_string()).is_empty());
+    }
+
+    #[test]
+    fn invalid_dates_match_nothing() {
+        let date_filter = date_filter("%-m/%-d/%Y");
+        let days = ["1/1/2026", "12/31/2025"];
+        assert!(search(&date_filter, "<13/40/2026", &days).is_empty());
+        assert!(search(&date_filter, "1/1/2026..13/40/2026", &days).is_empty());
+
+        let pattern = "<13/40/2026,>1/1/2026,1/".to_string();
+        assert_eq!(date_filter.invalid_terms(&pattern), ["<13/40/2026"]);
+    }
+
+    #[test]
+    fn padded_and_blank_terms_are_skipped() {
+        let cities = ["ATL", "ORD", "LAX"];
+        assert_eq!(search(&string_filter(), " ATL , ORD ", &cities), ["ATL", "ORD"]);
+        assert_eq!(search(&string_filter(), "ATL,,ORD", &cities), ["ATL", "ORD"]);
+        assert_eq!(search(&string_filter(), "ATL,", &cities), ["ATL"]);
+        // nothing but separators is an empty search, which matches everything
+        assert_eq!(search(&string_filter(), ",", &cities), cities);
+        assert_eq!(search(&string_filter(), " , ", &cities), cities);
+
+        let miles = ["100", "200", "300"];
+        assert_eq!(search(&u32_filter(), " 100 , >250 ,", &miles), ["100", "300"]);
+        assert_eq!(search(&u32_filter(), ",", &miles), miles);
+    }
+
+    #[test]
+    fn match_modes_change_how_plain_terms_match() {
+        let cities = ["LAX", "LAS", "AXE", "SLAX"];
+        let filter = |match_mode| string_filter().with_match_mode(match_mode);
+        assert_eq!(search(&filter(MatchMode::Prefix), "LA", &cities), ["LAX", "LAS"]);
+        assert_eq!(search(&filter(MatchMode::Contains), "AX", &cities), ["LAX", "AXE", "SLAX"]);
+        assert_eq!(search(&filter(MatchMode::Suffix), "AX", &cities), ["LAX", "SLAX"]);
+        assert_eq!(search(&filter(MatchMode::Exact), "LAX", &cities), ["LAX"]);
+
+        // Auto is contains for one term and prefix for several
+        assert_eq!(search(&filter(MatchMode::Auto), "AX", &cities), ["LAX", "AXE", "SLAX"]);
+        assert_eq!(search(&filter(MatchMode::Auto), "AX,SL", &cities), ["AXE", "SLAX"]);
+
+        // highlighting follows the mode
+        assert_eq!(filter(MatchMode::Suffix).match_spans("AX", "SLAX"), [Range { start: 2, end: 4 }]);
+        assert_eq!(filter(MatchMode::Prefix).match_spans("AX", "AXE"), [Range { start: 0, end: 2 }]);
+    }
+
+    #[test]
+    fn multi_field_match_modes_apply_to_each_field() {
+        let flights = ["AA100 / BA2001", "DL300 / AF3003", "UA10 / AA100"];
+        let filter = |match_mode| MultiFieldStringColumnFilter::new("f", unit_table(), vec![]).with_match_mode(match_mode);
+        assert_eq!(search(&filter(MatchMode::Auto), "100", &flights), ["AA100 / BA2001", "UA10 / AA100"]);
+        assert_eq!(search(&filter(MatchMode::Prefix), "AA", &flights), ["AA100 / BA2001", "UA10 / AA100"]);
+        assert!(search(&filter(MatchMode::Prefix), "100", &flights).is_empty());
+        assert_eq!(search(&filter(MatchMode::Suffix), "003", &flights), ["DL300 / AF3003"]);
+        assert_eq!(search(&filter(MatchMode::Exact), "UA10", &flights), ["UA10 / AA100"]);
+        assert!(search(&filter(MatchMode::Exact), "UA", &flights).is_empty());
+
+        // never across the separator, whatever the mode
+        assert!(search(&filter(MatchMode::Contains), "0 / B", &flights).is_empty());
+        assert!(search(&filter(MatchMode::Exact), "AA100 / BA2001", &flights).is_empty());
+    }
+
+    #[test]
+    fn fuzzy_terms_match_as_subsequences_one_by_one() {
+        let cities = ["LAX", "LAS", "SFO", "PHX"];
+        let fuzzy = string_filter().fuzzy(true);
+        assert_eq!(search(&fuzzy, "lx", &cities), ["LAX"]);
+        // each term is matched on its own, after the operators are read
+        assert_eq!(search(&fuzzy, "lx,sfo", &cities), ["LAX", "SFO"]);
+        assert_eq!(search(&fuzzy, "la,!x", &cities), ["LAS"]);
+        assert_eq!(search(&fuzzy, "=LAS", &cities), ["LAS"]);
+        assert_eq!(search(&fuzzy, "L*", &cities), ["LAX", "LAS"]);
+
+        // a target scores by its best term
+        assert_eq!(fuzzy.search_score(&"px,phx".to_string(), &"PHX".to_string()), fuzzy_score("phx", "PHX"));
+        assert_eq!(fuzzy.search_score(&"lx".to_string(), &"PHX".to_string()), None);
+        assert_eq!(string_filter().search_score(&"PH".to_string(), &"PHX".to_string()), Some(0));
+        assert_eq!(fuzzy.match_spans("lx,sf", "LAX"), [0..1, 2..3]);
+
+        // greedy, the first A is taken over the consecutive AX
+        assert_eq!(fuzzy_match("ax", "LAHAX"), Some((-1, vec![1..2, 4..5])));
+    }
+
+    #[test]
+    fn enum_filter_lists_variants_missing_from_the_data() {
+        #[derive(PartialEq)]
+        enum Status { OnTime, Delayed, Cancelled }
+        impl Display for Status {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(match self { Status::OnTime => "On Time", Status::Delayed => "Delayed", Status::Cancelled => "Cancelled" })
+            }
+        }
+        let status = |d: &i32| if *d == 0 { Status::OnTime } else { Status::Delayed };
+        let variants = || vec![Status::OnTime, Status::Delayed, Status::Cancelled];
+        let table_filter = TableFilter::builder(&Rc::new(RefCell::new(vec![0, 1, 0])))
+            .enumeration("status", status, variants())
+            .build();
+
+        let status_filter = EnumColumnFilter::new("status", Rc::clone(&table_filter), Box::new(status), variants());
+        assert_eq!(status_filter.all_values(), [
+            (ScalarValue::USize(0), "On Time".to_string()),
+            (ScalarValue::USize(1), "Delayed".to_string()),
+            (ScalarValue::USize(2), "Cancelled".to_string()),
+        ]);
+
+        // the absent variant is matched by the search like the others
+        table_filter.apply_search_for_id("status", "Can");
+        assert_eq!(table_filter.export_state().columns["status"].unselected_values, [ScalarValue::USize(0), ScalarValue::USize(1)]);
+        assert_eq!(*table_filter.evaluate_array(), [false, false, false]);
+
+        table_filter.set_selected_values_for_id("status", [ScalarValue::USize(1)]);
+        assert_eq!(*table_filter.evaluate_array(), [false, true, false]);
+    }
+
+    #[test]
+    fn char_terms_form_a_set_of_single_characters() {
+        let char_filter = CharColumnFilter::new("c", unit_table(), Box::new(|_: &()| 'A'));
+        let classes = ["A", "B", "C", "É"];
+        assert_eq!(search(&char_filter, "A,C", &classes), ["A", "C"]);
+        assert_eq!(search(&char_filter, "!B", &classes), ["A", "C", "É"]);
+        assert_eq!(search(&char_filter, "É", &classes), ["É"]);
+        // a term longer than one character is rejected rather than cut down to its first
+        assert!(search(&char_filter, "AB", &classes).is_empty());
+        assert_eq!(search(&char_filter, "AB,C", &classes), ["C"]);
+        assert_eq!(char_filter.invalid_terms(&"AB,C,!BC".to_string()), ["AB", "!BC"]);
+    }
+
+    // the values of a u32 column left selected after applying `pattern` from its search box
+    fn u32_search(data: &[u32], affixes: &[&str], pattern: &str) -> Vec<u32> {
+        let table_filter = TableFilter::builder(&Rc::new(RefCell::new(data.to_vec())))
+            .u32("n", |n: &u32| *n)
+            .build();
+        table_filter.number_affixes_for_id("n", affixes);
+        table_filter.apply_search_for_id("n", pattern);
+        let passing = table_filter.evaluate_array();
+        data.iter().zip(passing.iter()).filter(|(_, b)| **b).map(|(n, _)| *n).collect()
+    }
+
+    #[test]
+    fn numeric_search_reads_thousands_separators() {
+        let data = [999, 1000, 1001, 5000];
+        assert_eq!(u32_search(&data, &[], "\">1,000\""), vec![1001, 5000]);
+        assert_eq!(u32_search(&data, &[], "1 000"), vec![1000]);
+        assert_eq!(u32_search(&data, &[], "\"<=1,000\""), vec![999, 1000]);
+        // unquoted, the comma splits the comparison, which is flagged rather than silently matching `000...`
+        assert_eq!(numeric_invalid_terms(">1,000", &[]), [">1,000"]);
+        assert_eq!(numeric_invalid_terms("!<=$1,000,5", &["$".to_string()]), ["!<=$1,000"]);
+        assert!(numeric_invalid_terms(">1000,250", &[]).is_empty());
+        assert!(numeric_invalid_terms(">100, 250", &[]).is_empty());
+        assert!(numeric_invalid_terms("\">1,000\"", &[]).is_empty());
+        // outside quotes a comma always separates terms, so plain values stay OR-ed
+        assert_eq!(u32_search(&[5, 100, 200, 5100], &[], "<5,100"), vec![100]);
+        assert_eq!(u32_search(&[100, 200, 300], &[], "100,200"), vec![100, 200]);
+    }
+
+    #[test]
+    fn numeric_search_strips_affixes() {
+        let data = [400, 500, 600, 1500];
+        assert_eq!(u32_search(&data, &["$"], "$500"), vec![500]);
+        assert_eq!(u32_search(&data, &["$"], ">$500"), vec![600, 1500]);
+        assert_eq!(u32_search(&data, &["$"], "\">$1,000\""), vec![1500]);
+    }
+
+    #[test]
+    fn multi_value_rows_keep_the_mapper_values_whole() {
+        let rows: Rc<RefCell<Vec<Vec<&str>>>> = Rc::new(RefCell::new(vec![vec!["Smith, J", "Doe"], vec!["Doe"]]));
+        let tf = TableFilter::new(&rows);
+        let cf = MultiValueColumnFilter::new("names", Rc::clone(&tf), Box::new(|r: &Vec<&str>| r.clone()), MultiMatch::Any);
+        let names = |values: &[&str]| values.iter().map(|v| ScalarValue::Str(v.to_string())).collect::<Vec<_>>();
+
+        assert_eq!(cf.row_values(&rows.borrow()[0]), names(&["Smith, J", "Doe"]));
+        assert_eq!(cf.selected_values(), names(&["Doe", "Smith, J"]));
+
+        // picks up rows added after the values were first collected
+        rows.borrow_mut().push(vec!["Roe"]);
+        tf.mark_dirty();
+        assert_eq!(cf.selected_values(), names(&["Doe", "Roe", "Smith, J"]));
+    }
+
+    #[test]
+    fn multi_value_rows_match_any_or_all_selected_values() {
+        let connections = vec![vec!["DFW", "ORD"], vec!["ORD"], vec!["DEN", "DFW"], vec![]];
+        let table_filter = TableFilter::builder(&Rc::new(RefCell::new(connections)))
+            .multi_value("any", |c| c.clone())
+            .multi_value_matching("all", |c| c.clone(), MultiMatch::All)
+            .build();
+        let strs = |values: &[&str]| values.iter().map(|v| ScalarValue::Str(v.to_string())).collect::<Vec<_>>();
+
+        // the popup lists the union of the rows' values
+        assert_eq!(table_filter.selected_values_for_id("any"), strs(&["DEN", "DFW", "ORD"]));
+
+        table_filter.set_selected_values_for_id("any", strs(&["ORD"]));
+        assert_eq!(*table_filter.evaluate_array(), vec![true, true, false, false]);
+        table_filter.reset_all();
+
+        table_filter.set_selected_values_for_id("all", strs(&["DFW", "ORD"]));
+        assert_eq!(*table_filter.evaluate_array(), vec![true, true, false, true]);
+
+        // a cell's context menu excludes each of the row's values
+        table_filter.reset_all();
+        let data = table_filter.backing_data();
+        let (values, text) = table_filter.row_values_for_id("any", &data.borrow()[2]).unwrap();
+        assert_eq!(text, "DEN, DFW");
+        table_filter.exclude_values_for_id("any", values);
+        assert_eq!(table_filter.selected_values_for_id("any"), strs(&["ORD"]));
+        assert_eq!(*table_filter.evaluate_array(), vec![true, true, false, false]);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn unicode_matching_ignores_case_and_normal_form() {
+        let cafes = ["cafe\u{301}", "Caf\u{e9} au lait", "cafeteria"];
+        let unicode_filter = string_filter().with_unicode_matching(true);
+        assert_eq!(search(&unicode_filter, "CAF\u{c9}", &cafes), ["cafe\u{301}", "Caf\u{e9} au lait"]);
+        assert_eq!(search(&unicode_filter, "cafe\u{301}", &cafes), ["cafe\u{301}", "Caf\u{e9} au lait"]);
+        assert_eq!(search(&unicode_filter, "STRASSE", &["Stra\u{df}e", "Strand"]), ["Stra\u{df}e"]);
+
+        // byte matching stays the default
+        assert!(search(&string_filter(), "CAF\u{c9}", &cafes).is_empty());
+        assert_eq!(search(&string_filter(), "cafe\u{301}", &cafes), ["cafe\u{301}"]);
+    }
+
+    #[test]
+    fn ip_terms_match_cidr_blocks_ranges_and_exact_addresses() {
+        let ip_filter = IpAddrColumnFilter::new("ip", unit_table(), Box::new(|_: &()| IpAddr::from([127, 0, 0, 1])));
+        let addrs = ["192.168.1.10", "192.168.200.3", "10.0.0.5", "::1", "fe80::1"];
+        assert_eq!(search(&ip_filter, "192.168.0.0/16", &addrs), ["192.168.1.10", "192.168.200.3"]);
+        assert_eq!(search(&ip_filter, "fe80::/10", &addrs), ["fe80::1"]);
+        assert_eq!(search(&ip_filter, "::/0", &addrs), ["::1", "fe80::1"]);
+        assert_eq!(search(&ip_filter, "10.0.0.5", &addrs), ["10.0.0.5"]);
+        assert_eq!(search(&ip_filter, "=10.0.0.5", &addrs), ["10.0.0.5"]);
+        assert_eq!(search(&ip_filter, "10.0.0.9-10.0.0.1", &addrs), ["10.0.0.5"]);
+        assert_eq!(search(&ip_filter, "192.168.", &addrs), ["192.168.1.10", "192.168.200.3"]);
+        assert_eq!(search(&ip_filter, "10.0.0.0/8,::1", &addrs), ["10.0.0.5", "::1"]);
+        assert!(search(&ip_filter, "10.0.0.5", &["not an address"]).is_empty());
+    }
+
+    #[test]
+    fn malformed_ip_terms_match_nothing() {
+        let ip_filter = IpAddrColumnFilter::new("ip", unit_table(), Box::new(|_: &()| IpAddr::from([127, 0, 0, 1])));
+        let addrs = ["192.168.1.10", "10.0.0.5"];
+        assert!(search(&ip_filter, "192.168.0.0/33", &addrs).is_empty());
+        assert!(search(&ip_filter, "host", &addrs).is_empty());
+        assert!(search(&ip_filter, "10.0.0.1..10.0.0.x", &addrs).is_empty());
+
+        let pattern = "192.168.0.0/33,host,10.0.0.5".to_string();
+        assert_eq!(ip_filter.search_error_for(&pattern).unwrap(), "Invalid address: 192.168.0.0/33, host");
+        assert_eq!(ip_filter.search_error_for("10.0.0.0/8,!10.0.0.5"), None);
+    }
+
+    #[test]
+    fn not_equal_and_set_terms_combine_with_ranges() {
+        let data = [100, 200, 300, 500, 5000];
+        assert_eq!(u32_search(&data, &[], "!=500"), vec![100, 200, 300, 5000]);
+        // unlike `!500`, which negates a prefix
+        assert_eq!(u32_search(&data, &[], "!500"), vec![100, 200, 300]);
+        assert_eq!(u32_search(&data, &[], "in(100,200,300)"), vec![100, 200, 300]);
+        assert_eq!(u32_search(&data, &[], "IN(100, 300)"), vec![100, 300]);
+        assert_eq!(u32_search(&data, &[], "!in(100,200)"), vec![300, 500, 5000]);
+
+        // != is AND-ed like any negation, a set is OR-ed like a plain value
+        assert_eq!(u32_search(&data, &[], "100..1000,!=500"), vec![100, 200, 300]);
+        assert_eq!(u32_search(&data, &[], ">1000,in(200,500)"), vec![200, 500, 5000]);
+        assert_eq!(u32_search(&data, &[], "in(200,300),!=300"), vec![200]);
+
+        let i32_filter = I32ColumnFilter::from_mapper("n", unit_table(), Box::new(|_: &()| 0));
+        assert_eq!(search(&i32_filter, "in(-5,5)", &["-5", "0", "5"]), ["-5", "5"]);
+        assert_eq!(search(&i32_filter, "!=-5", &["-5", "0", "5"]), ["0", "5"]);
+
+        assert_eq!(numeric_invalid_terms("in(1,x),in(),!=5,in(1,2)", &[]), ["in(1,x)", "in()"]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::{Rc, Weak};
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
+use crate::table_filter::{ScalarValue, TableFilter};
+
+// how many values matching_values() fetches from unique_values() at a time
+const MATCHING_PAGE_SIZE: usize = 1000;
+
+// supplies the values a column's popup lists, e.g. from a database, instead of the popup
+// scanning the backing data for them. register one with TableFilter::data_source_for_id()
+pub trait FilterDataSource {
+    // how many distinct values the column holds
+    fn unique_value_count(&self, id: &str) -> usize;
+    // the distinct values of the column with their display strings, sorted, `page` indexing into them
+    fn unique_values(&self, id: &str, page: Range<usize>) -> Vec<(ScalarValue, String)>;
+    // the distinct values whose display string passes `predicate`, sorted, e.g. the ones matching
+    // a popup search. pages through unique_values() by default, a database would rather query
+    fn matching_values(&self, id: &str, predicate: &dyn Fn(&str) -> bool) -> Vec<(ScalarValue, String)> {
+        let count = self.unique_value_count(id);
+        (0..count).step_by(MATCHING_PAGE_SIZE)
+            .flat_map(|start| self.unique_values(id, start..(start + MATCHING_PAGE_SIZE).min(count)))
+            .filter(|(_, text)| predicate(text))
+            .collect()
+    }
+}
+
+// a FilterDataSource over the table filter's own backing data, mostly as a reference
+// implementation. held weakly so the table filter can own it
+pub struct InMemoryDataSource<T> {
+    table_filter: Weak<TableFilter<T>>,
+    // each column's sorted unique values along with the data revision and length they were
+    // collected at, so paging doesn't scan the rows again
+    cache: RefCell<FxHashMap<String, CachedValues>>
+}
+
+// sorted unique values along with the data revision and row count they were collected at
+pub(crate) type CachedValues = (u64, usize, Rc<Vec<(ScalarValue, String)>>);
+
+impl <T> InMemoryDataSource<T> {
+    pub fn new(table_filter: Weak<TableFilter<T>>) -> Self {
+        Self { table_filter, cache: RefCell::new(FxHashMap::default()) }
+    }
+
+    fn all_unique_values(&self, id: &str) -> Rc<Vec<(ScalarValue, String)>> {
+        let Some(table_filter) = self.table_filter.upgrade() else {
+            return Rc::new(vec![]);
+        };
+        let data = table_filter.backing_data();
+        let data = data.borrow();
+        let data_revision = table_filter.data_revision();
+        if let Some((revision, len, values)) = self.cache.borrow().get(id)
+            && *revision == data_revision
+            && *len == data.len() {
+            return Rc::clone(values);
+        }
+        let values = Rc::new(data.iter()
+            .filter_map(|t| table_filter.value_for_id(id, t))
+            .unique_by(|(v, _)| v.clone())
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect::<Vec<_>>());
+        self.cache.borrow_mut().insert(id.to_string(), (data_revision, data.len(), Rc::clone(&values)));
+        values
+    }
+}
+
+impl <T> FilterDataSource for InMemoryDataSource<T> {
+    fn unique_value_count(&self, id: &str) -> usize {
+        self.all_unique_values(id).len()
+    }
+    fn unique_values(&self, id: &str, page: Range<usize>) -> Vec<(ScalarValue, String)> {
+        let values = self.all_unique_values(id);
+        let end = page.end.min(values.len());
+        let start = page.start.min(end);
+        values[start..end].to_vec()
+    }
+}
@@ -0,0 +1,100 @@
+use std::rc::Rc;
+use egui::{Align, Layout, Response, Sense, Ui};
+use egui_extras::{Column, TableBuilder};
+use crate::table_filter::TableFilter;
+
+struct FilterableColumn<'a, T> {
+    title: String,
+    id: String,
+    column: Column,
+    cell: CellRenderer<'a, T>
+}
+
+// renders a row's cell in a FilterableTable column
+type CellRenderer<'a, T> = Box<dyn Fn(&mut Ui, &T) + 'a>;
+
+// renders a whole filterable table: headers with filter popups and sort buttons,
+// and a body of the filtered, sorted rows. use TableBuilder with col_with_filter!
+// directly for custom layouts
+pub struct FilterableTable<'a, T> {
+    table_filter: &'a Rc<TableFilter<T>>,
+    columns: Vec<FilterableColumn<'a, T>>,
+    row_height: Option<f32>
+}
+
+impl <'a, T> FilterableTable<'a, T> {
+    pub fn new(table_filter: &'a Rc<TableFilter<T>>) -> Self {
+        Self {
+            table_filter,
+            columns: vec![],
+            row_height: None
+        }
+    }
+
+    // adds a column bound to the column filter with the given id
+    pub fn column(mut self, title: &str, id: &str, column: Column, cell: impl Fn(&mut Ui, &T) + 'a) -> Self {
+        self.columns.push(FilterableColumn {
+            title: title.to_string(),
+            id: id.to_string(),
+            column,
+            cell: Box::new(cell)
+        });
+        self
+    }
+
+    pub fn row_height(mut self, row_height: f32) -> Self {
+        self.row_height = Some(row_height);
+        self
+    }
+
+    // right-click menu narrowing the column to the cell's value or hiding it
+    fn cell_context_menu(&self, response: &Response, id: &str, item: &T) {
+        let Some((values, text)) = self.table_filter.row_values_for_id(id, item) else {
+            return;
+        };
+        response.context_menu(|ui| {
+            if ui.button(format!("Filter to {}", text)).clicked() {
+                self.table_filter.set_selected_values_for_id(id, values.clone());
+                ui.close();
+            }
+            if ui.button(format!("Exclude {}", text)).clicked() {
+                self.table_filter.exclude_values_for_id(id, values.clone());
+                ui.close();
+            }
+        });
+    }
+
+    pub fn show(self, ui: &mut Ui) {
+        let row_height = self.row_height
+            .unwrap_or_else(|| ui.text_style_height(&egui::TextStyle::Body) + 10.0);
+
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .sense(Sense::click_and_drag())
+            .cell_layout(Layout::left_to_right(Align::Center));
+        for column in self.columns.iter() {
+            table = table.column(column.column);
+        }
+
+        table
+            .header(20.0, |mut header| {
+                for column in self.columns.iter() {
+                    let (_, response) = header.col(|ui| {
+                        ui.strong(&column.title);
+                        self.table_filter.active_indicator_for_id(ui, &column.id);
+                        self.table_filter.sort_button_for_id(ui, &column.id);
+                    });
+                    self.table_filter.bind_for_id(&column.id, response);
+                }
+            })
+            .body(|body| {
+                self.table_filter.body_rows(body, row_height, |mut row, item| {
+                    for column in self.columns.iter() {
+                        let (_, response) = row.col(|ui| (column.cell)(ui, item));
+                        self.cell_context_menu(&response, &column.id, item);
+                    }
+                });
+            });
+    }
+}
@@ -2,19 +2,26 @@ use crate::data::generate_random_flights;
 use chrono::NaiveDate;
 use eframe::egui;
 use eframe::App;
-use egui::Sense;
-use egui_extras::{Column, TableBuilder};
+use egui_extras::Column;
 use itertools::Itertools;
 use std::any::Any;
 use std::cell::RefCell;
 use std::error::Error;
 use std::rc::Rc;
-use crate::column_filters::{NaiveDateColumnFilter, StringColumnFilter, U32ColumnFilter, BoolColumnFilter};
-use crate::table_filter::{ColumnFilter, TableFilter};
+use crate::column_filters::natural_cmp;
+use crate::filterable_table::FilterableTable;
+use crate::table_filter::{BlanksPosition, Combine, FilterPopupStyle, LinkedColumnGroup, ScalarValue, SortDirection, TableFilter, ValueOrder};
 
 mod table_filter;
 mod data;
 mod column_filters;
+mod filterable_table;
+mod data_source;
+#[cfg(feature = "polars")]
+mod polars_filters;
+
+// how the DEP DATE column displays dates, and so how its popup lists and parses them
+const DATE_FORMAT: &str = "%-m/%-d/%Y";
 
 #[derive(Clone)]
 pub struct Flight {
@@ -45,159 +52,182 @@ fn main() -> eframe::Result {
 struct TableFilterApp {
     flights: Rc<RefCell<Vec<Flight>>>,
     table_filter: Rc<TableFilter<Flight>>,
+    status: Rc<RefCell<String>>,
+    preset: String,
 }
 
 impl Default for TableFilterApp {
     fn default() -> Self {
         // backing data and table filter objects MUST be in a Rc.
-        let flights = Rc::new(RefCell::new(generate_random_flights(1_000)));
-        let table_filter = TableFilter::new(&flights);
+        // pass a row count to try larger datasets, e.g. `cargo run --release -- 100000`
+        let flight_count = std::env::args().nth(1)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(1_000);
+        let flights = Rc::new(RefCell::new(generate_random_flights(flight_count)));
+        let table_filter = TableFilter::builder(&flights)
+            .string_ref("orig_filter", |x| x.orig.as_str())
+            .string_ref("dest_filter", |x| x.dest.as_str())
+            .fuzzy_string("gate_number_filter", |x| x.gate.borrow().clone().unwrap_or_default())
+            .date("dep_date_filter", |x| x.dep_date, DATE_FORMAT)
+            .u32("mileage_filter", |x| x.mileage)
+            .bool_with_str("cancelled_filter",
+                |x| x.cancelled.borrow().clone(),
+                |x| (if *x.cancelled.borrow() { "Y" } else { "N" }).to_string() // override string
+            )
+            .computed_bool("long_haul_filter", |x| x.mileage > 1500, ("Y", "N"))
+            .value_histogram(true)
+            .build();
+
+        // sort gates like "A9" before "A10"
+        table_filter.sort_comparator_for_id("gate_number_filter", Box::new(|a: &Flight, b: &Flight| {
+            natural_cmp(&a.gate.borrow().clone().unwrap_or_default(), &b.gate.borrow().clone().unwrap_or_default())
+        }));
+
+        // flights without a gate yet are listed last as N/A
+        table_filter.blank_label_for_id("gate_number_filter", "N/A");
+        table_filter.blanks_position_for_id("gate_number_filter", BlanksPosition::Last);
+
+        // wider popup so the formatted dates are not clipped, latest dates first
+        table_filter.popup_width_for_id("dep_date_filter", 200.0);
+        table_filter.value_direction_for_id("dep_date_filter", SortDirection::Descending);
+
+        // min/max sliders for mileage, alongside the text search
+        table_filter.range_slider_for_id("mileage_filter", true);
+
+        // `~1000` finds mileages within 10% of 1000
+        table_filter.search_operator_for_id("mileage_filter", "~", |value, operand| {
+            match (value.parse::<f64>(), operand.parse::<f64>()) {
+                (Ok(value), Ok(target)) => (value - target).abs() <= target.abs() * 0.1,
+                _ => false
+            }
+        });
 
-        // STRING FILTERS
-        string_filters!(
-            table_filter,
-            ("orig_filter", |x| x.orig.clone()),
-            ("dest_filter", |x| x.dest.clone()),
-            ("gate_number_filter", |x| x.gate.borrow().clone().unwrap_or_default()),
-        );
+        // a stand-in for a server-side search, answering DEST searches from another thread
+        let airports = flights.borrow().iter().map(|f| f.dest.clone()).unique().collect::<Vec<_>>();
+        table_filter.search_provider_for_id("dest_filter", move |query| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let airports = airports.clone();
+            let query = query.to_uppercase();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                let _ = sender.send(airports.into_iter().filter(|airport| airport.contains(&query)).collect());
+            });
+            receiver
+        });
 
-        // NAIVE DATE FILTERS
-        naive_date_filters!(
-            table_filter,
-            ("dep_date_filter", |x| x.dep_date, "%m/%d/%Y"),
-        );
+        // amber accents for the popups, suited to a dark theme
+        table_filter.set_popup_style(FilterPopupStyle {
+            selected_color: Some(egui::Color32::from_gray(230)),
+            unselected_color: Some(egui::Color32::from_gray(150)),
+            absent_color: Some(egui::Color32::from_gray(90)),
+            match_color: Some(egui::Color32::BLACK),
+            match_background: Some(egui::Color32::from_rgb(255, 191, 0)),
+            histogram_color: Some(egui::Color32::from_rgba_unmultiplied(255, 191, 0, 70)),
+        });
 
-        // U32 FILTERS
-        u32_filters!(
-            table_filter,
-            ("mileage_filter", |x| x.mileage, |x| x.mileage.to_string()),
-        );
+        // picking an airport in ORIG or DEST keeps the flights from or to it
+        table_filter.link_columns(LinkedColumnGroup::new(&["orig_filter", "dest_filter"], Combine::Or));
 
-        // BOOL FILTERS
-        bool_filters!(
-            table_filter,
-            ("cancelled_filter",
-                |x| x.cancelled.borrow().clone(),
-                |x| (if *x.cancelled.borrow() { "Y" } else { "N" }).to_string() // override string
-            ),
-        );
+        // airports listed under their region, so a whole region can be picked at once
+        table_filter.parent_of_for_id("orig_filter", |v| region_of(&v.to_string()));
+        table_filter.parent_of_for_id("dest_filter", |v| region_of(&v.to_string()));
+
+        // the airports are listed under their regions, so give the lists more room
+        table_filter.popup_max_height_for_id("orig_filter", 450.0);
+        table_filter.popup_max_height_for_id("dest_filter", 450.0);
+
+        // busiest airports first
+        table_filter.value_order_for_id("orig_filter", ValueOrder::Frequency);
+        table_filter.value_order_for_id("dest_filter", ValueOrder::Frequency);
+
+        // presets offered in the dropdown above the table
+        table_filter.save_preset("All flights");
+        table_filter.apply_batch(|batch| {
+            batch.set_selected_values_for_id("cancelled_filter", [ScalarValue::Bool(true)]);
+            batch.apply_search_for_id("mileage_filter", ">1000");
+        });
+        table_filter.save_preset("Cancelled, over 1000 mi");
+        table_filter.reset_all();
+        table_filter.clear_history();
+
+        // keep a row count label up to date whenever a popup changes a filter
+        let status = Rc::new(RefCell::new(String::new()));
+        let weak_filter = Rc::downgrade(&table_filter);
+        let status_label = Rc::clone(&status);
+        table_filter.on_change(move |id| {
+            if let Some(table_filter) = weak_filter.upgrade() {
+                let (total, matching) = table_filter.counts();
+                *status_label.borrow_mut() = format!("Showing {} of {} rows after changing {}", matching, total, id);
+            }
+        });
 
         Self {
             flights,
-            table_filter
+            table_filter,
+            status,
+            preset: "All flights".to_string()
         }
     }
 }
 
+// rough US region of the demo's airports
+fn region_of(airport: &str) -> &'static str {
+    match airport {
+        "LAX" | "SFO" | "SEA" | "LAS" | "PHX" | "SAN" | "SJC" | "SNA" | "TUS" | "ABQ" | "PDX" | "DEN" => "West",
+        "ORD" | "DFW" | "IAH" | "DAL" | "BNA" | "MKE" | "MEM" | "HOU" | "MDW" => "Central",
+        _ => "East",
+    }
+}
+
 impl App for TableFilterApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Flights");
-
-            ui.style_mut().interaction.selectable_labels = false;
-            let text_style = egui::TextStyle::Body;
-            let row_height = ui.text_style_height(&text_style) + 10.0;
-
-            TableBuilder::new(ui)
-                .striped(true)
-                .resizable(true)
-                .sense(Sense::click_and_drag())
-                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                .column(Column::auto())
-                .column(Column::auto())
-                .column(Column::auto())
-                .column(Column::auto())
-                .column(Column::auto())
-                .column(Column::remainder())
-                .header(20.0, |mut header| {
-
-                    // ORIG COLUMN
-                    col_with_filter!(header, self.table_filter, "orig_filter", |ui| {
-                        ui.strong("ORIG");
-                        if self.table_filter.is_active_for_id("orig_filter") {
-                            ui.strong("🌰");
-                        }
-                    });
-
-                    // DEST COLUMN
-                    col_with_filter!(header, self.table_filter, "dest_filter", |ui| {
-                        ui.strong("DEST");
-                        if self.table_filter.is_active_for_id("dest_filter") {
-                            ui.strong("🌰");
-                        }
-                    });
-
-                    // DEP DT COLUMN
-                    col_with_filter!(header, self.table_filter, "dep_date_filter", |ui| {
-                        ui.strong("DEP DATE");
-                        if self.table_filter.is_active_for_id("dep_date_filter") {
-                            ui.strong("🌰");
+            ui.label(self.status.borrow().as_str());
+            egui::ComboBox::from_label("Preset")
+                .selected_text(self.preset.as_str())
+                .show_ui(ui, |ui| {
+                    for name in self.table_filter.preset_names() {
+                        if ui.selectable_label(self.preset == name, name.as_str()).clicked() {
+                            self.table_filter.load_preset(&name);
+                            self.preset = name;
                         }
-                    });
-
-                    // MILEAGE COLUMN
-                    col_with_filter!(header, self.table_filter, "mileage_filter", |ui| {
-                        ui.strong("MILEAGE");
-                        if self.table_filter.is_active_for_id("mileage_filter") {
-                            ui.strong("🌰");
-                        }
-                    });
-
-                    // CANCELLED COLUMN
-                    col_with_filter!(header, self.table_filter, "cancelled_filter", |ui| {
-                        ui.strong("CANCELLED");
-                        if self.table_filter.is_active_for_id("cancelled_filter") {
-                            ui.strong("🌰");
-                        }
-                    });
+                    }
+                });
+            self.table_filter.toolbar(ui, &self.flights.borrow());
+            self.table_filter.summary_ui(ui);
 
-                    // GATE NUMBER COLUMN
-                    col_with_filter!(header, self.table_filter, "gate_number_filter", |ui| {
-                        ui.strong("GATE NUMBER");
-                        if self.table_filter.is_active_for_id("gate_number_filter") {
-                            ui.strong("🌰");
-                        }
-                    });
+            ui.style_mut().interaction.selectable_labels = false;
 
+            FilterableTable::new(&self.table_filter)
+                .column("ORIG", "orig_filter", Column::auto(), |ui, flight| {
+                    ui.label(&flight.orig);
                 })
-                .body(|mut body| {
-
-                    let binding = self.flights.borrow();
-                    let filtered_flights = binding
-                        .iter()
-                        .filter(|flt| self.table_filter.evaluate(&flt))
-                        .collect::<Vec<_>>();
-
-                    let total_rows = filtered_flights.len();
-
-                    // use rows to only render the rows that are in scrolled view
-                    body.rows(row_height, total_rows, |mut row| {
-                        let flight = filtered_flights[row.index()];
-
-                        row.col(|ui| {
-                            ui.label(&flight.orig);
-                        });
-                        row.col(|ui| {
-                            ui.label(&flight.dest);
-                        });
-                        row.col(|ui| {
-                            ui.label(flight.dep_date.format("%-m/%-d/%Y").to_string());
-                        });
-                        row.col(|ui| {
-                            ui.label(flight.mileage.to_string());
-                        });
-                        row.col(|ui| {
-                            ui.checkbox(&mut flight.cancelled.borrow_mut(), "");
-                        });
-                        row.col(|ui| {
-                            let mut option_proxy = flight.gate.borrow().clone().unwrap_or(String::default());
-                            if ui.text_edit_singleline(&mut option_proxy).changed() {
-                                *flight.gate.borrow_mut() = if option_proxy.is_empty() { None } else { Some(option_proxy) };
-                            }
-                        });
-
-                    });
-                });
+                .column("DEST", "dest_filter", Column::auto(), |ui, flight| {
+                    ui.label(&flight.dest);
+                })
+                .column("DEP DATE", "dep_date_filter", Column::auto(), |ui, flight| {
+                    ui.label(flight.dep_date.format(DATE_FORMAT).to_string());
+                })
+                .column("MILEAGE", "mileage_filter", Column::auto(), |ui, flight| {
+                    ui.label(flight.mileage.to_string());
+                })
+                .column("LONG HAUL", "long_haul_filter", Column::auto(), |ui, flight| {
+                    ui.label(if flight.mileage > 1500 { "Y" } else { "N" });
+                })
+                .column("CANCELLED", "cancelled_filter", Column::auto(), |ui, flight| {
+                    if ui.checkbox(&mut flight.cancelled.borrow_mut(), "").changed() {
+                        self.table_filter.mark_dirty();
+                    }
+                })
+                .column("GATE NUMBER", "gate_number_filter", Column::remainder(), |ui, flight| {
+                    let mut option_proxy = flight.gate.borrow().clone().unwrap_or(String::default());
+                    if ui.text_edit_singleline(&mut option_proxy).changed() {
+                        *flight.gate.borrow_mut() = if option_proxy.is_empty() { None } else { Some(option_proxy) };
+                        self.table_filter.mark_dirty();
+                    }
+                })
+                .show(ui);
         });
     }
-}
\ No newline at end of file
+}
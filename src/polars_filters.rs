@@ -0,0 +1,202 @@
+use polars::prelude::*;
+use crate::column_filters::in_operands;
+use crate::table_filter::{split_terms, ColumnFilter, ScalarValue};
+
+// translates a column filter's unselected values into a boolean polars expression over
+// the named column, so a DataFrame can be filtered with `df.lazy().filter(expr)`.
+// date columns are expected to hold polars Date values
+pub fn column_filter_expr<T>(cf: &dyn ColumnFilter<T>, column: &str) -> Expr {
+    cf.column_filter_state().unselected_values().iter()
+        .fold(lit(true), |expr, v| {
+            // a date filter's values are I32 epoch days, which is what a Date column holds as Int32
+            let target = match v {
+                ScalarValue::I32(_) => col(column).cast(DataType::Int32),
+                _ => col(column),
+            };
+            expr.and(target.neq(scalar_lit(v)))
+        })
+}
+
+// translates a search pattern the same way search_terms() evaluates it: negated terms are
+// AND-ed, comparison terms on numeric columns are AND-ed into a range, and the rest are OR-ed.
+// numeric columns support <, <=, >, >=, = and a..b, string columns support = and prefix matching
+pub fn search_expr(pattern: &str, column: &str, numeric: bool) -> Expr {
+    let terms = split_terms(pattern);
+    let (negated, positive): (Vec<&str>, Vec<&str>) = terms.iter()
+        .map(|term| term.as_str())
+        .filter(|term| *term != "!")
+        .partition(|term| term.starts_with('!'));
+    let (comparisons, alternatives): (Vec<&str>, Vec<&str>) = positive.into_iter()
+        .partition(|term| numeric && term.starts_with(['<', '>']));
+
+    let multiple = terms.len() > 1;
+    let term_expr = |term: &str| if numeric {
+        numeric_term_expr(term, column)
+    } else {
+        string_term_expr(term, column, multiple)
+    };
+
+    let range = comparisons.iter().map(|term| term_expr(term)).reduce(Expr::and);
+    let alternative = alternatives.iter().map(|term| term_expr(term)).reduce(Expr::or);
+    let positive = match (range, alternative) {
+        (Some(range), Some(alternative)) => range.or(alternative),
+        (Some(expr), None) | (None, Some(expr)) => expr,
+        (None, None) => lit(true),
+    };
+    negated.iter().fold(positive, |expr, term| expr.and(term_expr(&term[1..]).not()))
+}
+
+fn scalar_lit(v: &ScalarValue) -> Expr {
+    match v {
+        ScalarValue::Str(s) => lit(s.as_str()),
+        ScalarValue::U8(u) => lit(*u as u32),
+        ScalarValue::I8(i) => lit(*i as i32),
+        ScalarValue::U32(u) => lit(*u),
+        ScalarValue::USize(u) => lit(*u as u64),
+        ScalarValue::I32(i) => lit(*i),
+        ScalarValue::Bool(b) => lit(*b),
+        ScalarValue::IpAddr(ip) => lit(ip.to_string()),
+        ScalarValue::Fields(_) => lit(v.to_string()),
+    }
+}
+
+fn numeric_term_expr(term: &str, column: &str) -> Expr {
+    let number = |s: &str| s.parse::<f64>().ok();
+    let compare = |op: &str, cmp: fn(Expr, Expr) -> Expr| match number(&term[op.len()..]) {
+        Some(n) => cmp(col(column), lit(n)),
+        None => lit(false),
+    };
+
+    if let Some(operands) = in_operands(term) {
+        operands.iter()
+            .filter_map(|operand| number(operand))
+            .map(|n| col(column).eq(lit(n)))
+            .reduce(Expr::or)
+            .unwrap_or(lit(false))
+    } else if let Some((lower, upper)) = term.split_once("..") {
+        // inclusive, with reversed bounds swapped like range_term_matches()
+        match (number(lower), number(upper)) {
+            (Some(l), Some(u)) => col(column).gt_eq(lit(l.min(u))).and(col(column).lt_eq(lit(l.max(u)))),
+            (Some(l), None) if upper.is_empty() => col(column).gt_eq(lit(l)),
+            (None, Some(u)) if lower.is_empty() => col(column).lt_eq(lit(u)),
+            _ => lit(false),
+        }
+    } else if term.starts_with("<=") {
+        compare("<=", Expr::lt_eq)
+    } else if term.starts_with(">=") {
+        compare(">=", Expr::gt_eq)
+    } else if term.starts_with("=") {
+        compare("=", Expr::eq)
+    } else if term.starts_with("<") {
+        compare("<", Expr::lt)
+    } else if term.starts_with(">") {
+        compare(">", Expr::gt)
+    } else {
+        col(column).cast(DataType::String).str().starts_with(lit(term))
+    }
+}
+
+fn string_term_expr(term: &str, column: &str, multiple: bool) -> Expr {
+    if let Some(exact) = term.strip_prefix("=") {
+        col(column).eq(lit(exact))
+    } else if multiple {
+        col(column).str().starts_with(lit(term))
+    } else {
+        col(column).str().contains_literal(lit(term))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use chrono::NaiveDate;
+    use crate::column_filters::{NaiveDateColumnFilter, StringColumnFilter, U32ColumnFilter};
+    use crate::table_filter::TableFilter;
+    use super::*;
+
+    struct Row {
+        city: &'static str,
+        miles: u32,
+        day: NaiveDate
+    }
+
+    fn rows() -> Vec<Row> {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        vec![
+            Row { city: "PHX", miles: 100, day: day(1) },
+            Row { city: "LAX", miles: 1500, day: day(2) },
+            Row { city: "LAS", miles: 250, day: day(3) },
+            Row { city: "ATL", miles: 900, day: day(2) },
+            Row { city: "PHL", miles: 1000, day: day(5) },
+        ]
+    }
+
+    fn frame(rows: &[Row]) -> DataFrame {
+        let days = Series::new("day".into(), rows.iter().map(|r| r.day.to_epoch_days()).collect::<Vec<_>>())
+            .cast(&DataType::Date)
+            .unwrap();
+        DataFrame::new(vec![
+            Column::new("city".into(), rows.iter().map(|r| r.city).collect::<Vec<_>>()),
+            Column::new("miles".into(), rows.iter().map(|r| r.miles).collect::<Vec<_>>()),
+            days.into(),
+        ]).unwrap()
+    }
+
+    // whether each row of the frame passes the expression
+    fn kept(df: &DataFrame, expr: Expr) -> Vec<bool> {
+        let df = df.clone().lazy().with_column(expr.alias("kept")).collect().unwrap();
+        df.column("kept").unwrap().bool().unwrap().into_no_null_iter().collect()
+    }
+
+    fn assert_parity(cf: &dyn ColumnFilter<Row>, data: &[Row], df: &DataFrame) {
+        let expected = data.iter().map(|r| cf.evaluate(r)).collect::<Vec<_>>();
+        assert_eq!(kept(df, column_filter_expr(cf, cf.id())), expected, "column {}", cf.id());
+    }
+
+    #[test]
+    fn column_filter_expr_matches_evaluate() {
+        let data = Rc::new(RefCell::new(rows()));
+        let table_filter = TableFilter::new(&data);
+        let df = frame(&data.borrow());
+
+        let city = StringColumnFilter::new("city", Rc::clone(&table_filter), Box::new(|r: &Row| r.city.to_string()));
+        let miles = U32ColumnFilter::new("miles", Rc::clone(&table_filter), Box::new(|r: &Row| r.miles), Box::new(|r: &Row| r.miles.to_string()));
+        let day = NaiveDateColumnFilter::new("day", Rc::clone(&table_filter), "%m/%d/%Y".to_string(), Box::new(|r: &Row| r.day));
+        let data = data.borrow();
+        let columns: [&dyn ColumnFilter<Row>; 3] = [&city, &miles, &day];
+
+        // nothing unselected keeps every row
+        columns.iter().for_each(|cf| assert_parity(*cf, &data, &df));
+
+        city.exclude_value(ScalarValue::Str("LAX".to_string()));
+        city.exclude_value(ScalarValue::Str("ATL".to_string()));
+        miles.exclude_value(ScalarValue::U32(100));
+        day.exclude_value(day.get_value(&data[1]));
+        columns.iter().for_each(|cf| assert_parity(*cf, &data, &df));
+    }
+
+    #[test]
+    fn search_expr_matches_search_pattern() {
+        let data = Rc::new(RefCell::new(rows()));
+        let table_filter = TableFilter::new(&data);
+        let df = frame(&data.borrow());
+        let data = data.borrow();
+
+        let city = StringColumnFilter::new("city", Rc::clone(&table_filter), Box::new(|r: &Row| r.city.to_string()));
+        for pattern in ["PH", "A", "=LAX", "LA,PH", "!LAX", "LA,!LAS"] {
+            let expected = data.iter()
+                .map(|r| city.search_pattern(&pattern.to_string(), &city.get_string_value(r)))
+                .collect::<Vec<_>>();
+            assert_eq!(kept(&df, search_expr(pattern, "city", false)), expected, "pattern {}", pattern);
+        }
+
+        let miles = U32ColumnFilter::new("miles", Rc::clone(&table_filter), Box::new(|r: &Row| r.miles), Box::new(|r: &Row| r.miles.to_string()));
+        for pattern in [">250", ">=250,<1000", "100,250", "250..1000", "1000..250", "..900", "in(100,900)", "!100", ">1,000", "=1000", "1"] {
+            let expected = data.iter()
+                .map(|r| miles.search_pattern(&pattern.to_string(), &miles.get_string_value(r)))
+                .collect::<Vec<_>>();
+            assert_eq!(kept(&df, search_expr(pattern, "miles", true)), expected, "pattern {}", pattern);
+        }
+    }
+}
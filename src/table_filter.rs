@@ -1,293 +1,5554 @@
-use std::cell::{Cell, RefCell};
-use std::collections::{HashSet};
-use std::hash::Hash;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
 use std::iter::zip;
+use std::ops::{Range, RangeInclusive};
 use std::rc::Rc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
 use eframe::emath::RectAlign;
-use egui::{ScrollArea, Id, Popup, PopupCloseBehavior, Response, TextEdit, RichText, Color32, Layout, Align, Key};
+use egui::text::{LayoutJob, TextFormat};
+use egui::{ScrollArea, Id, LayerId, Order, Popup, PopupCloseBehavior, Response, TextEdit, Color32, Layout, Align, Key, Modifiers};
 use itertools::Itertools;
+use chrono::NaiveDate;
+use crate::data_source::FilterDataSource;
+use crate::column_filters::{in_operands, BoolColumnFilter, CharColumnFilter, ComputedBoolColumnFilter, EnumColumnFilter, I32ColumnFilter, IpAddrColumnFilter, MatchMode, MultiFieldStringColumnFilter, MultiMatch, MultiValueColumnFilter, NaiveDateColumnFilter, StrMapper, StringColumnFilter, U32ColumnFilter, U8ColumnFilter, USizeColumnFilter};
+
+// the "Fx" hash used inside rustc, much cheaper than the default SipHash
+// for the short strings and integers held in ScalarValue
+use rustc_hash::{FxHashMap, FxHashSet};
+
+const DEFAULT_POPUP_WIDTH: f32 = 150.0;
+const DEFAULT_POPUP_MAX_HEIGHT: f32 = 300.0;
+const MAX_HISTOGRAM_BAR_WIDTH: f32 = 40.0;
+// how many values are fetched from a column's data source at a time
+const SOURCE_PAGE_SIZE: usize = 200;
+// columns with fewer rows are evaluated serially, where spawning the work costs more than it saves
+#[cfg(feature = "rayon")]
+const PARALLEL_MIN_ROWS: usize = 10_000;
+// seconds the search field must be idle before the value list is filtered again
+const DEFAULT_SEARCH_DEBOUNCE: f64 = 0.15;
+const DEFAULT_HISTORY_DEPTH: usize = 50;
 
 pub struct TableFilter<T> {
     backing_data: Rc<RefCell<Vec<T>>>,
-    column_filters: RefCell<Vec<Box<dyn ColumnFilter<T>>>>
+    column_filters: RefCell<Vec<Box<dyn ColumnFilter<T>>>>,
+    data_revision: Cell<u64>,
+    composite_cache: RefCell<Option<CompositeCache>>,
+    combine: Cell<Combine>,
+    linked_groups: RefCell<Vec<LinkedColumnGroup>>,
+    // column_units(), kept until a column is registered or a group linked
+    units_cache: RefCell<Option<ColumnUnits>>,
+    sort_keys: RefCell<Vec<SortState>>,
+    // draws a frequency bar next to each value in the popups
+    value_histogram: Cell<bool>,
+    active_indicator: RefCell<ActiveIndicator>,
+    popup_style: Cell<FilterPopupStyle>,
+    popup_labels: RefCell<PopupLabels>,
+    // called with a column id after the popup changes that column's selection
+    on_change: RefCell<Option<ChangeCallback>>,
+    // free-text query matched against every column, kept apart from the columns' search fields
+    global_query: RefCell<String>,
+    // the global query's terms, lowercased once when it's set rather than per row
+    global_terms: RefCell<Vec<String>>,
+    global_cache: RefCell<Option<GlobalCache>>,
+    search_locale: Cell<SearchLocale>,
+    // named snapshots of export_state(), see save_preset()
+    presets: RefCell<HashMap<String, TableFilterState>>,
+    history: RefCell<History>
 }
 
-impl <T> TableFilter<T> {
-    pub fn new(backing_data: &Rc<RefCell<Vec<T>>>) -> Rc<Self> {
-        Rc::new(
-            Self {
-                backing_data: Rc::clone(backing_data),
-                column_filters: RefCell::new(vec![])
-            }
-        )
-    }
+// each filtering unit as how its members are combined and their column indices, see column_units()
+type ColumnUnits = Rc<Vec<(Combine, Vec<usize>)>>;
+type ChangeCallback = Box<dyn FnMut(&str)>;
+// the global query, the data revision it was evaluated at and its eval array
+type GlobalCache = (String, u64, Rc<Vec<bool>>);
 
-    pub fn evaluate(&self, item: &T) -> bool {
-        self.column_filters.borrow().iter().all(|cf| cf.evaluate(item))
+// states before each committed change for undo(), and the ones undone for redo()
+struct History {
+    undo: Vec<TableFilterState>,
+    redo: Vec<TableFilterState>,
+    // state after the last recorded change, which the next change pushes onto undo
+    last: Option<TableFilterState>,
+    depth: usize
+}
+
+// the changes allowed inside TableFilter::apply_batch(), recording which columns changed
+pub struct BatchCtx<'a, T> {
+    table_filter: &'a TableFilter<T>,
+    changed_ids: Vec<String>
+}
+
+impl <T> BatchCtx<'_, T> {
+    pub fn set_selected_values_for_id(&mut self, id: &str, values: impl IntoIterator<Item = ScalarValue>) {
+        self.table_filter.with_column_for_id(id, |cf| cf.set_selected_values(values.into_iter().collect()));
+        self.changed(id);
     }
-    pub fn reset(&self) {
-        self.column_filters.borrow().iter().for_each(|cf| cf.reset());
+    pub fn apply_search_for_id(&mut self, id: &str, pattern: &str) {
+        self.table_filter.with_column_for_id(id, |cf| apply_search_pattern(cf, pattern));
+        self.changed(id);
+    }
+    pub fn reset_for_id(&mut self, id: &str) {
+        self.table_filter.with_column_for_id(id, |cf| cf.reset());
+        self.changed(id);
+    }
+    fn changed(&mut self, id: &str) {
+        if !self.changed_ids.iter().any(|changed| changed == id) {
+            self.changed_ids.push(id.to_string());
+        }
     }
+}
 
-    pub fn column_filter(&self, cf: Box<dyn ColumnFilter<T>>) {
-        self.column_filters.borrow_mut().push(cf);
+// last evaluate_array() result along with the eval arrays it was composed from
+struct CompositeCache {
+    combine: Combine,
+    global: Rc<Vec<bool>>,
+    columns: Vec<Rc<Vec<bool>>>,
+    result: Rc<Vec<bool>>
+}
+
+// drawn in the header of a column whose filter is active, see TableFilter::active_indicator_for_id()
+#[derive(Clone, Debug)]
+pub struct ActiveIndicator {
+    pub glyph: String,
+    // None uses the strong text color
+    pub color: Option<Color32>,
+    // hovering lists the values the column hides
+    pub tooltip: bool
+}
+impl Default for ActiveIndicator {
+    fn default() -> Self {
+        // a funnel-like glyph
+        Self { glyph: "⛛".to_string(), color: None, tooltip: true }
     }
+}
 
-    pub fn is_active_for_id(&self, id: &str) -> bool {
-        self.column_filters.borrow().iter()
-            .filter(|cf| *cf.id() == *id)
-            .any(|cf| cf.is_active())
+// colors of the popups' value lists, see TableFilter::set_popup_style(). None keeps the
+// color from the egui theme
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FilterPopupStyle {
+    pub selected_color: Option<Color32>,
+    pub unselected_color: Option<Color32>,
+    // values no row passing the other columns holds
+    pub absent_color: Option<Color32>,
+    // text matching the search
+    pub match_color: Option<Color32>,
+    pub match_background: Option<Color32>,
+    pub histogram_color: Option<Color32>,
+}
+
+impl FilterPopupStyle {
+    fn value_color(&self, ui: &egui::Ui, selected: bool, present: bool) -> Color32 {
+        if !present {
+            self.absent_color.unwrap_or_else(|| ui.visuals().weak_text_color())
+        } else if selected {
+            self.selected_color.unwrap_or_else(|| ui.visuals().text_color())
+        } else {
+            self.unselected_color.unwrap_or_else(|| ui.visuals().text_color())
+        }
     }
-    pub fn bind_for_id(&self, id: &str, response: Response) {
-        self.column_filters.borrow().iter()
-            .find(|cf| *cf.id() == *id)
-            .map(|cf| {
-                cf.bind(response);
-            });
+}
+
+// text of the popups' labels and buttons, see TableFilter::set_popup_labels(). a label can
+// hold an icon glyph instead of or along with the text, e.g. "✔ OK"
+#[derive(Clone, Debug)]
+pub struct PopupLabels {
+    pub search: String,
+    pub searching: String,
+    pub min: String,
+    pub max: String,
+    pub from: String,
+    pub to: String,
+    pub only_selected: String,
+    pub select_all: String,
+    pub apply: String,
+    pub none: String,
+    pub all: String,
+    pub invert: String,
+    pub reset: String,
+}
+impl Default for PopupLabels {
+    fn default() -> Self {
+        Self {
+            search: "Search...".to_string(),
+            searching: "Searching...".to_string(),
+            min: "min".to_string(),
+            max: "max".to_string(),
+            from: "From".to_string(),
+            to: "To".to_string(),
+            only_selected: "Only show selected".to_string(),
+            select_all: "(Select All)".to_string(),
+            apply: "APPLY".to_string(),
+            none: "NONE".to_string(),
+            all: "ALL".to_string(),
+            invert: "INVERT".to_string(),
+            reset: "RESET".to_string(),
+        }
     }
 }
 
-pub struct ColumnFilterState<T> {
-    table_filter: Rc<TableFilter<T>>,
-    unselected_values: RefCell<HashSet<ScalarValue>>,
-    search_field: RefCell<String>,
-    apply_requested: Cell<bool>
+// separators search patterns are typed with, e.g. `;` and `,` for `>1.000,5; <2.000`, and an
+// optional pattern dates may also be typed in on every date column, e.g. `%d.%m.%Y`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SearchLocale {
+    pub term_separator: char,
+    pub decimal_separator: char,
+    pub date_pattern: Option<&'static str>,
 }
-impl <T> ColumnFilterState<T> {
-    pub fn new(table_filter: &Rc<TableFilter<T>>) -> Self {
+
+impl Default for SearchLocale {
+    fn default() -> Self {
+        Self { term_separator: ',', decimal_separator: '.', date_pattern: None }
+    }
+}
+
+// what closing a popup without APPLY, RESET or Escape does, e.g. by clicking outside it.
+// toggled checkboxes always take effect right away
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CloseAction {
+    // applies a pending search as if APPLY was pressed
+    #[default]
+    ApplyOnClose,
+    // restores the selection the popup opened with and clears the search
+    DiscardOnClose,
+    // clicking outside doesn't close the popup, only APPLY, RESET and Escape do
+    KeepOpenUntilApply,
+}
+
+// what ColumnFilter::reset() returns a column's selection to
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ResetMode {
+    // every value selected
+    Empty,
+    // the column's default_unselected() values, which is Empty for a column without any
+    #[default]
+    Default,
+}
+
+// where a popup lists the blank value, i.e. the one displayed as an empty string
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BlanksPosition {
+    #[default]
+    First,
+    Last,
+}
+
+// order of the values listed in a column's popup
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ValueOrder {
+    // sorted by value
+    #[default]
+    Natural,
+    // by how many rows passing the other columns hold each value, most first
+    Frequency,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Combine {
+    #[default]
+    And,
+    Or,
+}
+
+// columns drawing from one value vocabulary, e.g. origin and destination airports. a selection
+// made in any member's popup is copied to the other members, and the members are combined with
+// `combine` into one filter before it is combined with the other columns. see TableFilter::link_columns()
+#[derive(Clone, Debug)]
+pub struct LinkedColumnGroup {
+    ids: Vec<String>,
+    combine: Combine,
+}
+
+impl LinkedColumnGroup {
+    pub fn new(ids: &[&str], combine: Combine) -> Self {
         Self {
-            table_filter: Rc::clone(table_filter),
-            unselected_values: RefCell::new(Default::default()),
-            search_field: RefCell::new("".to_string()),
-            apply_requested: Cell::new(false),
+            ids: ids.iter().map(|id| id.to_string()).collect(),
+            combine
         }
     }
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+    pub fn combine(&self) -> Combine {
+        self.combine
+    }
 }
 
-#[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum ScalarValue {
-    Str(String),
-    U8(u8),
-    I8(i8),
-    U32(u32),
-    USize(usize),
-    I32(i32),
-    Bool(bool),
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortDirection {
+    Ascending,
+    Descending,
 }
-impl std::fmt::Display for ScalarValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ScalarValue::Str(s) => write!(f, "{}", s),
-            ScalarValue::U32(u) => write!(f, "{}", u),
-            ScalarValue::USize(u) => write!(f, "{}", u),
-            ScalarValue::Bool(b) => write!(f, "{}", b),
-            ScalarValue::I32(i) => write!(f, "{}", i),
-            ScalarValue::U8(u) => write!(f, "{}", u),
-            ScalarValue::I8(i) => write!(f, "{}", i),
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SortState {
+    pub id: String,
+    pub direction: SortDirection
+}
+
+// a column value ordered according to its sort direction
+#[derive(PartialEq, Eq)]
+struct SortKey(ScalarValue, SortDirection);
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.1 {
+            SortDirection::Ascending => self.0.cmp(&other.0),
+            SortDirection::Descending => other.0.cmp(&self.0),
         }
     }
 }
 
+impl <T> TableFilter<T> {
+    pub fn new(backing_data: &Rc<RefCell<Vec<T>>>) -> Rc<Self> {
+        Rc::new(
+            Self {
+                backing_data: Rc::clone(backing_data),
+                column_filters: RefCell::new(vec![]),
+                data_revision: Cell::new(0),
+                composite_cache: RefCell::new(None),
+                combine: Cell::new(Combine::And),
+                linked_groups: RefCell::new(vec![]),
+                units_cache: RefCell::new(None),
+                sort_keys: RefCell::new(vec![]),
+                value_histogram: Cell::new(false),
+                on_change: RefCell::new(None),
+                active_indicator: RefCell::new(ActiveIndicator::default()),
+                popup_style: Cell::new(FilterPopupStyle::default()),
+                popup_labels: RefCell::new(PopupLabels::default()),
+                global_query: RefCell::new(String::new()),
+                global_terms: RefCell::new(vec![]),
+                global_cache: RefCell::new(None),
+                search_locale: Cell::new(SearchLocale::default()),
+                presets: RefCell::new(HashMap::new()),
+                history: RefCell::new(History {
+                    undo: vec![],
+                    redo: vec![],
+                    last: None,
+                    depth: DEFAULT_HISTORY_DEPTH
+                })
+            }
+        )
+    }
 
-pub trait ColumnFilter<T> {
-    fn id(&self) -> &str;
-    fn get_value(&self, t: &T) -> ScalarValue;
+    pub fn builder(backing_data: &Rc<RefCell<Vec<T>>>) -> TableFilterBuilder<T> {
+        TableFilterBuilder { table_filter: Self::new(backing_data) }
+    }
 
-    fn column_filter_state(&self) -> &ColumnFilterState<T>;
+    pub fn backing_data(&self) -> Rc<RefCell<Vec<T>>> {
+        Rc::clone(&self.backing_data)
+    }
+    // bumped by mark_dirty(), for column filters caching what they derive from the data
+    pub(crate) fn data_revision(&self) -> u64 {
+        self.data_revision.get()
+    }
+    pub(crate) fn locale(&self) -> SearchLocale {
+        self.search_locale.get()
+    }
 
-    // default implementations
-    fn get_eval_bool_array(&self) -> Vec<bool> {
-        self.column_filter_state().table_filter.backing_data
-            .borrow()
-            .iter()
-            .map(|t| self.evaluate(t))
-            .collect()
+    pub fn evaluate(&self, item: &T) -> bool {
+        let column_filters = self.column_filters.borrow();
+        let units = self.column_units(&column_filters);
+        let unit_active = |members: &[usize]| members.iter().any(|&i| column_filters[i].is_active());
+        let unit_passes = |combine: Combine, members: &[usize]| match combine {
+            Combine::And => members.iter().all(|&i| column_filters[i].evaluate(item)),
+            Combine::Or => {
+                let mut active = members.iter().filter(|&&i| column_filters[i].is_active()).peekable();
+                active.peek().is_none() || active.any(|&i| column_filters[i].evaluate(item))
+            },
+        };
+        let columns = match self.combine.get() {
+            Combine::And => units.iter().all(|(combine, members)| unit_passes(*combine, members)),
+            Combine::Or => {
+                let mut active = units.iter().filter(|(_, members)| unit_active(members)).peekable();
+                active.peek().is_none() || active.any(|(combine, members)| unit_passes(*combine, members))
+            },
+        };
+        columns && self.global_matches(item)
     }
-    fn selectable_value_bool_array(&self) -> Vec<bool> {
-        let evals = self.column_filter_state().table_filter
-            .column_filters.borrow().iter()
-            .filter(|cf| cf.id() != self.id())
-            .map(|cf| cf.get_eval_bool_array())
+    // indices into column_filters in the units they are combined as, each linked group
+    // together with its combinator and every other column on its own
+    fn column_units(&self, column_filters: &[Box<dyn ColumnFilter<T>>]) -> ColumnUnits {
+        let mut units_cache = self.units_cache.borrow_mut();
+        if let Some(units) = &*units_cache {
+            return Rc::clone(units);
+        }
+        let linked_groups = self.linked_groups.borrow();
+        let mut units = linked_groups.iter()
+            .map(|group| (group.combine, vec![]))
             .collect::<Vec<_>>();
+        for (i, cf) in column_filters.iter().enumerate() {
+            match linked_groups.iter().position(|group| group.ids.iter().any(|id| *id == *cf.id())) {
+                Some(g) => units[g].1.push(i),
+                None => units.push((Combine::And, vec![i])),
+            }
+        }
+        units.retain(|(_, members)| !members.is_empty());
+        let units = Rc::new(units);
+        *units_cache = Some(Rc::clone(&units));
+        units
+    }
+    // treats the given columns as one filter, see LinkedColumnGroup. a column belongs to
+    // the first group listing it. the members' current selections are left as they are
+    // until one of them is changed
+    pub fn link_columns(&self, group: LinkedColumnGroup) {
+        self.linked_groups.borrow_mut().push(group);
+        *self.units_cache.borrow_mut() = None;
+        *self.composite_cache.borrow_mut() = None;
+    }
+    // ids of the columns linked with the given one, including itself, or just itself when unlinked
+    fn linked_ids(&self, id: &str) -> Vec<String> {
+        self.linked_groups.borrow().iter()
+            .find(|group| group.ids.iter().any(|linked| *linked == *id))
+            .map(|group| group.ids.clone())
+            .unwrap_or_else(|| vec![id.to_string()])
+    }
+    // copies a linked column's selection to the rest of its group
+    fn sync_linked(&self, id: &str) {
+        let linked_ids = self.linked_ids(id);
+        if linked_ids.len() < 2 {
+            return;
+        }
+        let column_filters = self.column_filters.borrow();
+        let Some(source) = column_filters.iter().find(|cf| *cf.id() == *id) else {
+            return;
+        };
+        let unselected_values = source.column_filter_state().unselected_values.borrow().clone();
+        column_filters.iter()
+            .filter(|cf| *cf.id() != *id && linked_ids.iter().any(|linked| *linked == *cf.id()))
+            .for_each(|cf| *cf.column_filter_state().unselected_values.borrow_mut() = unselected_values.clone());
+    }
+    // keeps only rows where any column's string value matches the query, on top of the column filters.
+    // case-insensitive, with the same comma-separated terms as a string column's search field
+    pub fn global_search(&self, query: &str) {
+        *self.global_query.borrow_mut() = query.to_string();
+        *self.global_terms.borrow_mut() = split_terms(&query.to_lowercase());
+    }
+
+    pub fn global_query(&self) -> String {
+        self.global_query.borrow().clone()
+    }
 
-        assert!(!evals.is_empty());
-        let len = evals[0].len();
-        // Defensive check: ensure all have same length
-        assert!(evals.iter().all(|v| v.len() == len));
+    fn global_matches(&self, item: &T) -> bool {
+        let terms = self.global_terms.borrow();
+        if terms.is_empty() {
+            return true;
+        }
+        let multiple = terms.len() > 1;
+        let column_filters = self.column_filters.borrow();
+        let values = column_filters.iter()
+            .map(|cf| match cf.get_str_ref(item) {
+                Some(value) => std::borrow::Cow::Borrowed(value),
+                None => std::borrow::Cow::Owned(cf.get_string_value(item))
+            })
+            .collect::<Vec<_>>();
 
-        let mut result = vec![true; len]; // Start with all true
-        for eval in evals {
-            for (r, &b) in result.iter_mut().zip(eval.iter()) {
-                *r &= b;
+        search_split_terms(&terms, false, |term| values.iter().any(|value| {
+            if let Some(exact) = term.strip_prefix("=") {
+                lowercase_eq(value, exact)
+            } else if multiple {
+                lowercase_starts_with(value, term)
+            } else {
+                lowercase_contains(value, term)
             }
+        }))
+    }
+    // cached until the query changes, the data length changes or mark_dirty() is called
+    fn global_eval_array(&self) -> Rc<Vec<bool>> {
+        let query = self.global_query.borrow().clone();
+        let data_revision = self.data_revision.get();
+        let data = self.backing_data.borrow();
+
+        let mut global_cache = self.global_cache.borrow_mut();
+        if let Some((cached_query, cached_revision, result)) = &*global_cache
+            && *cached_query == query
+            && *cached_revision == data_revision
+            && result.len() == data.len() {
+            return Rc::clone(result);
         }
+
+        let result = Rc::new(data.iter().map(|item| self.global_matches(item)).collect::<Vec<_>>());
+        *global_cache = Some((query, data_revision, Rc::clone(&result)));
         result
     }
+    // one bool per row of the backing data, composed from each column's cached eval array.
+    // only columns whose selection changed are re-evaluated, and when none did
+    // the previous result is returned as is. with no columns registered every row passes,
+    // and empty data gives an empty array
+    pub fn evaluate_array(&self) -> Rc<Vec<bool>> {
+        let column_filters = self.column_filters.borrow();
+        let columns = column_filters.iter()
+            .map(|cf| cf.get_eval_bool_array())
+            .collect::<Vec<_>>();
+        let global = self.global_eval_array();
 
-    fn reset(&self) {
-        self.column_filter_state().search_field.borrow_mut().clear();
-        self.column_filter_state().unselected_values.borrow_mut().clear();
-    }
+        let combine = self.combine.get();
+        let mut composite_cache = self.composite_cache.borrow_mut();
+        if let Some(cache) = &*composite_cache
+            && cache.combine == combine
+            && Rc::ptr_eq(&cache.global, &global)
+            && cache.columns.len() == columns.len()
+            && zip(&cache.columns, &columns).all(|(a, b)| Rc::ptr_eq(a, b)) {
+            return Rc::clone(&cache.result);
+        }
 
-    fn contains(&self, value: &ScalarValue) -> bool {
-        !self.column_filter_state().unselected_values.borrow().contains(value)
+        let len = self.backing_data.borrow().len();
+        // a linked group is folded into one array first, single columns are used as they are
+        let units = self.column_units(&column_filters).iter()
+            .map(|(unit_combine, members)| {
+                let active = members.iter().any(|&i| column_filters[i].is_active());
+                let eval = match members.as_slice() {
+                    [i] => std::borrow::Cow::Borrowed(columns[*i].as_slice()),
+                    _ => {
+                        let evals = members.iter()
+                            .map(|&i| (column_filters[i].is_active(), columns[i].as_slice()))
+                            .collect::<Vec<_>>();
+                        std::borrow::Cow::Owned(combine_arrays(*unit_combine, len, &evals))
+                    },
+                };
+                (active, eval)
+            })
+            .collect::<Vec<_>>();
+        let units = units.iter()
+            .map(|(active, eval)| (*active, eval.as_ref()))
+            .collect::<Vec<_>>();
+        let mut result = combine_arrays(combine, len, &units);
+        for (r, &b) in result.iter_mut().zip(global.iter()) {
+            *r &= b;
+        }
+        let result = Rc::new(result);
+        *composite_cache = Some(CompositeCache { combine, global, columns, result: Rc::clone(&result) });
+        result
     }
-    fn search_pattern(&self, pattern: &String, target: &String) -> bool {
-        target.starts_with(pattern)
+    // with the `rayon` feature, evaluates the column across threads once the data reaches
+    // 10,000 rows. the mapper has to return the same values as the column's own mapper, but
+    // unlike it must be Send + Sync, and the rows Sync, so the demo's RefCell rows can't use it
+    #[cfg(feature = "rayon")]
+    pub fn parallel_mapper_for_id(&self, id: &str, mapper: impl Fn(&T) -> ScalarValue + Send + Sync + 'static) where T: Sync {
+        use rayon::prelude::*;
+        self.with_column_for_id(id, |cf| {
+            *cf.column_filter_state().parallel_evals.borrow_mut() = Some(Box::new(move |data, unselected_values| {
+                data.par_iter().map(|t| !unselected_values.contains(&mapper(t))).collect()
+            }));
+            cf.column_filter_state().eval_cache.borrow_mut().take();
+        });
     }
-    fn get_string_value(&self, t: &T) -> String {
-        self.get_value(t).to_string()
+    // (total, matching) row counts, e.g. for "Showing 327 of 1000". nothing is evaluated
+    // while no column is active and the global search is empty
+    pub fn counts(&self) -> (usize, usize) {
+        let total = self.backing_data.borrow().len();
+        if self.active_column_ids().is_empty() && self.global_query.borrow().is_empty() {
+            return (total, total);
+        }
+        (total, self.evaluate_array().iter().filter(|b| **b).count())
     }
-    fn evaluate(&self, t: &T) -> bool {
-        let v = self.get_value(t);
-        !self.column_filter_state().unselected_values.borrow().contains(&v)
+    // indices into the backing data of the rows passing every filter, from evaluate_array()
+    pub fn matching_indices(&self) -> Vec<usize> {
+        self.evaluate_array().iter()
+            .enumerate()
+            .filter(|(_, b)| **b)
+            .map(|(i, _)| i)
+            .collect()
     }
-    fn is_active(&self) -> bool {
-        !self.column_filter_state().unselected_values.borrow().is_empty()
+    // the same indices evaluated row by row with evaluate(), without building the bool array.
+    // the backing data must not be borrowed mutably while iterating
+    pub fn matching_iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let len = self.backing_data.borrow().len();
+        (0..len).filter(move |i| self.backing_data.borrow().get(*i).is_some_and(|item| self.evaluate(item)))
     }
-    fn bind(&self, response: Response)  {
-        // add popup
-        Popup::menu(&response).id(Id::new(self.id()))
-            .align(RectAlign::default())
-            .gap(4.0)
-            .close_behavior(PopupCloseBehavior::CloseOnClickOutside)
-            .width(150.0)
-            .show(|ui| {
-                ui.vertical(|ui| {
-
-                    ui.label("Search...");
-
-                    {
-                        let mut search_field = self.column_filter_state().search_field.borrow_mut();
-
-                        let search_input = TextEdit::singleline(&mut *search_field)
-                            .desired_width(ui.available_width());
+    // fills a TableBuilder body with the filtered, sorted rows. only the rows scrolled into view
+    // are laid out, which keeps 100k+ rows smooth, but every row must be `row_height` tall
+    pub fn body_rows(&self, body: egui_extras::TableBody, row_height: f32, mut add_row: impl FnMut(egui_extras::TableRow, &T)) {
+        let data = self.backing_data.borrow();
+        let mut rows = self.matching_indices().into_iter()
+            .map(|i| &data[i])
+            .collect::<Vec<_>>();
+        self.sort_rows(&mut rows);
 
-                        ui.add(search_input);
-                    }
+        body.rows(row_height, rows.len(), |row| {
+            let item = rows[row.index()];
+            add_row(row, item);
+        });
+    }
+    // separators the popups' search fields are read with, `,` between terms and `.` for decimals by default
+    pub fn search_locale(&self, locale: SearchLocale) {
+        self.search_locale.set(locale);
+    }
+    // how column filters are combined, AND by default.
+    // with OR a row passes if it passes any active column filter, or when none are active
+    pub fn combine(&self, combine: Combine) {
+        self.combine.set(combine);
+    }
+    // true when the next evaluate_array() has work to do, false on idle frames
+    pub fn is_dirty(&self) -> bool {
+        let data_len = self.backing_data.borrow().len();
+        let global_stale = !self.global_cache.borrow().as_ref()
+            .is_some_and(|(query, revision, _)| *query == *self.global_query.borrow() && *revision == self.data_revision.get());
+        let combine_stale = !self.composite_cache.borrow().as_ref()
+            .is_some_and(|cache| cache.combine == self.combine.get());
+        combine_stale || global_stale || self.column_filters.borrow().iter().any(|cf| {
+            let state = cf.column_filter_state();
+            !state.eval_cache.borrow().as_ref()
+                .is_some_and(|cache| cache.is_valid(self.data_revision.get(), data_len, &state.unselected_values.borrow()))
+        })
+    }
+    // call after mutating the backing data in place, so cached results get rebuilt
+    pub fn mark_dirty(&self) {
+        self.data_revision.set(self.data_revision.get() + 1);
+    }
+    // ColumnFilter::prune_stale() on every column, returning the ids of the columns that changed.
+    // not run automatically, since editing a cell can remove a value only for a moment and the
+    // selection should survive that. call it after replacing the backing data
+    pub fn prune_stale(&self) -> Vec<String> {
+        self.column_filters.borrow().iter()
+            .filter(|cf| cf.prune_stale())
+            .map(|cf| cf.id().to_string())
+            .collect()
+    }
+    // clears the search field and selection of every column, each back to its ResetMode baseline,
+    // as one undoable step notifying on_change for each column it changed
+    pub fn reset_all(&self) {
+        self.change_all(|| self.reset_columns());
+    }
+    #[deprecated(note = "renamed to reset_all(), reset_for_id() clears a single column")]
+    pub fn reset(&self) {
+        self.reset_all();
+    }
+    fn reset_columns(&self) {
+        self.column_filters.borrow().iter().for_each(|cf| cf.reset());
+    }
 
-                    if ui.input(|input| input.key_pressed(Key::Enter)) {
-                        self.column_filter_state().apply_requested.set(true);
-                    }
+    pub fn reset_for_id(&self, id: &str) {
+        self.change_for_id(id, |cf| cf.reset());
+    }
 
-                    let filter_array = self.selectable_value_bool_array();
+    pub fn column_filter(&self, cf: Box<dyn ColumnFilter<T>>) {
+        self.column_filters.borrow_mut().push(cf);
+        *self.units_cache.borrow_mut() = None;
+    }
 
-                    let visible_unique: HashSet<ScalarValue> = zip(self.column_filter_state().table_filter.backing_data
-                                                                       .borrow()
-                                                                       .iter(), filter_array)
-                        .map(|(d, b)| (self.get_value(&d),b))
-                        .filter(|(d,b)| *b)
-                        .map(|(d,b)| d)
-                        .collect();
+    // rows passing every column filter except the one with the given id, which is what
+    // that column's popup lists as selectable so its own selection doesn't grey out its values
+    pub fn evaluate_array_excluding_id(&self, id: &str) -> Vec<bool> {
+        let global = self.global_eval_array();
+        // linked columns share the selection, so the whole group is left out
+        let linked_ids = self.linked_ids(id);
+        let mut result = match self.combine.get() {
+            Combine::And => {
+                let evals = self.column_filters.borrow().iter()
+                    .filter(|cf| !linked_ids.iter().any(|linked| *linked == *cf.id()))
+                    .map(|cf| cf.get_eval_bool_array())
+                    .collect::<Vec<_>>();
+                and_arrays(global.len(), evals.iter().map(|eval| eval.as_slice()))
+            },
+            // selecting a value brings its rows in regardless of the other columns
+            Combine::Or => vec![true; global.len()],
+        };
+        for (r, &b) in result.iter_mut().zip(global.iter()) {
+            *r &= b;
+        }
+        result
+    }
 
-                    let search_field_empty = self.column_filter_state().search_field.borrow().is_empty();
+    // ids of the columns currently filtering out values, in registration order
+    pub fn active_column_ids(&self) -> Vec<String> {
+        self.column_filters.borrow().iter()
+            .filter(|cf| cf.is_active())
+            .map(|cf| cf.id().to_string())
+            .collect()
+    }
 
-                    let binding = self.column_filter_state().table_filter.backing_data.borrow();
+    pub fn selected_values_for_id(&self, id: &str) -> Vec<ScalarValue> {
+        self.with_column_for_id(id, |cf| cf.selected_values())
+            .unwrap_or_default()
+    }
+    // drives a column's selection from code, e.g. from a linked chart
+    pub fn set_selected_values_for_id(&self, id: &str, values: impl IntoIterator<Item = ScalarValue>) {
+        self.change_for_id(id, |cf| cf.set_selected_values(values.into_iter().collect()));
+    }
 
-                    let listed_data = binding.iter()
-                        .filter(|d|search_field_empty ||
-                            self.search_pattern(&self.column_filter_state().search_field.borrow(), &self.get_string_value(d))
-                        )
-                        .unique_by(|d| self.get_value(d))
-                        .sorted_by_key(|d| self.get_value(d))
-                        .collect::<Vec<_>>();
+    // applies a change to a column as one undoable step, notifying on_change
+    fn change_for_id(&self, id: &str, change: impl FnOnce(&dyn ColumnFilter<T>)) {
+        self.mark_history_baseline();
+        if self.with_column_for_id(id, change).is_some() {
+            self.notify_change(id);
+        }
+    }
+    // runs `f` on a column without notifying, returning None when no column has the id
+    fn with_column_for_id<R>(&self, id: &str, f: impl FnOnce(&dyn ColumnFilter<T>) -> R) -> Option<R> {
+        self.column_filters.borrow().iter()
+            .find(|cf| *cf.id() == *id)
+            .map(|cf| f(cf.as_ref()))
+    }
 
-                    let text_style = egui::TextStyle::Body;
-                    let row_height = ui.text_style_height(&text_style);
-                    // selectable values
-                    ScrollArea::vertical()
-                        .min_scrolled_height(300.0)
-                        .max_height(300.0)
-                        .show_rows(ui, row_height, listed_data.len(), |ui, row_range| {
-
-                            ui.with_layout(
-                                Layout::top_down(Align::Min)          // left align
-                                    .with_cross_justify(true), |ui| {
-
-                                    listed_data[row_range].iter()
-                                        .for_each(|d| {
-                                            let v = self.get_value(d);
-                                            let label = if !visible_unique.contains(&v) {
-                                                RichText::new(&self.get_string_value(d)).weak()
-                                            } else {
-                                                RichText::new(&self.get_string_value(d))
-                                            };
+    // keeps only the rows holding the given value in a column, e.g. from a cell's context menu
+    pub fn filter_to_value_for_id(&self, id: &str, value: ScalarValue) {
+        self.change_for_id(id, |cf| cf.filter_to_value(value));
+    }
+    // hides the rows holding the given value in a column, leaving its other values as they are
+    pub fn exclude_value_for_id(&self, id: &str, value: ScalarValue) {
+        self.exclude_values_for_id(id, [value]);
+    }
+    pub fn exclude_values_for_id(&self, id: &str, values: impl IntoIterator<Item = ScalarValue>) {
+        self.change_for_id(id, |cf| values.into_iter().for_each(|value| cf.exclude_value(value)));
+    }
+    // the value and display string a column's filter sees for a row
+    pub fn value_for_id(&self, id: &str, item: &T) -> Option<(ScalarValue, String)> {
+        self.with_column_for_id(id, |cf| (cf.get_value(item), cf.get_string_value(item)))
+    }
+    // the values a row holds in a column along with its display string, several for
+    // multi-valued columns, e.g. for a cell's context menu
+    pub fn row_values_for_id(&self, id: &str, item: &T) -> Option<(Vec<ScalarValue>, String)> {
+        self.with_column_for_id(id, |cf| (cf.row_values(item), cf.get_string_value(item)))
+    }
 
-                                            let mut checked = !self.column_filter_state().unselected_values.borrow().contains(&v) && (
-                                                self.column_filter_state().search_field.borrow().is_empty() ||
-                                                    self.search_pattern(&self.column_filter_state().search_field.borrow(), &self.get_string_value(d))
-                                            );
+    // applies a search pattern the way the popup's APPLY button does, without a UI frame,
+    // e.g. to open the app on a pre-filtered deep link
+    pub fn apply_search_for_id(&self, id: &str, pattern: &str) {
+        self.change_for_id(id, |cf| apply_search_pattern(cf, pattern));
+    }
 
-                                            if ui.checkbox(&mut checked, label).clicked() {
-                                                if checked {
-                                                    self.column_filter_state().unselected_values.borrow_mut().remove(&v);
-                                                } else {
-                                                    self.column_filter_state().unselected_values.borrow_mut().insert(v);
-                                                }
-                                            }
-                                        });
-                                }
-                            );
-                        });
-                    ui.add_space(20.0);
+    // the message shown under a column's search field when its pattern can't be parsed
+    pub fn search_error_for_id(&self, id: &str) -> Option<String> {
+        self.with_column_for_id(id, |cf| cf.search_error()).flatten()
+    }
 
-                    ui.horizontal(|ui| {
-                        if ui.button("APPLY").clicked() {
-                            self.column_filter_state().apply_requested.set(true);
-                        }
-                        if self.column_filter_state().apply_requested.get() {
-                            if !self.column_filter_state().search_field.borrow().is_empty() {
-                                self.column_filter_state().table_filter.backing_data.borrow()
-                                    .iter()
-                                    .unique_by(|d| self.get_value(d))
-                                    .collect::<Vec<_>>()
-                                    .iter()
-                                    .for_each(|d| {
-                                        let v = self.get_value(&d);
-                                        if self.search_pattern(&self.column_filter_state().search_field.borrow(), &self.get_string_value(&d)) {
-                                            self.column_filter_state().unselected_values.borrow_mut().remove(&v);
-                                        } else {
-                                            self.column_filter_state().unselected_values.borrow_mut().insert(v);
-                                        }
-                                    });
+    // ColumnFilter::as_predicate() for a registered column, applying its selection outside egui,
+    // e.g. `rows.iter().filter(|f| predicate(f))`. the column is looked up and its unselected values
+    // copied once, so the selection is frozen as it is at the call. it holds a shared borrow of the
+    // registered columns, so registering another column while it's alive panics, and passes every
+    // row when no column has the id
+    pub fn predicate_for_id(&self, id: &str) -> impl Fn(&T) -> bool + '_ {
+        let column = Ref::filter_map(self.column_filters.borrow(), |column_filters| column_filters.iter()
+            .find(|cf| *cf.id() == *id)
+            .map(|cf| cf.as_ref()))
+            .ok();
+        let unselected_values = column.as_ref()
+            .map(|cf| cf.column_filter_state().unselected_values.borrow().clone())
+            .unwrap_or_default();
+        move |t| column.as_ref().is_none_or(|cf| cf.evaluate_against(t, &unselected_values))
+    }
 
-                                self.column_filter_state().search_field.borrow_mut().clear();
-                            }
-                            self.column_filter_state().apply_requested.set(false);
-                            ui.close();
-                        }
+    pub fn describe_active_for_id(&self, id: &str) -> Option<String> {
+        self.with_column_for_id(id, |cf| cf.describe_active())
+    }
 
-                        if ui.button("NONE").clicked() {
-                            self.column_filter_state().table_filter.backing_data.borrow()
-                                .iter()
-                                .unique_by(|d| self.get_value(d))
-                                .collect::<Vec<_>>()
-                                .iter()
-                                .for_each(|d| {
-                                    let v = self.get_value(&d);
-                                    self.column_filter_state().unselected_values.borrow_mut().insert(v);
-                                });
-                        }
+    pub fn is_active_for_id(&self, id: &str) -> bool {
+        self.with_column_for_id(id, |cf| cf.is_active()).unwrap_or(false)
+    }
+    // writes the rows passing every filter as RFC 4180 CSV, one column per filter
+    // in registration order with the column ids as the header
+    pub fn export_csv<W: Write>(&self, rows: &[T], mut writer: W) -> io::Result<()> {
+        let column_filters = self.column_filters.borrow();
+        let header = column_filters.iter().map(|cf| csv_field(cf.id())).join(",");
+        write!(writer, "{}\r\n", header)?;
 
+        for row in rows.iter().filter(|row| self.evaluate(row)) {
+            let line = column_filters.iter().map(|cf| csv_field(&cf.get_string_value(row))).join(",");
+            write!(writer, "{}\r\n", line)?;
+        }
+        writer.flush()
+    }
+    // the rows passing every filter as an array of objects keyed by column id,
+    // with numbers and bools kept as JSON numbers and bools
+    #[cfg(feature = "serde")]
+    pub fn export_json(&self, rows: &[T]) -> serde_json::Value {
+        let column_filters = self.column_filters.borrow();
+        rows.iter()
+            .filter(|row| self.evaluate(row))
+            .map(|row| column_filters.iter()
+                .map(|cf| (cf.id().to_string(), cf.json_value(row)))
+                .collect::<serde_json::Map<_, _>>()
+            )
+            .map(serde_json::Value::Object)
+            .collect()
+    }
+    // the rows passing every filter as tab-separated values, for pasting into spreadsheets.
+    // tabs and line breaks inside values are replaced with spaces
+    pub fn to_tsv(&self, rows: &[T]) -> String {
+        let column_filters = self.column_filters.borrow();
+        let tsv_field = |s: &str| s.replace(['\t', '\r', '\n'], " ");
 
-                        if ui.button("ALL").clicked() {
-                            self.column_filter_state().table_filter.backing_data.borrow()
-                                .iter()
-                                .unique_by(|d| self.get_value(d))
-                                .collect::<Vec<_>>()
-                                .iter()
-                                .for_each(|d| {
-                                    let v = self.get_value(&d);
-                                    self.column_filter_state().unselected_values.borrow_mut().remove(&v);
-                                });
-                        }
+        let header = column_filters.iter().map(|cf| tsv_field(cf.id())).join("\t");
+        let lines = rows.iter()
+            .filter(|row| self.evaluate(row))
+            .map(|row| column_filters.iter().map(|cf| tsv_field(&cf.get_string_value(row))).join("\t"));
 
-                        if ui.button("RESET").clicked() {
-                            self.column_filter_state().table_filter.reset();
-                            ui.close();
+        std::iter::once(header).chain(lines).join("\n")
+    }
+    pub fn copy_tsv(&self, ctx: &egui::Context, rows: &[T]) {
+        ctx.copy_text(self.to_tsv(rows));
+    }
+    // optional toolbar with actions over the filtered rows
+    pub fn toolbar(&self, ui: &mut egui::Ui, rows: &[T]) {
+        ui.horizontal(|ui| {
+            let mut query = self.global_query();
+            if ui.add(TextEdit::singleline(&mut query).hint_text("Search all columns")).changed() {
+                self.global_search(&query);
+            }
+            if ui.button("COPY").on_hover_text("Copy the filtered rows as tab-separated values").clicked() {
+                self.copy_tsv(ui.ctx(), rows);
+            }
+            if ui.add_enabled(self.can_undo(), egui::Button::new("UNDO")).clicked() {
+                self.undo();
+            }
+            if ui.add_enabled(self.can_redo(), egui::Button::new("REDO")).clicked() {
+                self.redo();
+            }
+            let active = self.active_column_ids().len();
+            if active > 0 {
+                let label = if active == 1 { "1 filter active".to_string() } else { format!("{} filters active", active) };
+                ui.label(label);
+                if ui.button("CLEAR ALL").clicked() {
+                    self.reset_all();
+                }
+            }
+        });
+    }
+    // a row of chips, one per active column, each with a ✕ that resets just that column,
+    // followed by a CLEAR ALL button. hovering a chip shows describe_active()
+    pub fn summary_ui(&self, ui: &mut egui::Ui) {
+        let active_column_ids = self.active_column_ids();
+        if active_column_ids.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            let mut cleared = None;
+            for id in active_column_ids.iter() {
+                egui::Frame::group(ui.style())
+                    .inner_margin(egui::Margin::symmetric(4, 1))
+                    .show(ui, |ui| {
+                        ui.label(id.as_str())
+                            .on_hover_text(self.describe_active_for_id(id).unwrap_or_default());
+                        if ui.small_button("✕").on_hover_text("Reset this column").clicked() {
+                            cleared = Some(id.as_str());
                         }
                     });
-                });
-            });
+            }
+            if let Some(id) = cleared {
+                self.reset_for_id(id);
+            }
+            if ui.button("CLEAR ALL").clicked() {
+                self.reset_all();
+            }
+        });
+    }
+    // custom ordering for a column, e.g. natural_cmp() so "A2" sorts before "A10"
+    pub fn sort_comparator_for_id(&self, id: &str, comparator: SortComparator<T>) {
+        self.with_column_for_id(id, |cf| {
+            *cf.column_filter_state().sort_comparator.borrow_mut() = Some(comparator);
+        });
+    }
+    // currency or unit symbols a numeric column's values are displayed with, e.g. &["$"] or &[" mi"],
+    // so search terms like `>$500` parse. thousands separators are always ignored
+    pub fn number_affixes_for_id(&self, id: &str, affixes: &[&str]) {
+        self.with_column_for_id(id, |cf| *cf.column_filter_state().number_affixes.borrow_mut() = affixes.iter().map(|a| a.to_string()).collect());
+    }
+    // label the popup lists empty values under, "(blank)" by default
+    pub fn blank_label_for_id(&self, id: &str, label: &str) {
+        self.with_column_for_id(id, |cf| *cf.column_filter_state().blank_label.borrow_mut() = label.to_string());
+    }
+    pub fn blanks_position_for_id(&self, id: &str, blanks_position: BlanksPosition) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().blanks_position.set(blanks_position));
+    }
+    // lists a column's values from the given source instead of scanning the backing data, e.g.
+    // for values held in a database. rows are still evaluated against the backing data
+    pub fn data_source_for_id(&self, id: &str, data_source: Rc<dyn FilterDataSource>) {
+        self.with_column_for_id(id, |cf| *cf.column_filter_state().data_source.borrow_mut() = Some(Rc::clone(&data_source)));
+    }
+    // answers a column's settled searches with the display strings of the matching values,
+    // e.g. from a server. the provider returns a receiver the popup polls every frame, and
+    // until the answer arrives the popup shows the local matches and a spinner. if the sender
+    // is dropped without an answer, the local search is used
+    pub fn search_provider_for_id(&self, id: &str, provider: impl Fn(&str) -> Receiver<Vec<String>> + 'static) {
+        self.with_column_for_id(id, |cf| {
+            *cf.column_filter_state().search_provider.borrow_mut() = Some(Box::new(provider));
+            let mut provider_search = cf.column_filter_state().provider_search.borrow_mut();
+            provider_search.pending = None;
+            provider_search.results = None;
+        });
+    }
+    // shows or hides the search field of a column's popup, leaving the value list and its buttons.
+    // bool columns hide it by default
+    pub fn show_search_for_id(&self, id: &str, show: bool) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().show_search.set(Some(show)));
+    }
+    pub fn close_action_for_id(&self, id: &str, close_action: CloseAction) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().close_action.set(close_action));
+    }
+    // registers a search operator for a column, e.g. `#` for an exact id. terms starting with
+    // the prefix are matched by `operator(value, operand)`, with the value as displayed and the
+    // operand being the rest of the term. registering a prefix again replaces its operator
+    pub fn search_operator_for_id(&self, id: &str, prefix: &str, operator: impl Fn(&str, &str) -> bool + 'static) {
+        self.with_column_for_id(id, |cf| {
+            let mut operators = cf.column_filter_state().operators.borrow_mut();
+            operators.retain(|(p, _)| p != prefix);
+            operators.push((prefix.to_string(), Box::new(operator)));
+            operators.sort_by_key(|(p, _)| Reverse(p.len()));
+        });
+    }
+    pub fn value_order_for_id(&self, id: &str, value_order: ValueOrder) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().value_order.set(value_order));
+    }
+    // Descending reverses the popup's value order, e.g. to list the latest dates first, or with
+    // ValueOrder::Frequency the least common values first. blanks keep their BlanksPosition
+    pub fn value_direction_for_id(&self, id: &str, direction: SortDirection) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().value_direction.set(direction));
+    }
+    // lists a column's popup values as a tree under their parents, e.g. airports under regions.
+    // each parent has a checkbox selecting or unselecting all of its listed children, and shows
+    // as indeterminate while only some are selected. the tree isn't virtualized like the flat list,
+    // so it suits columns with a modest number of values
+    pub fn parent_of_for_id<P: std::fmt::Display>(&self, id: &str, parent_of: impl Fn(&ScalarValue) -> P + 'static) {
+        self.with_column_for_id(id, |cf| {
+            *cf.column_filter_state().parent_of.borrow_mut() = Some(Box::new(move |v| parent_of(v).to_string()));
+        });
+    }
+    // selects or unselects every value of a column under a parent_of_for_id() parent,
+    // as one undoable change
+    pub fn set_parent_selected_for_id(&self, id: &str, parent: &str, selected: bool) {
+        self.change_for_id(id, |cf| {
+            let parent_of = cf.column_filter_state().parent_of.borrow();
+            let Some(parent_of) = &*parent_of else {
+                return;
+            };
+            let mut values = cf.present_values();
+            values.extend(cf.all_values().into_iter().map(|(v, _)| v));
+            let children = values.into_iter()
+                .filter(|v| parent_of(v) == parent)
+                .collect::<Vec<_>>();
+            if selected {
+                cf.select_all(children);
+            } else {
+                cf.select_none(children);
+            }
+        });
+    }
+    // overrides the popup width, e.g. for long text values that would otherwise get clipped.
+    // egui keeps the width a popup first opened with, so set it before the column is bound
+    pub fn popup_width_for_id(&self, id: &str, width: f32) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().popup_width.set(Some(width)));
+    }
+    // overrides how long typing must pause, in seconds, before the popup's value list is re-filtered.
+    // 0.0 filters on every keystroke
+    pub fn search_debounce_for_id(&self, id: &str, seconds: f64) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().search_debounce.set(Some(seconds)));
+    }
+    // the values a column starts with unselected, applied right away. see ColumnFilter::default_unselected()
+    pub fn default_unselected_for_id(&self, id: &str, values: impl IntoIterator<Item = ScalarValue>) {
+        self.with_column_for_id(id, |cf| {
+            cf.column_filter_state().set_default_unselected(values);
+        });
+    }
+    // whether resetting a column, from its popup's RESET, a summary chip or CLEAR ALL, returns it
+    // to its default_unselected_for_id() values (ResetMode::Default) or selects every value
+    pub fn reset_mode_for_id(&self, id: &str, reset_mode: ResetMode) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().reset_mode.set(reset_mode));
+    }
+    // shows min/max sliders above the value list of a numeric column. they read and write
+    // an `a..b` range in the search field, so the slider and the text stay in sync
+    pub fn range_slider_for_id(&self, id: &str, enabled: bool) {
+        self.with_column_for_id(id, |cf| if cf.supports_range_ui() {
+            cf.column_filter_state().range_slider.set(enabled);
+        });
+    }
+    // registers a callback invoked with the column id once per change committed in a popup
+    // (APPLY, NONE, ALL, INVERT, RESET, Escape or a toggled value), after the state has changed.
+    // changes made from code, e.g. reset_all(), undo() or load_preset(), notify each column they change.
+    // it must not mutate the backing data, which the popup is still borrowing
+    pub fn on_change(&self, callback: impl FnMut(&str) + 'static) {
+        *self.on_change.borrow_mut() = Some(Box::new(callback));
+    }
+
+    // runs several selection or search changes from code, e.g. when restoring saved state,
+    // and then notifies on_change once per changed column. the column caches are rebuilt
+    // lazily on the next evaluate, so the whole batch costs a single recompute.
+    // bind() must not be called inside the batch
+    pub fn apply_batch(&self, f: impl FnOnce(&mut BatchCtx<T>)) {
+        self.mark_history_baseline();
+        let mut ctx = BatchCtx { table_filter: self, changed_ids: vec![] };
+        f(&mut ctx);
+        let changed_ids = ctx.changed_ids;
+        if !changed_ids.is_empty() {
+            *self.composite_cache.borrow_mut() = None;
+        }
+        changed_ids.iter().for_each(|id| self.notify_change(id));
+    }
+
+    // how many changes undo() can step back through, 50 by default
+    pub fn history_depth(&self, depth: usize) {
+        let mut history = self.history.borrow_mut();
+        history.depth = depth;
+        let excess = history.undo.len().saturating_sub(depth);
+        history.undo.drain(..excess);
+    }
+    // forgets every recorded change, e.g. after setting up the initial filters from code
+    pub fn clear_history(&self) {
+        let mut history = self.history.borrow_mut();
+        history.undo.clear();
+        history.redo.clear();
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.history.borrow().undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.history.borrow().redo.is_empty()
+    }
+    // restores the state before the last change committed in a popup or batch,
+    // returning false when there is nothing to undo
+    pub fn undo(&self) -> bool {
+        let previous = self.history.borrow_mut().undo.pop();
+        match previous {
+            Some(previous) => {
+                let current = self.export_state();
+                self.history.borrow_mut().redo.push(current);
+                self.restore_history(previous);
+                true
+            },
+            None => false
+        }
+    }
+    // reapplies the last undone change, returning false when there is nothing to redo
+    pub fn redo(&self) -> bool {
+        let next = self.history.borrow_mut().redo.pop();
+        match next {
+            Some(next) => {
+                let current = self.export_state();
+                self.history.borrow_mut().undo.push(current);
+                self.restore_history(next);
+                true
+            },
+            None => false
+        }
+    }
+    // notifies on_change for each column the restored state changes, without recording a new step
+    fn restore_history(&self, state: TableFilterState) {
+        let before = self.export_state();
+        self.reset_columns();
+        self.restore_state(&state);
+        self.history.borrow_mut().last = Some(state);
+        self.notify_changed_since(&before);
+    }
+    // the state a following change is undone back to, taken when a popup opens or a batch
+    // starts so changes made from code in between aren't lost
+    fn mark_history_baseline(&self) {
+        let state = self.export_state();
+        self.history.borrow_mut().last = Some(state);
+    }
+    fn record_history(&self) {
+        let current = self.export_state();
+        let mut history = self.history.borrow_mut();
+        if let Some(previous) = history.last.replace(current.clone()) && previous != current {
+            history.undo.push(previous);
+            let excess = history.undo.len().saturating_sub(history.depth);
+            history.undo.drain(..excess);
+            history.redo.clear();
+        }
+    }
+
+    // makes several columns' changes as one undoable step, then notifies on_change
+    // once per column whose search field or selection actually changed
+    fn change_all(&self, change: impl FnOnce()) {
+        let before = self.export_state();
+        self.history.borrow_mut().last = Some(before.clone());
+        change();
+        self.notify_changed_since(&before);
+    }
+    fn notify_changed_since(&self, before: &TableFilterState) {
+        let after = self.export_state();
+        let changed_ids = self.column_filters.borrow().iter()
+            .map(|cf| cf.id().to_string())
+            .filter(|id| before.columns.get(id) != after.columns.get(id))
+            .collect::<Vec<_>>();
+        changed_ids.iter().for_each(|id| self.notify_change(id));
+    }
+    fn notify_change(&self, id: &str) {
+        self.sync_linked(id);
+        self.record_history();
+        // taken out while it runs, so the callback may call back into the table filter
+        let callback = self.on_change.borrow_mut().take();
+        if let Some(mut callback) = callback {
+            callback(id);
+            self.on_change.borrow_mut().get_or_insert(callback);
+        }
+    }
+    pub fn set_active_indicator(&self, indicator: ActiveIndicator) {
+        *self.active_indicator.borrow_mut() = indicator;
+    }
+    pub fn set_popup_style(&self, style: FilterPopupStyle) {
+        self.popup_style.set(style);
+    }
+    // relabels the popups, e.g. to translate them. Enter still triggers the apply button
+    pub fn set_popup_labels(&self, labels: PopupLabels) {
+        *self.popup_labels.borrow_mut() = labels;
+    }
+    // draws the active indicator when the column is filtering, which col_with_filter! and
+    // FilterableTable do after each header
+    pub fn active_indicator_for_id(&self, ui: &mut egui::Ui, id: &str) {
+        let column_filters = self.column_filters.borrow();
+        let Some(cf) = column_filters.iter().find(|cf| *cf.id() == *id && cf.is_active()) else {
+            return;
+        };
+        let indicator = self.active_indicator.borrow();
+        let color = indicator.color.unwrap_or_else(|| ui.visuals().strong_text_color());
+        let response = ui.label(egui::RichText::new(&indicator.glyph).color(color));
+
+        if indicator.tooltip {
+            response.on_hover_text(indicator_tooltip(cf.as_ref()));
+        }
+    }
+    // draws a bar next to each value in the popups, sized by how many rows
+    // passing the other columns hold that value
+    pub fn value_histogram(&self, enabled: bool) {
+        self.value_histogram.set(enabled);
+    }
+    // overrides the max height of the popup's value list
+    pub fn popup_max_height_for_id(&self, id: &str, max_height: f32) {
+        self.with_column_for_id(id, |cf| cf.column_filter_state().popup_max_height.set(Some(max_height)));
+    }
+    // snapshot of every column's search field and unselected values, keyed by column id
+    pub fn export_state(&self) -> TableFilterState {
+        TableFilterState {
+            columns: self.column_filters.borrow().iter()
+                .map(|cf| {
+                    let state = cf.column_filter_state();
+                    (cf.id().to_string(), ColumnState {
+                        search_field: state.search_field.borrow().clone(),
+                        unselected_values: state.unselected_values.borrow().iter().cloned().sorted().collect()
+                    })
+                })
+                .collect()
+        }
+    }
+    // reattaches a previously exported state by column id, ignoring ids no column has.
+    // notifies on_change for each column it changes, as one undoable step
+    pub fn import_state(&self, table_filter_state: &TableFilterState) {
+        self.change_all(|| self.restore_state(table_filter_state));
+    }
+    fn restore_state(&self, table_filter_state: &TableFilterState) {
+        self.column_filters.borrow().iter().for_each(|cf| {
+            if let Some(column_state) = table_filter_state.columns.get(cf.id()) {
+                let state = cf.column_filter_state();
+                *state.search_field.borrow_mut() = column_state.search_field.clone();
+                state.set_unselected_values(column_state.unselected_values.iter().cloned());
+            }
+        });
+    }
+    // stores every column's current state under a name, replacing any preset of the same name
+    pub fn save_preset(&self, name: &str) {
+        let state = self.export_state();
+        self.presets.borrow_mut().insert(name.to_string(), state);
+    }
+    // restores a saved preset, returning false if there is none with that name
+    pub fn load_preset(&self, name: &str) -> bool {
+        let state = self.presets.borrow().get(name).cloned();
+        match state {
+            Some(state) => {
+                self.change_all(|| {
+                    self.reset_columns();
+                    self.restore_state(&state);
+                });
+                true
+            },
+            None => false
+        }
+    }
+    pub fn remove_preset(&self, name: &str) {
+        self.presets.borrow_mut().remove(name);
+    }
+    // names of the saved presets, sorted
+    pub fn preset_names(&self) -> Vec<String> {
+        self.presets.borrow().keys().cloned().sorted().collect()
+    }
+    // compact form for sharing a filtered view, e.g. in a URL:
+    // `orig_filter=ATL,ORD&dest_filter.search=S` lists each column's unselected values
+    // and search field. delimiters inside ids and values are percent-encoded, along with
+    // the dots of ids, so an id ending in `.search` isn't taken for a search key
+    pub fn to_query_string(&self) -> String {
+        self.column_filters.borrow().iter()
+            .flat_map(|cf| {
+                let state = cf.column_filter_state();
+                // dots too, which separate an id from `.search`
+                let id = percent_encode(cf.id()).replace('.', "%2E");
+                let unselected_values = state.unselected_values.borrow();
+                let search_field = state.search_field.borrow();
+
+                let unselected = (!unselected_values.is_empty()).then(|| format!("{}={}", id,
+                    unselected_values.iter().sorted().map(|v| percent_encode(&v.to_string())).join(",")
+                ));
+                let search = (!search_field.is_empty()).then(|| format!("{}.search={}", id, percent_encode(&search_field)));
+                unselected.into_iter().chain(search)
+            })
+            .join("&")
+    }
+    // restores a view from to_query_string(). columns missing from the query are cleared,
+    // and ids no column has are ignored. notifies on_change like import_state()
+    pub fn apply_query_string(&self, query: &str) {
+        self.change_all(|| self.restore_query_string(query));
+    }
+    fn restore_query_string(&self, query: &str) {
+        // (column id, whether it's the search field, value still encoded)
+        let params = query.split("&")
+            .filter_map(|param| param.split_once("="))
+            .map(|(key, value)| match key.strip_suffix(".search") {
+                Some(id) => (percent_decode(id), true, value),
+                None => (percent_decode(key), false, value)
+            })
+            .collect::<Vec<_>>();
+
+        self.column_filters.borrow().iter().for_each(|cf| {
+            let state = cf.column_filter_state();
+            let param = |search: bool| params.iter()
+                .find(|(id, is_search, _)| *id == cf.id() && *is_search == search)
+                .map(|(_, _, value)| *value);
+            let unselected = param(false)
+                .map(|value| value.split(",").map(percent_decode).collect::<HashSet<_>>())
+                .unwrap_or_default();
+
+            *state.search_field.borrow_mut() = param(true).map(percent_decode).unwrap_or_default();
+            // values are matched back up by their display string
+            *state.unselected_values.borrow_mut() = cf.distinct_values().into_iter()
+                .filter(|v| unselected.contains(&v.to_string()))
+                .collect();
+        });
+    }
+    // sort keys in priority order, the first one being the primary sort
+    pub fn sort_keys(&self) -> Vec<SortState> {
+        self.sort_keys.borrow().clone()
+    }
+    // cycles a column's sort none -> ascending -> descending -> none.
+    // with `additive`, the column is added to or cycled within the existing keys,
+    // otherwise it becomes the only sort key
+    pub fn cycle_sort_for_id(&self, id: &str, additive: bool) {
+        let mut sort_keys = self.sort_keys.borrow_mut();
+        let position = sort_keys.iter().position(|key| key.id == id);
+        let direction = match position.map(|i| sort_keys[i].direction) {
+            None => Some(SortDirection::Ascending),
+            Some(SortDirection::Ascending) => Some(SortDirection::Descending),
+            Some(SortDirection::Descending) => None,
+        };
+
+        if !additive {
+            sort_keys.retain(|key| key.id == id);
+        }
+        match (sort_keys.iter().position(|key| key.id == id), direction) {
+            (Some(i), Some(direction)) => sort_keys[i].direction = direction,
+            (Some(i), None) => { sort_keys.remove(i); },
+            (None, Some(direction)) => sort_keys.push(SortState { id: id.to_string(), direction }),
+            (None, None) => {},
+        }
+    }
+    // orders rows of the backing data by a column's get_value()
+    pub fn sort_by_id(&self, id: &str, direction: SortDirection, rows: &mut [&T]) {
+        self.sort_by_keys(&[SortState { id: id.to_string(), direction }], rows);
+    }
+    // stable multi-key sort, later keys only break ties left by earlier ones
+    pub fn sort_by_keys(&self, sort_keys: &[SortState], rows: &mut [&T]) {
+        let column_filters = self.column_filters.borrow();
+        let keys = sort_keys.iter()
+            .filter_map(|key| column_filters.iter()
+                .find(|cf| *cf.id() == *key.id)
+                .map(|cf| (cf, key.direction))
+            )
+            .collect::<Vec<_>>();
+
+        if keys.iter().any(|(cf, _)| cf.column_filter_state().sort_comparator.borrow().is_some()) {
+            // custom comparators can't be cached as keys, so compare pairwise
+            rows.sort_by(|a, b| keys.iter()
+                .map(|(cf, direction)| match direction {
+                    SortDirection::Ascending => cf.compare(a, b),
+                    SortDirection::Descending => cf.compare(b, a),
+                })
+                .fold(Ordering::Equal, Ordering::then)
+            );
+        } else if !keys.is_empty() {
+            rows.sort_by_cached_key(|r| keys.iter()
+                .map(|(cf, direction)| SortKey(cf.get_value(r), *direction))
+                .collect::<Vec<_>>()
+            );
+        }
+    }
+    // applies the sort chosen with the header sort buttons, if any
+    pub fn sort_rows(&self, rows: &mut [&T]) {
+        self.sort_by_keys(&self.sort_keys.borrow(), rows);
+    }
+    // shift-click adds the column as a secondary sort key
+    pub fn sort_button_for_id(&self, ui: &mut egui::Ui, id: &str) {
+        let sort_keys = self.sort_keys();
+        let position = sort_keys.iter().position(|key| key.id == id);
+        let icon = match position.map(|i| sort_keys[i].direction) {
+            Some(SortDirection::Ascending) => "⬆",
+            Some(SortDirection::Descending) => "⬇",
+            None => "⬍",
+        };
+        // show the priority once there is more than one key
+        let label = match position {
+            Some(i) if sort_keys.len() > 1 => format!("{}{}", icon, i + 1),
+            _ => icon.to_string(),
+        };
+        if ui.small_button(label).clicked() {
+            self.cycle_sort_for_id(id, ui.input(|input| input.modifiers.shift));
+        }
+    }
+    pub fn bind_for_id(&self, id: &str, response: Response) {
+        self.with_column_for_id(id, |cf| cf.bind(response));
+    }
+}
+
+// the active criteria followed by the first ten values the column hides
+fn indicator_tooltip<T>(cf: &dyn ColumnFilter<T>) -> String {
+    let hidden = cf.column_filter_state().unselected_values();
+    let listed = hidden.iter().take(10).join(", ");
+    let more = hidden.len().saturating_sub(10);
+    let hiding = if more > 0 {
+        format!("Hiding {} (+{} more)", listed, more)
+    } else {
+        format!("Hiding {}", listed)
+    };
+    format!("{}\n{}", cf.describe_active(), hiding)
+}
+
+// rows passing the other columns per value, for the popup's counts and histogram bars
+fn visible_counts<T, C: ColumnFilter<T> + ?Sized>(cf: &C, filter_array: &[bool]) -> FxHashMap<ScalarValue, usize> {
+    let mut counts = FxHashMap::default();
+    zip(cf.column_filter_state().table_filter.backing_data.borrow().iter(), filter_array)
+        .filter(|(_, b)| **b)
+        .for_each(|(d, _)| *counts.entry(cf.get_value(d)).or_default() += 1);
+    counts
+}
+
+// rows kept by the other columns and the column's tentative selection and search, for the popup's
+// footer. summed per kept value from visible_counts(), so only columns listing their values apart
+// from the rows, e.g. multi-valued ones, go through the rows one by one
+fn kept_row_count<T, C: ColumnFilter<T> + ?Sized>(cf: &C, filter_array: &[bool], visible_counts: &FxHashMap<ScalarValue, usize>, search: &String) -> usize {
+    let data = cf.column_filter_state().table_filter.backing_data.borrow();
+    let kept = |d: &T| cf.evaluate(d) && (search.is_empty() || cf.matches_search(search, &cf.get_string_value(d)));
+    let unique_rows = cf.unique_rows();
+    if unique_rows.is_empty() {
+        return zip(data.iter(), filter_array).filter(|(d, b)| **b && kept(d)).count();
+    }
+    unique_rows.iter()
+        .map(|i| &data[*i])
+        .filter(|d| kept(d))
+        .map(|d| cf.get_value(d))
+        .collect::<FxHashSet<_>>()
+        .iter()
+        .filter_map(|v| visible_counts.get(v))
+        .sum()
+}
+
+// kept_row_count(), counted again only once the data, selection, search or visible counts change
+fn cached_kept_row_count<T, C: ColumnFilter<T> + ?Sized>(cf: &C, filter_array: &[bool], visible_counts: &FxHashMap<ScalarValue, usize>, search: &String) -> usize {
+    let state = cf.column_filter_state();
+    let data_revision = state.table_filter.data_revision.get();
+    let data_len = state.table_filter.backing_data.borrow().len();
+    if let Some(cache) = &*state.kept_cache.borrow()
+        && cache.data_revision == data_revision
+        && cache.data_len == data_len
+        && cache.search_field == *search
+        && cache.unselected_values == *state.unselected_values.borrow()
+        && cache.visible_counts == *visible_counts {
+        return cache.count;
+    }
+    let count = kept_row_count(cf, filter_array, visible_counts, search);
+    *state.kept_cache.borrow_mut() = Some(KeptCache {
+        data_revision,
+        data_len,
+        search_field: search.clone(),
+        unselected_values: state.unselected_values.borrow().clone(),
+        visible_counts: visible_counts.clone(),
+        count
+    });
+    count
+}
+
+// seconds left at `now` before a search last edited at `last_edit_time` settles and the value
+// list is filtered by it, None once it has. Enter and APPLY settle it straight away
+fn debounce_wait(now: f64, last_edit_time: f64, debounce: f64, apply_requested: bool) -> Option<f64> {
+    let idle = now - last_edit_time;
+    (idle < debounce && !apply_requested).then_some(debounce - idle)
+}
+
+// each unique row matching the search, sorted by value or, for a fuzzy search, best match first.
+// the rows the popup lists before arrange_listed()
+fn listed_rows<T, C: ColumnFilter<T> + ?Sized>(cf: &C, search: &String) -> Vec<usize> {
+    let data = cf.column_filter_state().table_filter.backing_data.borrow();
+    let rows = cf.unique_rows().iter()
+        .copied()
+        .filter(|i| search.is_empty() || cf.matches_search(search, &cf.get_string_value(&data[*i])))
+        .collect::<Vec<_>>();
+    if !search.is_empty() && cf.is_fuzzy() {
+        return rows.into_iter()
+            .sorted_by_key(|i| Reverse(cf.search_score(search, &cf.get_string_value(&data[*i]))))
+            .collect();
+    }
+    rows
+}
+
+// listed_rows() for the search field along with the search they were listed for, kept until the
+// data changes or, once it has `settled`, the search does
+fn settled_listed_rows<T, C: ColumnFilter<T> + ?Sized>(cf: &C, settled: bool) -> (Vec<usize>, String) {
+    let state = cf.column_filter_state();
+    let search_field = state.search_field.borrow();
+    let data_revision = state.table_filter.data_revision.get();
+    let data_len = state.table_filter.backing_data.borrow().len();
+    let mut listed_cache = state.listed_cache.borrow_mut();
+
+    let is_stale = !matches!(&*listed_cache,
+        Some(cache) if cache.data_revision == data_revision
+            && cache.data_len == data_len
+            && (cache.search_field == *search_field || !settled));
+    if is_stale {
+        *listed_cache = Some(ListedCache {
+            data_revision,
+            data_len,
+            search_field: search_field.clone(),
+            rows: listed_rows(cf, &search_field)
+        });
+    }
+    listed_cache.as_ref()
+        .map(|cache| (cache.rows.clone(), cache.search_field.clone()))
+        .unwrap_or_default()
+}
+
+// hands a settled search to the column's search provider unless it has been asked already, and
+// takes its answer once it arrives, dropping the listed rows so they're relisted by it. None while
+// no search is awaited, Some(true) when the answer has just arrived and Some(false) until then
+fn poll_provider_search<T, C: ColumnFilter<T> + ?Sized>(cf: &C, search: &String) -> Option<bool> {
+    let state = cf.column_filter_state();
+    let search_provider = state.search_provider.borrow();
+    let search_provider = search_provider.as_ref().filter(|_| !search.is_empty())?;
+    let mut provider_search = state.provider_search.borrow_mut();
+    let answered = provider_search.results.as_ref().is_some_and(|(query, _)| query == search);
+    let in_flight = provider_search.pending.as_ref().is_some_and(|(query, _)| query == search);
+    if !answered && !in_flight {
+        provider_search.pending = Some((search.clone(), search_provider(search)));
+    }
+    let (query, receiver) = provider_search.pending.as_ref()?;
+    let results = match receiver.try_recv() {
+        Ok(values) => Some(values.into_iter().collect()),
+        Err(TryRecvError::Disconnected) => None,
+        Err(TryRecvError::Empty) => return Some(false),
+    };
+    provider_search.results = Some((query.clone(), results));
+    provider_search.pending = None;
+    *state.listed_cache.borrow_mut() = None;
+    Some(true)
+}
+
+// values of all_values() matching the search that no listed row holds, e.g. unused enum variants
+// or the values of multi-valued columns, listed below the rows. a data source's are paged instead
+fn listed_extra<T, C: ColumnFilter<T> + ?Sized>(cf: &C, search: &String) -> Vec<(ScalarValue, String)> {
+    if cf.column_filter_state().data_source.borrow().is_some() {
+        return vec![];
+    }
+    let all_values = cf.all_values();
+    if all_values.is_empty() {
+        return vec![];
+    }
+    let data = cf.column_filter_state().table_filter.backing_data.borrow();
+    let listed = cf.unique_rows().iter()
+        .map(|i| cf.get_value(&data[*i]))
+        .collect::<FxHashSet<_>>();
+    all_values.into_iter()
+        .filter(|(v, text)| !listed.contains(v) && (search.is_empty() || cf.matches_search(search, text)))
+        .collect()
+}
+
+// what ticking or clearing the checkboxes of the values does, marking them as touched
+fn set_selected<T, C: ColumnFilter<T> + ?Sized>(cf: &C, values: Vec<ScalarValue>, selected: bool) {
+    if selected {
+        cf.select_all(values);
+    } else {
+        cf.select_none(values);
+    }
+}
+
+// the row of a `row_count` long list keyboard focus moves to from `focused_row` on Down or Up.
+// the first Down from the search field, where focus is None, lands on the first row and Up from
+// the first row goes back to the search field
+fn move_focus(focused_row: Option<usize>, row_count: usize, down: bool, up: bool) -> Option<usize> {
+    let mut focused_row = focused_row
+        .filter(|_| row_count > 0)
+        .map(|idx| idx.min(row_count - 1));
+    if down && row_count > 0 {
+        focused_row = Some(focused_row.map_or(0, |idx| (idx + 1).min(row_count - 1)));
+    }
+    if up {
+        focused_row = focused_row.and_then(|idx| idx.checked_sub(1));
+    }
+    focused_row
+}
+
+// the rows a click on `row` sets like it. shift-clicking takes every row from the previous click,
+// as long as the list hasn't been searched differently in between
+fn clicked_rows(last_clicked: Option<&(usize, String)>, row: usize, search: &str, shift: bool, row_count: usize) -> RangeInclusive<usize> {
+    match last_clicked.filter(|(_, last_search)| shift && last_search == search) {
+        Some((anchor, _)) => (*anchor).min(row)..=(*anchor).max(row).min(row_count - 1),
+        None => row..=row,
+    }
+}
+
+// the min and max the range sliders show for a search, `lo..hi` with an open bound taking
+// the end of the domain. any other search shows the whole domain
+fn slider_bounds(search: &str, (min, max): (i64, i64)) -> (i64, i64) {
+    let bound = |s: &str, default: i64| if s.is_empty() { Some(default) } else { s.parse::<i64>().ok() };
+    search.split_once("..")
+        .filter(|_| !search.contains(','))
+        .and_then(|(lower, upper)| Some((bound(lower, min)?, bound(upper, max)?)))
+        .unwrap_or((min, max))
+}
+// the search the range sliders write, none while they span the whole domain
+fn slider_search(lo: i64, hi: i64, (min, max): (i64, i64)) -> String {
+    if lo == min && hi == max { String::new() } else { format!("{}..{}", lo, hi) }
+}
+
+// the days the from/to pickers show for a search, read from `a..b` or `=a` with an open bound
+// taking the end of the domain. any other search shows the whole domain
+fn picked_dates(search: &str, pattern: &str, (min, max): (NaiveDate, NaiveDate)) -> (NaiveDate, NaiveDate) {
+    let parse = |s: &str, default: NaiveDate| if s.is_empty() {
+        Some(default)
+    } else {
+        NaiveDate::parse_from_str(s.trim(), pattern).ok()
+    };
+    if search.contains(',') {
+        None
+    } else if let Some(day) = search.strip_prefix('=') {
+        parse(day, min).map(|day| (day, day))
+    } else {
+        search.split_once("..")
+            .and_then(|(lower, upper)| Some((parse(lower, min)?, parse(upper, max)?)))
+    }.unwrap_or((min, max))
+}
+// the search the from/to pickers write, `=a` when both are the same day and none while they
+// span the whole domain
+fn picked_search(from: NaiveDate, to: NaiveDate, pattern: &str, (min, max): (NaiveDate, NaiveDate)) -> String {
+    if from == min && to == max {
+        String::new()
+    } else if from == to {
+        format!("={}", from.format(pattern))
+    } else {
+        format!("{}..{}", from.format(pattern), to.format(pattern))
+    }
+}
+
+// the popup has closed, so the next opening takes a fresh snapshot. a snapshot still held here
+// means it was closed some other way than APPLY, RESET or Escape, e.g. by clicking outside, and
+// the close action decides what becomes of the changes made while it was open
+fn popup_closed<T, C: ColumnFilter<T> + ?Sized>(cf: &C, close_action: CloseAction) {
+    let state = cf.column_filter_state();
+    let snapshot = state.snapshot.borrow_mut().take();
+    if let Some(snapshot) = snapshot {
+        match close_action {
+            CloseAction::ApplyOnClose => if !state.search_field.borrow().is_empty() {
+                cf.apply_search();
+                state.table_filter.notify_change(cf.id());
+            },
+            CloseAction::DiscardOnClose => {
+                state.search_field.borrow_mut().clear();
+                let changed = *state.unselected_values.borrow() != snapshot;
+                if changed {
+                    *state.unselected_values.borrow_mut() = snapshot;
+                    state.table_filter.notify_change(cf.id());
+                }
+            },
+            CloseAction::KeepOpenUntilApply => {},
+        }
+    }
+    state.touched_values.borrow_mut().clear();
+    state.focused_row.set(None);
+}
+
+// the popup's only selected toggle, value order and blanks position applied to the rows listed
+// for the search. `ranked` keeps fuzzy matches in the order of their scores
+fn arrange_listed<T, C: ColumnFilter<T> + ?Sized>(cf: &C, listed_data: &mut Vec<&T>, visible_counts: &FxHashMap<ScalarValue, usize>, ranked: bool) {
+    let state = cf.column_filter_state();
+    if state.only_selected.get() {
+        listed_data.retain(|d| cf.contains(&cf.get_value(d)));
+    }
+
+    // most common values first, ties keep their sorted order
+    if state.value_order.get() == ValueOrder::Frequency && !ranked {
+        listed_data.sort_by_key(|d| Reverse(visible_counts.get(&cf.get_value(d)).copied().unwrap_or(0)));
+    }
+    if state.value_direction.get() == SortDirection::Descending && !ranked {
+        listed_data.reverse();
+    }
+    // stable, so the other values keep their order
+    match state.blanks_position.get() {
+        BlanksPosition::First => listed_data.sort_by_key(|d| !cf.get_string_value(d).is_empty()),
+        BlanksPosition::Last => listed_data.sort_by_key(|d| cf.get_string_value(d).is_empty()),
+    }
+}
+
+// the state of a tri-state checkbox over the given values as (checked, indeterminate), checked
+// when every value is selected and indeterminate when only some are
+fn tri_state<T, C: ColumnFilter<T> + ?Sized>(cf: &C, values: &[ScalarValue]) -> (bool, bool) {
+    let unselected_values = cf.column_filter_state().unselected_values.borrow();
+    let selected = values.iter().filter(|v| !unselected_values.contains(*v)).count();
+    (selected == values.len(), selected > 0 && selected < values.len())
+}
+
+fn apply_search_pattern<T>(cf: &dyn ColumnFilter<T>, pattern: &str) {
+    *cf.column_filter_state().search_field.borrow_mut() = pattern.to_string();
+    cf.apply_search();
+}
+
+fn and_arrays<'a>(len: usize, evals: impl Iterator<Item = &'a [bool]>) -> Vec<bool> {
+    let mut result = vec![true; len];
+    for eval in evals {
+        for (r, &b) in result.iter_mut().zip(eval.iter()) {
+            *r &= b;
+        }
+    }
+    result
+}
+
+// all true when there is nothing to combine
+fn or_arrays(len: usize, evals: &[&[bool]]) -> Vec<bool> {
+    if evals.is_empty() {
+        return vec![true; len];
+    }
+    let mut result = vec![false; len];
+    for eval in evals {
+        for (r, &b) in result.iter_mut().zip(eval.iter()) {
+            *r |= b;
+        }
+    }
+    result
+}
+
+// (active, eval) arrays combined the way TableFilter::combine() describes, only active ones counting for OR
+fn combine_arrays(combine: Combine, len: usize, evals: &[(bool, &[bool])]) -> Vec<bool> {
+    match combine {
+        Combine::And => and_arrays(len, evals.iter().map(|(_, eval)| *eval)),
+        Combine::Or => {
+            let active = evals.iter()
+                .filter(|(active, _)| *active)
+                .map(|(_, eval)| *eval)
+                .collect::<Vec<_>>();
+            or_arrays(len, &active)
+        },
+    }
+}
+
+// quotes a field containing commas, quotes or line breaks, doubling any quotes inside
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b => format!("%{:02X}", b)
+        })
+        .collect()
+}
+
+// the inverse of percent_encode(). a `%` not followed by two hex digits is kept as it is,
+// and bytes that don't decode to UTF-8 become U+FFFD, so a mangled link can't fail to load
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(b) = escaped {
+            decoded.push(b);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// the serializable part of a TableFilter. the mapper closures can't be stored,
+// so only the state is kept and matched back up to the columns by id
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableFilterState {
+    pub columns: BTreeMap<String, ColumnState>
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColumnState {
+    pub search_field: String,
+    pub unselected_values: Vec<ScalarValue>
+}
+
+// fluent alternative to the *_filters! macros, registering columns in call order
+pub struct TableFilterBuilder<T> {
+    table_filter: Rc<TableFilter<T>>
+}
+
+impl <T: 'static> TableFilterBuilder<T> {
+    pub fn string(self, id: &str, mapper: impl Fn(&T) -> String + 'static) -> Self {
+        self.filter(|table_filter| Box::new(StringColumnFilter::new(id, table_filter, Box::new(mapper))))
+    }
+    // a string filter reading a field in place, so evaluating rows doesn't clone it
+    pub fn string_ref(self, id: &str, mapper: impl for<'a> Fn(&'a T) -> &'a str + 'static) -> Self {
+        self.filter(|table_filter| Box::new(StringColumnFilter::new_borrowed(id, table_filter, Box::new(mapper))))
+    }
+    // a string filter matching plain terms by prefix, substring, suffix or whole value
+    pub fn string_matching(self, id: &str, mapper: impl Fn(&T) -> String + 'static, match_mode: MatchMode) -> Self {
+        self.filter(|table_filter| Box::new(StringColumnFilter::new(id, table_filter, Box::new(mapper)).with_match_mode(match_mode)))
+    }
+    // a yes/no column computed from the row, shown with the (true, false) labels
+    pub fn computed_bool(self, id: &str, predicate: impl Fn(&T) -> bool + 'static, labels: (&str, &str)) -> Self {
+        self.filter(|table_filter| Box::new(ComputedBoolColumnFilter::new(id, table_filter, Box::new(predicate), labels)))
+    }
+    pub fn char(self, id: &str, mapper: impl Fn(&T) -> char + 'static) -> Self {
+        self.filter(|table_filter| Box::new(CharColumnFilter::new(id, table_filter, Box::new(mapper))))
+    }
+    // searched by address, CIDR block or range, e.g. `192.168.0.0/16`
+    pub fn ip_addr(self, id: &str, mapper: impl Fn(&T) -> std::net::IpAddr + 'static) -> Self {
+        self.filter(|table_filter| Box::new(IpAddrColumnFilter::new(id, table_filter, Box::new(mapper))))
+    }
+    // one column searching several string fields, matching a row when any field matches
+    pub fn multi_field_string(self, id: &str, mappers: Vec<StrMapper<T>>) -> Self {
+        self.filter(|table_filter| Box::new(MultiFieldStringColumnFilter::new(id, table_filter, mappers)))
+    }
+    // a column holding several values per row, kept if any of them is selected
+    pub fn multi_value<V: std::fmt::Display + 'static>(self, id: &str, mapper: impl Fn(&T) -> Vec<V> + 'static) -> Self {
+        self.multi_value_matching(id, mapper, MultiMatch::Any)
+    }
+    pub fn multi_value_matching<V: std::fmt::Display + 'static>(self, id: &str, mapper: impl Fn(&T) -> Vec<V> + 'static, multi_match: MultiMatch) -> Self {
+        self.filter(|table_filter| Box::new(MultiValueColumnFilter::new(id, table_filter, Box::new(mapper), multi_match)))
+    }
+    // an enum column listing every variant, including ones the data doesn't hold yet
+    pub fn enumeration<E: PartialEq + std::fmt::Display + 'static>(self, id: &str, mapper: impl Fn(&T) -> E + 'static, variants: impl IntoIterator<Item = E>) -> Self {
+        let variants = variants.into_iter().collect();
+        self.filter(|table_filter| Box::new(EnumColumnFilter::new(id, table_filter, Box::new(mapper), variants)))
+    }
+    pub fn fuzzy_string(self, id: &str, mapper: impl Fn(&T) -> String + 'static) -> Self {
+        self.filter(|table_filter| Box::new(StringColumnFilter::new(id, table_filter, Box::new(mapper)).fuzzy(true)))
+    }
+    pub fn u8(self, id: &str, mapper: impl Fn(&T) -> u8 + 'static) -> Self {
+        self.filter(|table_filter| Box::new(U8ColumnFilter::from_mapper(id, table_filter, Box::new(mapper))))
+    }
+    pub fn u32(self, id: &str, mapper: impl Fn(&T) -> u32 + 'static) -> Self {
+        self.filter(|table_filter| Box::new(U32ColumnFilter::from_mapper(id, table_filter, Box::new(mapper))))
+    }
+    pub fn usize(self, id: &str, mapper: impl Fn(&T) -> usize + 'static) -> Self {
+        self.filter(|table_filter| Box::new(USizeColumnFilter::from_mapper(id, table_filter, Box::new(mapper))))
+    }
+    pub fn i32(self, id: &str, mapper: impl Fn(&T) -> i32 + 'static) -> Self {
+        self.filter(|table_filter| Box::new(I32ColumnFilter::from_mapper(id, table_filter, Box::new(mapper))))
+    }
+    pub fn bool(self, id: &str, mapper: impl Fn(&T) -> bool + 'static) -> Self {
+        self.filter(|table_filter| Box::new(BoolColumnFilter::from_mapper(id, table_filter, Box::new(mapper))))
+    }
+    // bool column displayed with a custom string, e.g. Y/N
+    pub fn bool_with_str(self, id: &str, mapper: impl Fn(&T) -> bool + 'static, str_mapper: impl Fn(&T) -> String + 'static) -> Self {
+        self.filter(|table_filter| Box::new(BoolColumnFilter::new(id, table_filter, Box::new(mapper), Box::new(str_mapper))))
+    }
+    pub fn date(self, id: &str, mapper: impl Fn(&T) -> NaiveDate + 'static, date_str_pattern: &str) -> Self {
+        self.filter(|table_filter| Box::new(NaiveDateColumnFilter::new(id, table_filter, date_str_pattern.to_string(), Box::new(mapper))))
+    }
+    // any other ColumnFilter, constructed against the table filter being built
+    pub fn filter(self, column_filter: impl FnOnce(Rc<TableFilter<T>>) -> Box<dyn ColumnFilter<T>>) -> Self {
+        self.table_filter.column_filter(column_filter(Rc::clone(&self.table_filter)));
+        self
+    }
+    pub fn value_histogram(self, enabled: bool) -> Self {
+        self.table_filter.value_histogram(enabled);
+        self
+    }
+    pub fn build(self) -> Rc<TableFilter<T>> {
+        self.table_filter
+    }
+}
+
+// the query handed to a column's search provider and not answered yet, and the last answer.
+// None results mean the provider dropped its sender, so the local search is used
+struct ProviderSearch {
+    pending: Option<(String, Receiver<Vec<String>>)>,
+    results: Option<(String, Option<FxHashSet<String>>)>
+}
+
+pub struct ColumnFilterState<T> {
+    table_filter: Rc<TableFilter<T>>,
+    unselected_values: RefCell<FxHashSet<ScalarValue>>,
+    // unselected when the column is created, see ColumnFilter::default_unselected()
+    default_unselected: RefCell<FxHashSet<ScalarValue>>,
+    reset_mode: Cell<ResetMode>,
+    search_field: RefCell<String>,
+    #[cfg(feature = "rayon")]
+    parallel_evals: RefCell<Option<ParallelEvals<T>>>,
+    apply_requested: Cell<bool>,
+    // unselected_values as they were when the popup opened, restored on Escape
+    snapshot: RefCell<Option<FxHashSet<ScalarValue>>>,
+    // egui time of the last keystroke in the search field, for debouncing the value list
+    last_edit_time: Cell<f64>,
+    search_debounce: Cell<Option<f64>>,
+    // values toggled by hand since the popup opened, which APPLY leaves as they are
+    touched_values: RefCell<FxHashSet<ScalarValue>>,
+    // row of the value list with keyboard focus, None while the search field has it
+    focused_row: Cell<Option<usize>>,
+    // row last clicked in the value list and the search it was listed under, the anchor of a shift-click range
+    last_clicked: RefCell<Option<(usize, String)>>,
+    scroll_offset: Cell<f32>,
+    value_order: Cell<ValueOrder>,
+    value_direction: Cell<SortDirection>,
+    // groups the popup's values under the returned parent, see TableFilter::parent_of_for_id()
+    parent_of: RefCell<Option<ParentOf>>,
+    // shown in the popup in place of an empty value
+    blank_label: RefCell<String>,
+    blanks_position: Cell<BlanksPosition>,
+    // currency or unit symbols ignored when parsing numeric search terms, e.g. "$"
+    number_affixes: RefCell<Vec<String>>,
+    data_source: RefCell<Option<Rc<dyn FilterDataSource>>>,
+    source_cache: RefCell<Option<SourceCache>>,
+    // overrides ColumnFilter::shows_search()
+    show_search: Cell<Option<bool>>,
+    close_action: Cell<CloseAction>,
+    search_provider: RefCell<Option<SearchProvider>>,
+    provider_search: RefCell<ProviderSearch>,
+    // custom search operators by prefix, longest prefix first
+    operators: RefCell<Vec<(String, SearchOperator)>>,
+    // lists only the values not in unselected_values, without changing them
+    only_selected: Cell<bool>,
+    // shows min/max sliders in the popup, see TableFilter::range_slider_for_id()
+    range_slider: Cell<bool>,
+    // a from/to picker's calendar was shown last frame, outside the popup, so clicks don't close it
+    date_picker_open: Cell<bool>,
+    popup_width: Cell<Option<f32>>,
+    popup_max_height: Cell<Option<f32>>,
+    listed_cache: RefCell<Option<ListedCache>>,
+    kept_cache: RefCell<Option<KeptCache>>,
+    eval_cache: RefCell<Option<EvalCache>>,
+    unique_cache: RefCell<Option<UniqueCache>>,
+    // overrides the get_value() ordering when sorting by this column
+    sort_comparator: RefCell<Option<SortComparator<T>>>,
+    #[cfg(feature = "persistence")]
+    restored: Cell<bool>
+}
+
+struct UniqueCache {
+    data_revision: u64,
+    data_len: usize,
+    rows: Rc<Vec<usize>>
+}
+
+// what a column has fetched from its data source, dropped once the data changes
+struct SourceCache {
+    data_revision: u64,
+    count: usize,
+    pages: FxHashMap<usize, SourceValues>,
+    // the search the popup last listed the matching values for
+    search: Option<(String, SourceValues)>
+}
+
+// values fetched from a data source along with their display strings
+type SourceValues = Rc<Vec<(ScalarValue, String)>>;
+
+// the column's cache of its data source, reset when TableFilter::mark_dirty() is called
+fn source_cache<'a, T>(state: &'a ColumnFilterState<T>, id: &str, data_source: &dyn FilterDataSource) -> RefMut<'a, SourceCache> {
+    let data_revision = state.table_filter.data_revision.get();
+    let mut cache = state.source_cache.borrow_mut();
+    if cache.as_ref().is_none_or(|cache| cache.data_revision != data_revision) {
+        *cache = Some(SourceCache {
+            data_revision,
+            count: data_source.unique_value_count(id),
+            pages: FxHashMap::default(),
+            search: None
+        });
+    }
+    RefMut::map(cache, |cache| cache.as_mut().unwrap())
+}
+
+// the values of a data source column in `range`, fetched a page at a time and cached
+fn source_values<T>(state: &ColumnFilterState<T>, id: &str, data_source: &dyn FilterDataSource, range: Range<usize>) -> Vec<(ScalarValue, String)> {
+    let mut cache = source_cache(state, id, data_source);
+    let end = range.end.min(cache.count);
+    let count = cache.count;
+    let mut values = vec![];
+    for page in (range.start / SOURCE_PAGE_SIZE)..end.div_ceil(SOURCE_PAGE_SIZE) {
+        let start = page * SOURCE_PAGE_SIZE;
+        let page_values = cache.pages.entry(page)
+            .or_insert_with(|| Rc::new(data_source.unique_values(id, start..(start + SOURCE_PAGE_SIZE).min(count))));
+        values.extend(page_values.iter()
+            .enumerate()
+            .filter(|(i, _)| (range.start..end).contains(&(start + i)))
+            .map(|(_, v)| v.clone()));
+    }
+    values
+}
+
+// the values of a data source column matching the search, asked of the source once per search
+fn source_matches<T>(state: &ColumnFilterState<T>, id: &str, data_source: &dyn FilterDataSource, search: &String, predicate: &dyn Fn(&str) -> bool) -> Rc<Vec<(ScalarValue, String)>> {
+    let mut cache = source_cache(state, id, data_source);
+    if let Some((cached_search, values)) = &cache.search && cached_search == search {
+        return Rc::clone(values);
+    }
+    let values = Rc::new(data_source.matching_values(id, predicate));
+    cache.search = Some((search.clone(), Rc::clone(&values)));
+    values
+}
+
+// overrides a column's get_value() ordering when sorting rows, see TableFilter::sort_comparator_for_id()
+pub type SortComparator<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
+// the parent a popup value is listed under, see TableFilter::parent_of_for_id()
+type ParentOf = Box<dyn Fn(&ScalarValue) -> String>;
+
+// answers a settled search from outside the popup, see TableFilter::search_provider_for_id()
+type SearchProvider = Box<dyn Fn(&str) -> Receiver<Vec<String>>>;
+
+// matches a value against the operand of a custom term, see TableFilter::search_operator_for_id()
+type SearchOperator = Box<dyn Fn(&str, &str) -> bool>;
+
+// a column's eval array computed across threads, see TableFilter::parallel_mapper_for_id()
+#[cfg(feature = "rayon")]
+type ParallelEvals<T> = Box<dyn Fn(&[T], &FxHashSet<ScalarValue>) -> Vec<bool>>;
+
+// this column's eval array, valid while the data and unselected_values are unchanged
+struct EvalCache {
+    data_revision: u64,
+    data_len: usize,
+    unselected_values: FxHashSet<ScalarValue>,
+    evals: Rc<Vec<bool>>
+}
+impl EvalCache {
+    fn is_valid(&self, data_revision: u64, data_len: usize, unselected_values: &FxHashSet<ScalarValue>) -> bool {
+        self.data_revision == data_revision
+            && self.data_len == data_len
+            && self.unselected_values == *unselected_values
+    }
+}
+
+// rows of the backing data holding each unique value listed in the popup, in display order
+struct ListedCache {
+    data_revision: u64,
+    data_len: usize,
+    search_field: String,
+    rows: Vec<usize>
+}
+// the popup footer's row count along with what it was counted from, see kept_row_count()
+struct KeptCache {
+    data_revision: u64,
+    data_len: usize,
+    search_field: String,
+    unselected_values: FxHashSet<ScalarValue>,
+    visible_counts: FxHashMap<ScalarValue, usize>,
+    count: usize
+}
+impl <T> ColumnFilterState<T> {
+    pub fn new(table_filter: &Rc<TableFilter<T>>) -> Self {
+        Self {
+            table_filter: Rc::clone(table_filter),
+            unselected_values: RefCell::new(Default::default()),
+            default_unselected: RefCell::new(Default::default()),
+            reset_mode: Cell::new(ResetMode::default()),
+            search_field: RefCell::new("".to_string()),
+            #[cfg(feature = "rayon")]
+            parallel_evals: RefCell::new(None),
+            apply_requested: Cell::new(false),
+            snapshot: RefCell::new(None),
+            last_edit_time: Cell::new(f64::NEG_INFINITY),
+            search_debounce: Cell::new(None),
+            touched_values: RefCell::new(FxHashSet::default()),
+            focused_row: Cell::new(None),
+            last_clicked: RefCell::new(None),
+            scroll_offset: Cell::new(0.0),
+            value_order: Cell::new(ValueOrder::default()),
+            value_direction: Cell::new(SortDirection::Ascending),
+            parent_of: RefCell::new(None),
+            blank_label: RefCell::new("(blank)".to_string()),
+            blanks_position: Cell::new(BlanksPosition::default()),
+            only_selected: Cell::new(false),
+            number_affixes: RefCell::new(vec![]),
+            operators: RefCell::new(vec![]),
+            data_source: RefCell::new(None),
+            source_cache: RefCell::new(None),
+            show_search: Cell::new(None),
+            close_action: Cell::new(CloseAction::default()),
+            search_provider: RefCell::new(None),
+            provider_search: RefCell::new(ProviderSearch { pending: None, results: None }),
+            range_slider: Cell::new(false),
+            date_picker_open: Cell::new(false),
+            popup_width: Cell::new(None),
+            popup_max_height: Cell::new(None),
+            listed_cache: RefCell::new(None),
+            kept_cache: RefCell::new(None),
+            eval_cache: RefCell::new(None),
+            unique_cache: RefCell::new(None),
+            sort_comparator: RefCell::new(None),
+            #[cfg(feature = "persistence")]
+            restored: Cell::new(false),
+        }
+    }
+    pub(crate) fn unselected_values(&self) -> Vec<ScalarValue> {
+        self.unselected_values.borrow().iter().cloned().sorted().collect()
+    }
+    pub(crate) fn search_field(&self) -> String {
+        self.search_field.borrow().clone()
+    }
+    pub(crate) fn set_unselected_values(&self, values: impl IntoIterator<Item = ScalarValue>) {
+        *self.unselected_values.borrow_mut() = values.into_iter().collect();
+    }
+    pub(crate) fn set_default_unselected(&self, values: impl IntoIterator<Item = ScalarValue>) {
+        let values = values.into_iter().collect::<FxHashSet<_>>();
+        *self.unselected_values.borrow_mut() = values.clone();
+        *self.default_unselected.borrow_mut() = values;
+    }
+    pub(crate) fn number_affixes(&self) -> std::cell::Ref<'_, Vec<String>> {
+        self.number_affixes.borrow()
+    }
+    pub(crate) fn table_filter(&self) -> &Rc<TableFilter<T>> {
+        &self.table_filter
+    }
+}
+
+// how a ScalarValue::Fields value is displayed, and split back up for searching
+pub(crate) const FIELD_SEPARATOR: &str = " / ";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScalarValue {
+    Str(String),
+    U8(u8),
+    I8(i8),
+    U32(u32),
+    USize(usize),
+    I32(i32),
+    Bool(bool),
+    IpAddr(std::net::IpAddr),
+    // the fields of a multi_field_string() column, keyed as a whole
+    Fields(Vec<String>),
+}
+// Str hashes like the &str it holds, so sets of values can be searched by a borrowed
+// string through `dyn ValueKey` without allocating
+impl Hash for ScalarValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ScalarValue::Str(s) => hash_str(s, state),
+            ScalarValue::U8(v) => (1u8, v).hash(state),
+            ScalarValue::I8(v) => (2u8, v).hash(state),
+            ScalarValue::U32(v) => (3u8, v).hash(state),
+            ScalarValue::USize(v) => (4u8, v).hash(state),
+            ScalarValue::I32(v) => (5u8, v).hash(state),
+            ScalarValue::Bool(v) => (6u8, v).hash(state),
+            ScalarValue::IpAddr(v) => (7u8, v).hash(state),
+            ScalarValue::Fields(v) => (8u8, v).hash(state),
+        }
+    }
+}
+fn hash_str<H: Hasher>(s: &str, state: &mut H) {
+    (0u8, s).hash(state)
+}
+
+// a ScalarValue, or a borrowed string standing in for a ScalarValue::Str when looking one up
+pub(crate) trait ValueKey {
+    fn key(&self) -> Result<&str, &ScalarValue>;
+}
+impl ValueKey for ScalarValue {
+    fn key(&self) -> Result<&str, &ScalarValue> {
+        match self {
+            ScalarValue::Str(s) => Ok(s),
+            v => Err(v)
+        }
+    }
+}
+impl ValueKey for &str {
+    fn key(&self) -> Result<&str, &ScalarValue> { Ok(self) }
+}
+impl Hash for dyn ValueKey + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.key() {
+            Ok(s) => hash_str(s, state),
+            Err(v) => v.hash(state)
+        }
+    }
+}
+impl PartialEq for dyn ValueKey + '_ {
+    fn eq(&self, other: &Self) -> bool { self.key() == other.key() }
+}
+impl Eq for dyn ValueKey + '_ {}
+impl <'a> std::borrow::Borrow<dyn ValueKey + 'a> for ScalarValue {
+    fn borrow(&self) -> &(dyn ValueKey + 'a) { self }
+}
+impl std::fmt::Display for ScalarValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalarValue::Str(s) => write!(f, "{}", s),
+            ScalarValue::U32(u) => write!(f, "{}", u),
+            ScalarValue::USize(u) => write!(f, "{}", u),
+            ScalarValue::Bool(b) => write!(f, "{}", b),
+            ScalarValue::I32(i) => write!(f, "{}", i),
+            ScalarValue::U8(u) => write!(f, "{}", u),
+            ScalarValue::I8(i) => write!(f, "{}", i),
+            ScalarValue::IpAddr(ip) => write!(f, "{}", ip),
+            ScalarValue::Fields(fields) => write!(f, "{}", fields.join(FIELD_SEPARATOR)),
+        }
+    }
+}
+// values of the same variant compare naturally. across variants, bools come first, then
+// every numeric variant ordered by value (ties broken by variant so the order stays
+// consistent with Eq), then IP addresses (IPv4 before IPv6), then strings, then multiple fields
+impl Ord for ScalarValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (ScalarValue::Str(a), ScalarValue::Str(b)) => a.cmp(b),
+            (ScalarValue::Bool(a), ScalarValue::Bool(b)) => a.cmp(b),
+            (ScalarValue::IpAddr(a), ScalarValue::IpAddr(b)) => a.cmp(b),
+            (ScalarValue::Fields(a), ScalarValue::Fields(b)) => a.cmp(b),
+            (ScalarValue::U8(a), ScalarValue::U8(b)) => a.cmp(b),
+            (ScalarValue::I8(a), ScalarValue::I8(b)) => a.cmp(b),
+            (ScalarValue::U32(a), ScalarValue::U32(b)) => a.cmp(b),
+            (ScalarValue::USize(a), ScalarValue::USize(b)) => a.cmp(b),
+            (ScalarValue::I32(a), ScalarValue::I32(b)) => a.cmp(b),
+            _ => match (self.as_i128(), other.as_i128()) {
+                (Some(a), Some(b)) => a.cmp(&b).then_with(|| self.rank().cmp(&other.rank())),
+                _ => self.rank().cmp(&other.rank()),
+            },
+        }
+    }
+}
+impl PartialOrd for ScalarValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl ScalarValue {
+    // position of the variant in the cross-variant order
+    fn rank(&self) -> u8 {
+        match self {
+            ScalarValue::Bool(_) => 0,
+            ScalarValue::U8(_) => 1,
+            ScalarValue::I8(_) => 2,
+            ScalarValue::U32(_) => 3,
+            ScalarValue::USize(_) => 4,
+            ScalarValue::I32(_) => 5,
+            ScalarValue::IpAddr(_) => 6,
+            ScalarValue::Str(_) => 7,
+            ScalarValue::Fields(_) => 8,
+        }
+    }
+    // integer value of the numeric variants, None for strings, bools, IP addresses and fields,
+    // and for a usize too large for an i64
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+    // integer value of the numeric variants, wide enough to hold every one of them without wrapping
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            ScalarValue::U8(u) => Some(*u as i128),
+            ScalarValue::I8(i) => Some(*i as i128),
+            ScalarValue::U32(u) => Some(*u as i128),
+            ScalarValue::USize(u) => Some(*u as i128),
+            ScalarValue::I32(i) => Some(*i as i128),
+            ScalarValue::Str(_) | ScalarValue::Bool(_) | ScalarValue::IpAddr(_) | ScalarValue::Fields(_) => None,
+        }
+    }
+}
+
+// evaluates a comma-separated search pattern term by term.
+// a term prefixed with `!` is negated, e.g. `!ATL` or `!>500`. negated terms are AND-ed,
+// so a target must match none of them. when `ranges` is set, positive terms starting with
+// `<` or `>` are AND-ed into a range (`>100,<500`) while the rest are OR-ed as alternatives
+// (`100,200`), and a target matches if it falls in the range or matches any alternative.
+// a lone `!` is ignored
+pub fn search_terms(pattern: &str, ranges: bool, matches: impl Fn(&str) -> bool) -> bool {
+    search_split_terms(&split_terms(pattern), ranges, matches)
+}
+// search_terms() over a pattern already split into terms
+fn search_split_terms(terms: &[String], ranges: bool, matches: impl Fn(&str) -> bool) -> bool {
+    let (negated, positive): (Vec<&str>, Vec<&str>) = terms.iter()
+        .map(|term| term.as_str())
+        .filter(|term| *term != "!")
+        .partition(|term| term.starts_with('!'));
+    let (comparisons, alternatives): (Vec<&str>, Vec<&str>) = positive.into_iter()
+        .partition(|term| ranges && term.starts_with(['<', '>']));
+
+    let positive_match = (comparisons.is_empty() && alternatives.is_empty())
+        || (!comparisons.is_empty() && comparisons.iter().all(|term| matches(term)))
+        || alternatives.iter().any(|term| matches(term));
+    positive_match && !negated.iter().any(|term| matches(&term[1..]))
+}
+
+// case-insensitive comparisons of a value with an already lowercased term, lowercasing the
+// value a char at a time instead of into a new String
+fn lowercase_eq(value: &str, term: &str) -> bool {
+    value.chars().flat_map(char::to_lowercase).eq(term.chars())
+}
+fn lowercase_starts_with(value: &str, term: &str) -> bool {
+    let mut value = value.chars().flat_map(char::to_lowercase);
+    term.chars().all(|c| value.next() == Some(c))
+}
+fn lowercase_contains(value: &str, term: &str) -> bool {
+    term.is_empty() || value.char_indices().any(|(i, _)| lowercase_starts_with(&value[i..], term))
+}
+
+// splits a search pattern on commas, except inside double quotes or parentheses, so
+// `"Dallas, TX",ATL` is two terms and `in(100,200),>500` is too. the quotes themselves are dropped, and `\"` and `\,` stand for a literal
+// quote or comma. other backslashes are kept for the glob escapes `\*` and `\?`.
+// unquoted terms are trimmed and blank ones skipped, so ` ATL , ORD ,` is ATL and ORD
+// and a pattern of only commas or spaces has no terms, matching everything
+pub fn split_terms(pattern: &str) -> Vec<String> {
+    split_terms_by(pattern, ',')
+}
+
+// split_terms() with another term separator, e.g. `;` where commas are decimal separators
+pub fn split_terms_by(pattern: &str, separator: char) -> Vec<String> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut quoted = false;
+    let mut has_quotes = false;
+    // open parentheses outside quotes, an unmatched `)` is kept as text
+    let mut depth = 0usize;
+    let mut push_term = |term: &mut String, has_quotes: &mut bool| {
+        let term = std::mem::take(term);
+        let term = if *has_quotes { term } else { term.trim().to_string() };
+        if *has_quotes || !term.is_empty() {
+            terms.push(term);
+        }
+        *has_quotes = false;
+    };
+
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped == '"' || escaped == separator => term.push(escaped),
+                Some(other) => { term.push('\\'); term.push(other); },
+                None => term.push('\\'),
+            },
+            '"' => {
+                quoted = !quoted;
+                has_quotes = true;
+            },
+            '(' if !quoted => {
+                depth += 1;
+                term.push(c);
+            },
+            ')' if !quoted => {
+                depth = depth.saturating_sub(1);
+                term.push(c);
+            },
+            c if c == separator && !quoted && depth == 0 => push_term(&mut term, &mut has_quotes),
+            _ => term.push(c),
+        }
+    }
+    push_term(&mut term, &mut has_quotes);
+    terms
+}
+
+// rewrites a pattern typed in the given locale into the default syntax the column filters
+// parse, or None when the separators are the default ones. on numeric columns with comma
+// decimals, `1.234,56` becomes `1234.56` while `..` ranges and `in(...)` sets are kept
+fn localize_pattern(pattern: &str, locale: SearchLocale, numeric: bool) -> Option<String> {
+    if locale.term_separator == ',' && locale.decimal_separator == '.' {
+        return None;
+    }
+    let terms = split_terms_by(pattern, locale.term_separator).into_iter()
+        .map(|term| if numeric && locale.decimal_separator == ',' && in_operands(term.strip_prefix('!').unwrap_or(&term)).is_none() {
+            term.replace("..", "\u{0}").replace('.', "").replace(',', ".").replace('\u{0}', "..")
+        } else {
+            term
+        })
+        .map(|term| if term.contains([',', '"']) || term.trim() != term {
+            format!("\"{}\"", term.replace('"', "\\\""))
+        } else {
+            term
+        })
+        .join(",");
+    Some(terms)
+}
+
+// lays out `text` in `color`, with the matched `spans` emphasized on top
+fn highlighted_label(ui: &egui::Ui, text: &str, mut spans: Vec<Range<usize>>, color: Color32, style: &FilterPopupStyle) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let plain = TextFormat::simple(font_id.clone(), color);
+    let highlight = TextFormat {
+        font_id,
+        color: style.match_color.unwrap_or_else(|| ui.visuals().strong_text_color()),
+        background: style.match_background.unwrap_or_else(|| ui.visuals().selection.bg_fill.gamma_multiply(0.5)),
+        ..Default::default()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut pos = 0;
+    spans.sort_by_key(|span| span.start);
+    for span in spans {
+        // skip spans overlapping what is already laid out
+        if span.start < pos || span.end > text.len() {
+            continue;
+        }
+        job.append(&text[pos..span.start], 0.0, plain.clone());
+        job.append(&text[span.clone()], 0.0, highlight.clone());
+        pos = span.end;
+    }
+    job.append(&text[pos..], 0.0, plain);
+    job
+}
+
+pub trait ColumnFilter<T> {
+    fn id(&self) -> &str;
+    fn get_value(&self, t: &T) -> ScalarValue;
+
+    // borrowed string value of a row, letting evaluation compare without allocating.
+    // None for columns whose values are computed, which go through get_value()
+    fn get_str_ref<'a>(&self, _t: &'a T) -> Option<&'a str> {
+        None
+    }
+
+    fn column_filter_state(&self) -> &ColumnFilterState<T>;
+
+    // default implementations
+    fn get_eval_bool_array(&self) -> Rc<Vec<bool>> {
+        let state = self.column_filter_state();
+        let data = state.table_filter.backing_data.borrow();
+        let data_revision = state.table_filter.data_revision.get();
+        let unselected_values = state.unselected_values.borrow();
+        let mut eval_cache = state.eval_cache.borrow_mut();
+
+        if let Some(cache) = &*eval_cache && cache.is_valid(data_revision, data.len(), &unselected_values) {
+            return Rc::clone(&cache.evals);
+        }
+
+        #[cfg(feature = "rayon")]
+        let evals = Rc::new(match &*state.parallel_evals.borrow() {
+            Some(parallel_evals) if self.evaluates_by_value() && data.len() >= PARALLEL_MIN_ROWS => parallel_evals(&data, &unselected_values),
+            _ => data.iter().map(|t| self.evaluate(t)).collect()
+        });
+        #[cfg(not(feature = "rayon"))]
+        let evals = Rc::new(data.iter().map(|t| self.evaluate(t)).collect::<Vec<_>>());
+        *eval_cache = Some(EvalCache {
+            data_revision,
+            data_len: data.len(),
+            unselected_values: unselected_values.clone(),
+            evals: Rc::clone(&evals)
+        });
+        evals
+    }
+    // whether evaluate() is a lookup of get_value() in the unselected values, which lets a
+    // parallel mapper stand in for it. custom columns overriding evaluate() keep the default
+    fn evaluates_by_value(&self) -> bool {
+        false
+    }
+    fn selectable_value_bool_array(&self) -> Vec<bool> {
+        self.column_filter_state().table_filter.evaluate_array_excluding_id(self.id())
+    }
+
+    // keeps the selection as it is when the popup opens, for discard_changes()
+    fn take_snapshot(&self) {
+        let state = self.column_filter_state();
+        state.snapshot.borrow_mut().get_or_insert_with(|| state.unselected_values.borrow().clone());
+    }
+    // what Escape does: clears the search field and puts back the selection kept by
+    // take_snapshot(), returning whether that changed the selection
+    fn discard_changes(&self) -> bool {
+        let state = self.column_filter_state();
+        state.search_field.borrow_mut().clear();
+        let snapshot = state.snapshot.borrow_mut().take();
+        match snapshot {
+            Some(snapshot) => state.unselected_values.replace(snapshot) != *state.unselected_values.borrow(),
+            None => false
+        }
+    }
+
+    // one row index per unique value in the backing data, sorted by value.
+    // cached until the data length changes or TableFilter::mark_dirty() is called
+    fn unique_rows(&self) -> Rc<Vec<usize>> {
+        let state = self.column_filter_state();
+        // the data source lists the values instead, through all_values()
+        if state.data_source.borrow().is_some() {
+            return Rc::new(vec![]);
+        }
+        let data = state.table_filter.backing_data.borrow();
+        let data_revision = state.table_filter.data_revision.get();
+        let mut unique_cache = state.unique_cache.borrow_mut();
+
+        if let Some(cache) = &*unique_cache
+            && cache.data_revision == data_revision
+            && cache.data_len == data.len() {
+            return Rc::clone(&cache.rows);
+        }
+
+        let rows = Rc::new(data.iter()
+            .enumerate()
+            .unique_by(|(_, d)| self.get_value(d))
+            .sorted_by_key(|(_, d)| self.get_value(d))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>());
+        *unique_cache = Some(UniqueCache {
+            data_revision,
+            data_len: data.len(),
+            rows: Rc::clone(&rows)
+        });
+        rows
+    }
+
+    // every distinct value of the column, sorted: the data source's when one is set,
+    // otherwise the backing data's
+    fn distinct_values(&self) -> Vec<ScalarValue> {
+        if self.column_filter_state().data_source.borrow().is_some() {
+            return self.all_values().into_iter().map(|(v, _)| v).collect();
+        }
+        let data = self.column_filter_state().table_filter.backing_data.borrow();
+        self.unique_rows().iter()
+            .map(|i| self.get_value(&data[*i]))
+            .collect()
+    }
+
+    #[cfg(feature = "serde")]
+    fn json_value(&self, t: &T) -> serde_json::Value {
+        match self.get_value(t) {
+            ScalarValue::Str(s) => serde_json::Value::from(s),
+            ScalarValue::U8(u) => serde_json::Value::from(u),
+            ScalarValue::I8(i) => serde_json::Value::from(i),
+            ScalarValue::U32(u) => serde_json::Value::from(u),
+            ScalarValue::USize(u) => serde_json::Value::from(u),
+            ScalarValue::I32(i) => serde_json::Value::from(i),
+            ScalarValue::Bool(b) => serde_json::Value::from(b),
+            ScalarValue::IpAddr(ip) => serde_json::Value::from(ip.to_string()),
+            ScalarValue::Fields(fields) => serde_json::Value::from(fields),
+        }
+    }
+    // ordering used when sorting by this column
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match &*self.column_filter_state().sort_comparator.borrow() {
+            Some(comparator) => comparator(a, b),
+            None => self.get_value(a).cmp(&self.get_value(b)),
+        }
+    }
+
+    // unique values of the data not filtered out by this column, sorted
+    fn selected_values(&self) -> Vec<ScalarValue> {
+        let unselected_values = self.column_filter_state().unselected_values.borrow();
+        self.distinct_values().into_iter()
+            .filter(|v| !unselected_values.contains(v))
+            .sorted()
+            .collect()
+    }
+    // selects exactly the given values, unselecting every other value in the data
+    fn set_selected_values(&self, values: Vec<ScalarValue>) {
+        let selected = values.into_iter().collect::<FxHashSet<_>>();
+        let unselected = self.distinct_values().into_iter()
+            .filter(|v| !selected.contains(v))
+            .collect();
+        *self.column_filter_state().unselected_values.borrow_mut() = unselected;
+    }
+
+    // short summary of the column's criteria, e.g. "3 of 38 values selected" followed by
+    // the pending search on a second line when there is one
+    fn describe_active(&self) -> String {
+        let state = self.column_filter_state();
+        let values = self.distinct_values();
+        let total = values.len();
+        let unselected_values = state.unselected_values.borrow();
+        let selected = values.iter()
+            .filter(|v| !unselected_values.contains(*v))
+            .count();
+
+        let mut description = format!("{} of {} values selected", selected, total);
+        let search_field = state.search_field.borrow();
+        if !search_field.is_empty() {
+            description.push_str(&format!("\nsearch: {}", search_field));
+        }
+        description
+    }
+    // what APPLY does with a non-empty search field: values matching the search are selected
+    // and the rest unselected, except the ones toggled by hand in the popup, which keep what
+    // their checkbox shows. the search field is cleared afterwards
+    fn apply_search(&self) {
+        let state = self.column_filter_state();
+        if state.search_field.borrow().is_empty() {
+            return;
+        }
+        let matches = self.search_matches(&state.search_field.borrow());
+        let touched_values = state.touched_values.borrow();
+        let mut unselected_values = state.unselected_values.borrow_mut();
+
+        for (v, matched) in matches.into_iter().filter(|(v, _)| !touched_values.contains(v)) {
+            if matched {
+                unselected_values.remove(&v);
+            } else {
+                unselected_values.insert(v);
+            }
+        }
+        state.search_field.borrow_mut().clear();
+    }
+    // each unique value in the data along with whether it matches the search pattern,
+    // without touching the selection
+    fn search_matches(&self, pattern: &String) -> Vec<(ScalarValue, bool)> {
+        let state = self.column_filter_state();
+        if let Some(data_source) = state.data_source.borrow().clone() {
+            let matching = source_matches(state, self.id(), &*data_source, pattern, &|text| self.matches_search(pattern, &text.to_string()));
+            let matching = matching.iter().map(|(v, _)| v).collect::<FxHashSet<_>>();
+            return self.all_values().into_iter()
+                .map(|(v, _)| {
+                    let matched = matching.contains(&v);
+                    (v, matched)
+                })
+                .collect();
+        }
+        let data = state.table_filter.backing_data.borrow();
+        let mut matches = self.unique_rows().iter()
+            .map(|i| &data[*i])
+            .map(|d| (self.get_value(d), self.matches_search(pattern, &self.get_string_value(d))))
+            .collect::<Vec<_>>();
+        // values the popup lists through all_values() rather than from the rows
+        let listed = matches.iter().map(|(v, _)| v.clone()).collect::<FxHashSet<_>>();
+        matches.extend(self.all_values().into_iter()
+            .filter(|(v, _)| !listed.contains(v))
+            .map(|(v, text)| {
+                let matched = self.matches_search(pattern, &text);
+                (v, matched)
+            }));
+        matches
+    }
+
+    fn filter_to_value(&self, value: ScalarValue) {
+        self.set_selected_values(vec![value]);
+    }
+    fn exclude_value(&self, value: ScalarValue) {
+        self.column_filter_state().unselected_values.borrow_mut().insert(value);
+    }
+
+    // what the popup's NONE, ALL and INVERT buttons do with the values it lists. the values
+    // are marked as touched, so a following apply_search() leaves them as they are
+    fn select_none(&self, values: Vec<ScalarValue>) {
+        let state = self.column_filter_state();
+        state.touched_values.borrow_mut().extend(values.iter().cloned());
+        state.unselected_values.borrow_mut().extend(values);
+    }
+    fn select_all(&self, values: Vec<ScalarValue>) {
+        let state = self.column_filter_state();
+        let mut unselected_values = state.unselected_values.borrow_mut();
+        values.iter().for_each(|v| { unselected_values.remove(v); });
+        state.touched_values.borrow_mut().extend(values);
+    }
+    fn invert_selection(&self, values: Vec<ScalarValue>) {
+        let state = self.column_filter_state();
+        let mut unselected_values = state.unselected_values.borrow_mut();
+        for v in values.iter() {
+            if !unselected_values.remove(v) {
+                unselected_values.insert(v.clone());
+            }
+        }
+        state.touched_values.borrow_mut().extend(values);
+    }
+
+    // clears the search field and returns the selection to the baseline of the column's
+    // ResetMode, see TableFilter::reset_mode_for_id()
+    fn reset(&self) {
+        let state = self.column_filter_state();
+        state.search_field.borrow_mut().clear();
+        *state.unselected_values.borrow_mut() = match state.reset_mode.get() {
+            ResetMode::Empty => FxHashSet::default(),
+            ResetMode::Default => state.default_unselected.borrow().clone(),
+        };
+        state.touched_values.borrow_mut().clear();
+    }
+    // starts the column with the given values unselected, so the table opens pre-filtered,
+    // e.g. `BoolColumnFilter::new(...).default_unselected([ScalarValue::Bool(true)])`
+    fn default_unselected(self, values: impl IntoIterator<Item = ScalarValue>) -> Self where Self: Sized {
+        self.column_filter_state().set_default_unselected(values);
+        self
+    }
+
+    fn contains(&self, value: &ScalarValue) -> bool {
+        !self.column_filter_state().unselected_values.borrow().contains(value)
+    }
+    fn search_pattern(&self, pattern: &String, target: &String) -> bool {
+        target.starts_with(pattern)
+    }
+    // the pattern rewritten from the table filter's SearchLocale, None for the default locale
+    fn localized_pattern(&self, pattern: &str) -> Option<String> {
+        let locale = self.column_filter_state().table_filter.search_locale.get();
+        localize_pattern(pattern, locale, self.supports_range_ui())
+    }
+    // search_pattern() with the operators from search_operator_for_id(). a term starting with a
+    // registered prefix goes to its closure, which wins over the built-in operators, while
+    // every other term is matched by search_pattern() on its own
+    fn matches_search(&self, pattern: &String, target: &String) -> bool {
+        if let Some((query, Some(results))) = &self.column_filter_state().provider_search.borrow().results
+            && *query == *pattern {
+            return results.contains(target);
+        }
+        let localized;
+        let pattern = match self.localized_pattern(pattern) {
+            Some(pattern) => {
+                localized = pattern;
+                &localized
+            },
+            None => pattern
+        };
+        let operators = self.column_filter_state().operators.borrow();
+        if operators.is_empty() {
+            return self.search_pattern(pattern, target);
+        }
+        search_terms(pattern, self.supports_range_ui(), |term| {
+            match operators.iter().find(|(prefix, _)| term.starts_with(prefix.as_str())) {
+                Some((prefix, operator)) => operator(target, &term[prefix.len()..]),
+                None => self.search_pattern(&term.to_string(), target),
+            }
+        })
+    }
+    // relevance of a match, higher is better; None means no match
+    fn search_score(&self, pattern: &String, target: &String) -> Option<i32> {
+        if self.search_pattern(pattern, target) { Some(0) } else { None }
+    }
+    // byte ranges of `target` matched by `pattern`, highlighted in the popup's value list
+    fn match_spans(&self, pattern: &str, target: &str) -> Vec<Range<usize>> {
+        split_terms(pattern).into_iter()
+            .filter(|term| !term.is_empty() && !term.starts_with(['!', '<', '>', '=']) && !term.contains(".."))
+            .filter(|term| target.starts_with(term.as_str()))
+            .map(|term| 0..term.len())
+            .collect()
+    }
+    // the values of a row the column's selection applies to, several for multi-valued columns
+    fn row_values(&self, t: &T) -> Vec<ScalarValue> {
+        vec![self.get_value(t)]
+    }
+    // every value the column can hold with its display string, for columns whose values
+    // aren't all found in the data. the ones no row holds are listed greyed out in the popup
+    fn all_values(&self) -> Vec<(ScalarValue, String)> {
+        let state = self.column_filter_state();
+        match state.data_source.borrow().clone() {
+            Some(data_source) => source_values(state, self.id(), &*data_source, 0..usize::MAX),
+            None => vec![]
+        }
+    }
+    // the values of all_values() some row holds, the rest are greyed out
+    fn present_values(&self) -> HashSet<ScalarValue> {
+        if self.column_filter_state().data_source.borrow().is_some() {
+            return self.all_values().into_iter().map(|(v, _)| v).collect();
+        }
+        let data = self.column_filter_state().table_filter.backing_data.borrow();
+        self.unique_rows().iter()
+            .map(|i| self.get_value(&data[*i]))
+            .collect()
+    }
+    // drops unselected values that neither a row nor all_values() holds any more, e.g. after the
+    // backing data was replaced, so is_active() doesn't report a filter that hides nothing.
+    // returns whether any were dropped
+    fn prune_stale(&self) -> bool {
+        let mut present = self.present_values();
+        present.extend(self.all_values().into_iter().map(|(v, _)| v));
+        let mut unselected_values = self.column_filter_state().unselected_values.borrow_mut();
+        let len = unselected_values.len();
+        unselected_values.retain(|v| present.contains(v));
+        unselected_values.len() != len
+    }
+    // terms of the pattern that can never match, e.g. a malformed date, listed under the search field
+    fn invalid_terms(&self, _pattern: &String) -> Vec<String> {
+        vec![]
+    }
+    // what search_error() calls the invalid terms, e.g. "Invalid number"
+    fn invalid_terms_label(&self) -> &str {
+        "Invalid"
+    }
+    // why a pattern matches nothing, e.g. "Invalid number: >abc", or None when every term is
+    // well formed. terms going to a search_operator_for_id() closure are left to that closure
+    fn search_error_for(&self, pattern: &str) -> Option<String> {
+        let pattern = self.localized_pattern(pattern).unwrap_or_else(|| pattern.to_string());
+        let operators = self.column_filter_state().operators.borrow();
+        let invalid_terms = self.invalid_terms(&pattern).into_iter()
+            .filter(|term| !operators.iter().any(|(prefix, _)| term.starts_with(prefix.as_str())))
+            .collect::<Vec<_>>();
+        if invalid_terms.is_empty() {
+            None
+        } else {
+            Some(format!("{}: {}", self.invalid_terms_label(), invalid_terms.join(", ")))
+        }
+    }
+    // search_error_for() the current search field
+    fn search_error(&self) -> Option<String> {
+        let pattern = self.column_filter_state().search_field.borrow().clone();
+        self.search_error_for(&pattern)
+    }
+    // whether the popup has a search field, see TableFilter::show_search_for_id()
+    fn shows_search(&self) -> bool {
+        true
+    }
+    // true for numeric columns whose get_value() has an as_i64(), enabling range_slider_for_id()
+    fn supports_range_ui(&self) -> bool {
+        false
+    }
+    // the chrono format of a date column whose get_value() holds epoch days,
+    // which shows from/to date pickers in the popup
+    fn date_pattern(&self) -> Option<&str> {
+        None
+    }
+    // when true, the popup orders matches by descending search_score()
+    fn is_fuzzy(&self) -> bool {
+        false
+    }
+    fn get_string_value(&self, t: &T) -> String {
+        match self.get_str_ref(t) {
+            Some(s) => s.to_string(),
+            None => self.get_value(t).to_string()
+        }
+    }
+    // the text a row's value is listed with in the popup, the blank label for empty strings
+    fn listed_text(&self, t: &T) -> String {
+        let text = self.get_string_value(t);
+        if text.is_empty() {
+            self.column_filter_state().blank_label.borrow().clone()
+        } else {
+            text
+        }
+    }
+    fn evaluate(&self, t: &T) -> bool {
+        let unselected_values = self.column_filter_state().unselected_values.borrow();
+        if unselected_values.is_empty() {
+            return true;
+        }
+        match self.get_str_ref(t) {
+            Some(s) => !unselected_values.contains(&s as &dyn ValueKey),
+            None => !unselected_values.contains(&self.get_value(t))
+        }
+    }
+    fn is_active(&self) -> bool {
+        !self.column_filter_state().unselected_values.borrow().is_empty()
+    }
+    // evaluate() against the given unselected values instead of the column's own
+    fn evaluate_against(&self, t: &T, unselected_values: &FxHashSet<ScalarValue>) -> bool {
+        unselected_values.is_empty() || !unselected_values.contains(&self.get_value(t))
+    }
+    // the current selection as a standalone predicate, e.g. for Iterator::filter in a background
+    // job. the unselected values are copied in, so the selection is frozen as it is at the call and
+    // it holds no RefCell borrow, but it borrows the column for its mappers and can't outlive it.
+    // see TableFilter::predicate_for_id() for a registered column
+    fn as_predicate(&self) -> impl Fn(&T) -> bool + '_ where Self: Sized {
+        let unselected_values = self.column_filter_state().unselected_values.borrow().clone();
+        move |t| self.evaluate_against(t, &unselected_values)
+    }
+    // restores this column's state from egui's persisted memory the first time it is bound,
+    // dropping values no longer in the data, and keeps the stored copy up to date after that
+    #[cfg(feature = "persistence")]
+    fn persist(&self, ctx: &egui::Context) {
+        let state = self.column_filter_state();
+        let memory_id = Id::new(self.id()).with("column_state");
+
+        if !state.restored.replace(true)
+            && let Some(saved) = ctx.data_mut(|data| data.get_persisted::<ColumnState>(memory_id)) {
+            let present = self.distinct_values().into_iter().collect::<FxHashSet<_>>();
+
+            *state.search_field.borrow_mut() = saved.search_field;
+            *state.unselected_values.borrow_mut() = saved.unselected_values.into_iter()
+                .filter(|v| present.contains(v))
+                .collect();
+        }
+
+        let column_state = ColumnState {
+            search_field: state.search_field.borrow().clone(),
+            unselected_values: state.unselected_values.borrow().iter().cloned().collect()
+        };
+        ctx.data_mut(|data| data.insert_persisted(memory_id, column_state));
+    }
+
+    fn bind(&self, response: Response)  {
+        #[cfg(feature = "persistence")]
+        self.persist(&response.ctx);
+
+        // add popup
+        let close_action = self.column_filter_state().close_action.get();
+        let close_behavior = match close_action {
+            _ if self.column_filter_state().date_picker_open.get() => PopupCloseBehavior::IgnoreClicks,
+            CloseAction::KeepOpenUntilApply => PopupCloseBehavior::IgnoreClicks,
+            CloseAction::ApplyOnClose | CloseAction::DiscardOnClose => PopupCloseBehavior::CloseOnClickOutside,
+        };
+        let popup = Popup::menu(&response).id(Id::new(self.id()))
+            .align(RectAlign::default())
+            .gap(4.0)
+            .close_behavior(close_behavior)
+            .width(self.column_filter_state().popup_width.get().unwrap_or(DEFAULT_POPUP_WIDTH))
+            .show(|ui| {
+                ui.vertical(|ui| {
+
+                    let just_opened = self.column_filter_state().snapshot.borrow().is_none();
+                    if just_opened {
+                        self.column_filter_state().table_filter.mark_history_baseline();
+                        self.column_filter_state().last_clicked.borrow_mut().take();
+                    }
+                    self.take_snapshot();
+
+                    // Escape discards everything changed since the popup opened
+                    if ui.input(|input| input.key_pressed(Key::Escape)) {
+                        if self.discard_changes() {
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+                        ui.close();
+                        return;
+                    }
+
+                    let labels = self.column_filter_state().table_filter.popup_labels.borrow().clone();
+                    let show_search = self.column_filter_state().show_search.get().unwrap_or_else(|| self.shows_search());
+                    if show_search {
+                        ui.label(labels.search.as_str());
+                    }
+
+                    {
+                        let mut search_field = self.column_filter_state().search_field.borrow_mut();
+
+                        if show_search {
+                            ui.horizontal(|ui| {
+                                // leave room for the clear button once there is something to clear
+                                let show_clear = !search_field.is_empty();
+                                let search_input = TextEdit::singleline(&mut *search_field)
+                                    .desired_width(ui.available_width() - if show_clear { 24.0 } else { 0.0 });
+
+                                let search_response = ui.add(search_input);
+                                if search_response.changed() {
+                                    self.column_filter_state().last_edit_time.set(ui.input(|input| input.time));
+                                }
+                                if search_response.gained_focus() {
+                                    self.column_filter_state().focused_row.set(None);
+                                } else if self.column_filter_state().focused_row.get().is_some() {
+                                    search_response.surrender_focus();
+                                }
+
+                                if show_clear && ui.small_button("✕").clicked() {
+                                    search_field.clear();
+                                }
+                            });
+                        }
+
+                        if self.column_filter_state().range_slider.get() {
+                            let domain = self.distinct_values().iter()
+                                .filter_map(|v| v.as_i64())
+                                .minmax()
+                                .into_option();
+
+                            if let Some((min, max)) = domain {
+                                let (mut lo, mut hi) = slider_bounds(&search_field, (min, max));
+                                let lo_changed = ui.add(egui::Slider::new(&mut lo, min..=max).text(labels.min.as_str())).changed();
+                                let hi_changed = ui.add(egui::Slider::new(&mut hi, min..=max).text(labels.max.as_str())).changed();
+                                if lo_changed || hi_changed {
+                                    if lo_changed { hi = hi.max(lo); } else { lo = lo.min(hi); }
+                                    *search_field = slider_search(lo, hi, (min, max));
+                                    self.column_filter_state().last_edit_time.set(ui.input(|input| input.time));
+                                }
+                            }
+                        }
+
+                        // from/to pickers reading and writing `a..b`, or `=a` when both are the same day
+                        if let Some(pattern) = self.date_pattern() {
+                            let domain = self.distinct_values().iter()
+                                .filter_map(|v| v.as_i64())
+                                .filter_map(|days| NaiveDate::from_epoch_days(days as i32))
+                                .minmax()
+                                .into_option();
+
+                            if let Some((min, max)) = domain {
+                                let (mut from, mut to) = picked_dates(&search_field, pattern, (min, max));
+                                let mut changed = false;
+                                ui.horizontal(|ui| {
+                                    ui.label(labels.from.as_str());
+                                    let from_changed = ui.add(egui_extras::DatePickerButton::new(&mut from).id_salt("from")).changed();
+                                    ui.label(labels.to.as_str());
+                                    let to_changed = ui.add(egui_extras::DatePickerButton::new(&mut to).id_salt("to")).changed();
+                                    if from_changed { to = to.max(from); } else if to_changed { from = from.min(to); }
+                                    changed = from_changed || to_changed;
+                                    // each calendar is an area of its own, under the id its button keeps its state by
+                                    let picker_open = [Some("from"), Some("to")].into_iter()
+                                        .map(|id_salt| LayerId::new(Order::Foreground, ui.make_persistent_id(id_salt)))
+                                        .any(|layer_id| ui.ctx().memory(|memory| memory.areas().is_visible(&layer_id)));
+                                    self.column_filter_state().date_picker_open.set(picker_open);
+                                });
+                                if changed {
+                                    *search_field = picked_search(from, to, pattern, (min, max));
+                                    self.column_filter_state().last_edit_time.set(ui.input(|input| input.time));
+                                }
+                            }
+                        }
+
+                        if let Some(search_error) = self.search_error_for(&search_field) {
+                            ui.colored_label(ui.visuals().error_fg_color, search_error);
+                        }
+                    }
+
+                    if (show_search || close_action == CloseAction::KeepOpenUntilApply) && ui.input(|input| input.key_pressed(Key::Enter)) {
+                        self.column_filter_state().apply_requested.set(true);
+                    }
+
+                    let filter_array = self.selectable_value_bool_array();
+
+                    // values without rows passing the other columns are greyed out
+                    let visible_counts = visible_counts(self, &filter_array);
+                    let max_count = visible_counts.values().copied().max().unwrap_or(0);
+                    let value_histogram = self.column_filter_state().table_filter.value_histogram.get();
+                    let popup_style = self.column_filter_state().table_filter.popup_style.get();
+
+                    let binding = self.column_filter_state().table_filter.backing_data.borrow();
+
+                    // the sorted unique values are only rebuilt when the data or search changes,
+                    // and a changed search waits until typing has paused for the debounce duration
+                    let (mut listed_data, listed_search) = {
+                        let debounce = self.column_filter_state().search_debounce.get().unwrap_or(DEFAULT_SEARCH_DEBOUNCE);
+                        let wait = debounce_wait(ui.input(|input| input.time), self.column_filter_state().last_edit_time.get(),
+                            debounce, self.column_filter_state().apply_requested.get());
+                        if let Some(wait) = wait {
+                            ui.ctx().request_repaint_after(Duration::from_secs_f64(wait));
+                        }
+                        let settled = wait.is_none();
+
+                        let (rows, listed_search) = settled_listed_rows(self, settled);
+                        (rows.iter().map(|i| &binding[*i]).collect::<Vec<_>>(), listed_search)
+                    };
+                    let search_field_empty = listed_search.is_empty();
+
+                    // hands a settled search to the search provider, showing the local matches with
+                    // a spinner until its answer arrives, then relisting with the answer next frame
+                    match poll_provider_search(self, &listed_search) {
+                        Some(true) => ui.ctx().request_repaint(),
+                        Some(false) => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.weak(labels.searching.as_str());
+                            });
+                            ui.ctx().request_repaint_after(Duration::from_millis(100));
+                        },
+                        None => {}
+                    }
+
+                    // display only filter, layered on the search
+                    let mut only_selected = self.column_filter_state().only_selected.get();
+                    if ui.checkbox(&mut only_selected, labels.only_selected.as_str()).changed() {
+                        self.column_filter_state().only_selected.set(only_selected);
+                    }
+                    arrange_listed(self, &mut listed_data, &visible_counts, self.is_fuzzy() && !search_field_empty);
+
+                    // keyboard navigation, the first Down from the search field moves into the list
+                    let (down, up) = ui.input(|input| (input.key_pressed(Key::ArrowDown), input.key_pressed(Key::ArrowUp)));
+                    let focused_row = move_focus(self.column_filter_state().focused_row.get(), listed_data.len(), down, up);
+                    let scroll_to_focus = down || up;
+                    if let Some(idx) = focused_row && ui.input_mut(|input| input.consume_key(Modifiers::NONE, Key::Space)) {
+                        let v = self.get_value(listed_data[idx]);
+                        let selected = !self.contains(&v);
+                        set_selected(self, vec![v], selected);
+                        self.column_filter_state().table_filter.notify_change(self.id());
+                    }
+                    self.column_filter_state().focused_row.set(focused_row);
+
+                    let text_style = egui::TextStyle::Body;
+                    let row_height = ui.text_style_height(&text_style);
+                    let list_height = self.column_filter_state().popup_max_height.get().unwrap_or(DEFAULT_POPUP_MAX_HEIGHT);
+
+                    let mut scroll_area = ScrollArea::vertical()
+                        .id_salt(self.id())
+                        .min_scrolled_height(list_height)
+                        .max_height(list_height);
+
+                    // reopening returns to where the list was left
+                    if just_opened {
+                        scroll_area = scroll_area.vertical_scroll_offset(self.column_filter_state().scroll_offset.get());
+                    }
+
+                    // scroll just enough to keep the focused row in view
+                    if scroll_to_focus && let Some(idx) = focused_row {
+                        let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
+                        let top = idx as f32 * row_height_with_spacing;
+                        let bottom = top + row_height_with_spacing;
+                        let offset = self.column_filter_state().scroll_offset.get();
+                        if top < offset {
+                            scroll_area = scroll_area.vertical_scroll_offset(top);
+                        } else if bottom > offset + list_height {
+                            scroll_area = scroll_area.vertical_scroll_offset(bottom - list_height);
+                        }
+                    }
+
+                    // values of all_values() not listed by the rows, e.g. unused enum variants, which are
+                    // greyed out, or the values of multi-valued columns. drawn below the list
+                    let data_source = self.column_filter_state().data_source.borrow().clone();
+                    let source_matches = data_source.as_ref()
+                        .filter(|_| !search_field_empty)
+                        .map(|data_source| source_matches(self.column_filter_state(), self.id(), &**data_source, &listed_search, &|text| self.matches_search(&listed_search, &text.to_string())));
+                    let listed_extra = listed_extra(self, &listed_search);
+                    // every listed value. a data source's are only all fetched when asked for
+                    let visible_values = || {
+                        let values = listed_data.iter()
+                            .map(|d| self.get_value(d))
+                            .chain(listed_extra.iter().map(|(v, _)| v.clone()));
+                        match (&data_source, &source_matches) {
+                            (Some(_), Some(matches)) => values.chain(matches.iter().map(|(v, _)| v.clone())).collect(),
+                            (Some(_), None) => values.chain(self.all_values().into_iter().map(|(v, _)| v)).collect(),
+                            (None, _) => values.collect::<Vec<_>>()
+                        }
+                    };
+
+                    // master checkbox over every listed value, indeterminate while only some are selected.
+                    // without a search a data source lists all its values, so the unselected count will do
+                    let master_state = match (&data_source, &source_matches) {
+                        (Some(data_source), None) => {
+                            let count = source_cache(self.column_filter_state(), self.id(), &**data_source).count;
+                            let unselected = self.column_filter_state().unselected_values.borrow().len();
+                            (count > 0).then_some((unselected == 0, unselected > 0 && unselected < count))
+                        }
+                        _ => {
+                            let values = visible_values();
+                            (!values.is_empty()).then(|| tri_state(self, &values))
+                        }
+                    };
+                    if let Some((mut all_selected, indeterminate)) = master_state {
+                        let master = egui::Checkbox::new(&mut all_selected, labels.select_all.as_str())
+                            .indeterminate(indeterminate);
+                        if ui.add(master).clicked() {
+                            set_selected(self, visible_values(), all_selected);
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+                    }
+
+                    // selectable values, grouped under their parents when the column has parent_of_for_id()
+                    let parent_groups = self.column_filter_state().parent_of.borrow().as_ref().map(|parent_of| {
+                        let mut parent_groups = BTreeMap::<String, Vec<&T>>::new();
+                        for d in listed_data.iter() {
+                            parent_groups.entry(parent_of(&self.get_value(d))).or_default().push(*d);
+                        }
+                        parent_groups
+                    });
+                    let scroll_output = match parent_groups {
+                        // a collapsible parent per group whose tri-state checkbox selects or unselects
+                        // its listed children. not virtualized, and keyboard focus isn't drawn
+                        Some(parent_groups) => scroll_area.show(ui, |ui| {
+                            for (parent, children) in parent_groups {
+                                let values = children.iter().map(|d| self.get_value(d)).collect::<Vec<_>>();
+                                let selected = {
+                                    let unselected_values = self.column_filter_state().unselected_values.borrow();
+                                    values.iter().filter(|v| !unselected_values.contains(*v)).count()
+                                };
+                                let collapsing_id = ui.make_persistent_id((self.id(), "parent", parent.as_str()));
+                                egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), collapsing_id, false)
+                                    .show_header(ui, |ui| {
+                                        let mut all_selected = selected == values.len();
+                                        let checkbox = egui::Checkbox::new(&mut all_selected, parent.as_str())
+                                            .indeterminate(selected > 0 && selected < values.len());
+                                        if ui.add(checkbox).clicked() {
+                                            set_selected(self, values, all_selected);
+                                            self.column_filter_state().table_filter.notify_change(self.id());
+                                        }
+                                    })
+                                    .body(|ui| {
+                                        for d in children {
+                                            let v = self.get_value(d);
+                                            let blank = self.get_string_value(d).is_empty();
+                                            let text = self.listed_text(d);
+                                            let spans = if search_field_empty || blank {
+                                                vec![]
+                                            } else {
+                                                self.match_spans(&self.localized_pattern(&listed_search).unwrap_or_else(|| listed_search.clone()), &text)
+                                            };
+                                            let count = visible_counts.get(&v).copied().unwrap_or(0);
+                                            let mut checked = !self.column_filter_state().unselected_values.borrow().contains(&v) && (
+                                                search_field_empty || self.matches_search(&listed_search, &self.get_string_value(d))
+                                            );
+                                            let color = popup_style.value_color(ui, checked, count > 0);
+                                            let label = highlighted_label(ui, &text, spans, color, &popup_style);
+                                            if ui.checkbox(&mut checked, label).clicked() {
+                                                set_selected(self, vec![v], checked);
+                                                self.column_filter_state().table_filter.notify_change(self.id());
+                                            }
+                                        }
+                                    });
+                            }
+                        }),
+                        None => scroll_area
+                            .show_rows(ui, row_height, listed_data.len(), |ui, row_range| {
+
+                                ui.with_layout(
+                                    Layout::top_down(Align::Min)          // left align
+                                        .with_cross_justify(true), |ui| {
+
+                                        listed_data[row_range.clone()].iter()
+                                            .zip(row_range)
+                                            .for_each(|(d, row)| {
+                                                let v = self.get_value(d);
+                                                let text = self.get_string_value(d);
+                                                let blank = text.is_empty();
+                                                let text = if blank {
+                                                    self.column_filter_state().blank_label.borrow().clone()
+                                                } else {
+                                                    text
+                                                };
+                                                let spans = if search_field_empty || blank {
+                                                    vec![]
+                                                } else {
+                                                    self.match_spans(&self.localized_pattern(&listed_search).unwrap_or_else(|| listed_search.clone()), &text)
+                                                };
+                                                let count = visible_counts.get(&v).copied().unwrap_or(0);
+                                                let mut checked = !self.column_filter_state().unselected_values.borrow().contains(&v) && (
+                                                    search_field_empty || self.matches_search(&listed_search, &self.get_string_value(d))
+                                                );
+                                                let color = popup_style.value_color(ui, checked, count > 0);
+                                                let label = highlighted_label(ui, &text, spans, color, &popup_style);
+
+                                                let mut checkbox = ui.checkbox(&mut checked, label);
+                                                if focused_row == Some(row) {
+                                                    checkbox = checkbox.highlight();
+                                                }
+
+                                                // right-aligned frequency bar, drawn behind the end of the row
+                                                if value_histogram && max_count > 0 {
+                                                    let width = MAX_HISTOGRAM_BAR_WIDTH * count as f32 / max_count as f32;
+                                                    let rect = egui::Rect::from_min_max(
+                                                        egui::pos2(checkbox.rect.right() - width, checkbox.rect.top() + 2.0),
+                                                        egui::pos2(checkbox.rect.right(), checkbox.rect.bottom() - 2.0)
+                                                    );
+                                                    let bar_color = popup_style.histogram_color.unwrap_or_else(|| ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+                                                    ui.painter().rect_filled(rect, 2.0, bar_color);
+                                                    checkbox = checkbox.on_hover_text(format!("{} rows", count));
+                                                }
+
+                                                if checkbox.clicked() {
+                                                    let shift = ui.input(|input| input.modifiers.shift);
+                                                    let rows = clicked_rows(self.column_filter_state().last_clicked.borrow().as_ref(), row, &listed_search, shift, listed_data.len());
+                                                    set_selected(self, listed_data[rows].iter().map(|d| self.get_value(d)).collect(), checked);
+                                                    *self.column_filter_state().last_clicked.borrow_mut() = Some((row, listed_search.clone()));
+                                                    self.column_filter_state().table_filter.notify_change(self.id());
+                                                }
+                                            });
+                                    }
+                                );
+                            }),
+                    };
+                    self.column_filter_state().scroll_offset.set(scroll_output.state.offset.y);
+
+                    // checkbox for a value listed apart from the rows above
+                    let extra_checkbox = |ui: &mut egui::Ui, v: ScalarValue, text: String, present: bool| {
+                        let mut checked = self.contains(&v);
+                        let color = popup_style.value_color(ui, checked, present);
+                        let label = egui::RichText::new(text).color(color);
+                        if ui.checkbox(&mut checked, label).clicked() {
+                            set_selected(self, vec![v], checked);
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+                    };
+
+                    // a data source's values are listed below the rows, fetched a page at a time as
+                    // they're scrolled into view
+                    if let Some(data_source) = &data_source {
+                        let row_count = match &source_matches {
+                            Some(matches) => matches.len(),
+                            None => source_cache(self.column_filter_state(), self.id(), &**data_source).count
+                        };
+                        ScrollArea::vertical()
+                            .id_salt((self.id(), "data_source"))
+                            .max_height(list_height)
+                            .show_rows(ui, row_height, row_count, |ui, row_range| {
+                                let values = match &source_matches {
+                                    Some(matches) => matches[row_range].to_vec(),
+                                    None => source_values(self.column_filter_state(), self.id(), &**data_source, row_range)
+                                };
+                                values.into_iter().for_each(|(v, text)| extra_checkbox(ui, v, text, true));
+                            });
+                    }
+
+                    // listed_extra, below the rows
+                    if !listed_extra.is_empty() {
+                        let present = self.present_values();
+                        listed_extra.iter().cloned()
+                            .for_each(|(v, text)| {
+                                let present = present.contains(&v);
+                                extra_checkbox(ui, v, text, present);
+                            });
+                    }
+                    ui.add_space(20.0);
+
+                    // rows kept by the other columns and this column's tentative selection
+                    let kept_rows = cached_kept_row_count(self, &filter_array, &visible_counts, &listed_search);
+                    ui.weak(format!("{} of {} rows", kept_rows, binding.len()));
+
+                    ui.horizontal(|ui| {
+                        // the only way out with KeepOpenUntilApply, so it stays even without a search field
+                        if (show_search || close_action == CloseAction::KeepOpenUntilApply) && ui.button(labels.apply.as_str()).clicked() {
+                            self.column_filter_state().apply_requested.set(true);
+                        }
+                        if self.column_filter_state().apply_requested.get() {
+                            self.apply_search();
+                            self.column_filter_state().apply_requested.set(false);
+                            self.column_filter_state().snapshot.borrow_mut().take();
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                            ui.close();
+                        }
+
+                        // NONE and ALL only touch the listed values, so with a search active
+                        // the values hidden by the search keep their current selection
+                        if ui.button(labels.none.as_str()).clicked() {
+                            self.select_none(visible_values());
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+
+                        if ui.button(labels.all.as_str()).clicked() {
+                            self.select_all(visible_values());
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+
+                        // flips only the values currently listed, respecting the search field
+                        if ui.button(labels.invert.as_str()).clicked() {
+                            self.invert_selection(visible_values());
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                        }
+
+                        // only this column, the toolbar's CLEAR ALL resets every column
+                        if ui.button(labels.reset.as_str()).clicked() {
+                            self.reset();
+                            self.column_filter_state().snapshot.borrow_mut().take();
+                            self.column_filter_state().table_filter.notify_change(self.id());
+                            ui.close();
+                        }
+                    });
+                });
+            });
+
+        if popup.is_none() {
+            popup_closed(self, close_action);
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::column_filters::{NaiveDateColumnFilter, StringColumnFilter, U32ColumnFilter};
+    use crate::data_source::InMemoryDataSource;
+
+    struct Row {
+        city: &'static str,
+        miles: u32
+    }
+
+    fn row(city: &'static str, miles: u32) -> Row {
+        Row { city, miles }
+    }
+
+    fn table_filter(rows: Vec<Row>) -> Rc<TableFilter<Row>> {
+        let tf = TableFilter::new(&Rc::new(RefCell::new(rows)));
+        tf.column_filter(Box::new(StringColumnFilter::new("city", Rc::clone(&tf), Box::new(|r: &Row| r.city.to_string()))));
+        tf.column_filter(Box::new(U32ColumnFilter::new("miles", Rc::clone(&tf), Box::new(|r: &Row| r.miles), Box::new(|r: &Row| r.miles.to_string()))));
+        tf
+    }
+
+    fn with_column<T, R>(tf: &TableFilter<T>, id: &str, f: impl FnOnce(&dyn ColumnFilter<T>) -> R) -> R {
+        f(tf.column_filters.borrow().iter().find(|cf| cf.id() == id).unwrap().as_ref())
+    }
+
+    // the column's values left selected, in sorted order
+    fn selected(tf: &TableFilter<Row>, id: &str) -> Vec<String> {
+        with_column(tf, id, |cf| tf.backing_data.borrow().iter()
+            .map(|r| cf.get_value(r))
+            .filter(|v| cf.contains(v))
+            .map(|v| v.to_string())
+            .unique()
+            .sorted()
+            .collect())
+    }
+
+    fn text_centers(shape: &egui::Shape, centers: &mut Vec<(String, egui::Pos2)>) {
+        match shape {
+            egui::Shape::Text(text) => centers.push((text.galley.text().to_string(), text.visual_bounding_rect().center())),
+            egui::Shape::Vec(shapes) => shapes.iter().for_each(|shape| text_centers(shape, centers)),
+            _ => {}
+        }
+    }
+
+    // runs one egui frame with the column's header bound, returning where each text was drawn
+    fn run_frame(ctx: &egui::Context, tf: &TableFilter<Row>, id: &str, input: egui::RawInput) -> Vec<(String, egui::Pos2)> {
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let response = ui.button(id);
+                tf.bind_for_id(id, response);
+            });
+        });
+        let mut centers = vec![];
+        output.shapes.iter().for_each(|clipped| text_centers(&clipped.shape, &mut centers));
+        centers
+    }
+
+    // runs one egui frame with the column's header bound, the popup open or closed
+    fn popup_frame(ctx: &egui::Context, tf: &TableFilter<Row>, id: &str, open: bool) {
+        if open {
+            Popup::open_id(ctx, Id::new(id));
+        } else {
+            Popup::close_id(ctx, Id::new(id));
+        }
+        run_frame(ctx, tf, id, Default::default());
+    }
+
+    // types into the column's search field without applying it, like the popup's text field
+    fn type_search<T>(tf: &TableFilter<T>, id: &str, pattern: &str) {
+        with_column(tf, id, |cf| cf.column_filter_state().search_field.replace(pattern.to_string()));
+    }
+
+    // opens the column's popup and lets it lay out
+    fn open_popup(ctx: &egui::Context, tf: &TableFilter<Row>, id: &str) -> Vec<(String, egui::Pos2)> {
+        Popup::open_id(ctx, Id::new(id));
+        run_frame(ctx, tf, id, Default::default());
+        run_frame(ctx, tf, id, Default::default())
+    }
+
+    // clicks the text drawn in the previous frame, e.g. a popup button or value
+    fn click_text(ctx: &egui::Context, tf: &TableFilter<Row>, id: &str, centers: &[(String, egui::Pos2)], text: &str, modifiers: egui::Modifiers) -> Vec<(String, egui::Pos2)> {
+        click_at(ctx, tf, id, centers.iter().find(|(t, _)| t == text).unwrap().1, modifiers)
+    }
+
+    fn click_at(ctx: &egui::Context, tf: &TableFilter<Row>, id: &str, pos: egui::Pos2, modifiers: egui::Modifiers) -> Vec<(String, egui::Pos2)> {
+        let button = |pressed| egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers };
+        run_frame(ctx, tf, id, egui::RawInput { events: vec![egui::Event::PointerMoved(pos), button(true)], modifiers, ..Default::default() });
+        run_frame(ctx, tf, id, egui::RawInput { events: vec![button(false)], modifiers, ..Default::default() })
+    }
+
+    #[test]
+    fn none_and_all_leave_values_hidden_by_the_search() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHL", 200), row("LAX", 300)]);
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "city");
+        let search = |pattern: &str| type_search(&tf, "city", pattern);
+
+        search("PH");
+        let centers = click_text(&ctx, &tf, "city", &centers, "NONE", egui::Modifiers::NONE);
+        assert_eq!(selected(&tf, "city"), ["LAX"]);
+
+        search("PHX");
+        let centers = click_text(&ctx, &tf, "city", &centers, "ALL", egui::Modifiers::NONE);
+        assert_eq!(selected(&tf, "city"), ["LAX", "PHX"]);
+
+        search("L");
+        click_text(&ctx, &tf, "city", &centers, "INVERT", egui::Modifiers::NONE);
+        assert_eq!(selected(&tf, "city"), ["PHL", "PHX"]);
+    }
+
+    #[test]
+    fn escape_discards_toggles_since_the_popup_opened() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHL", 200), row("LAX", 300)]);
+        let city = |v: &str| ScalarValue::Str(v.to_string());
+
+        tf.exclude_value_for_id("city", city("LAX"));
+        with_column(&tf, "city", |cf| cf.take_snapshot());
+        tf.set_selected_values_for_id("city", [city("LAX")]);
+        type_search(&tf, "city", "PH");
+        assert!(with_column(&tf, "city", |cf| cf.discard_changes()));
+        assert!(tf.export_state().columns["city"].search_field.is_empty());
+        assert_eq!(selected(&tf, "city"), ["PHL", "PHX"]);
+
+        // nothing left to discard once the popup has closed
+        with_column(&tf, "city", |cf| assert!(!cf.discard_changes()));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_evals_match_evaluate() {
+        let cities = ["PHX", "LAX", "ATL"];
+        let tf = table_filter((0..20_000).map(|i| row(cities[i % 3], i as u32 % 700)).collect());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_values_for_id("miles", (0..300).map(ScalarValue::U32));
+        let expected = tf.backing_data.borrow().iter().map(|r| tf.evaluate(r)).collect::<Vec<_>>();
+
+        tf.parallel_mapper_for_id("city", |r| ScalarValue::Str(r.city.to_string()));
+        tf.parallel_mapper_for_id("miles", |r| ScalarValue::U32(r.miles));
+        tf.column_filters.borrow().iter().for_each(|cf| assert!(cf.column_filter_state().parallel_evals.borrow().is_some()));
+        assert_eq!(*tf.evaluate_array(), expected);
+    }
+
+    fn flights() -> Vec<Row> {
+        vec![row("PHX", 100), row("LAX", 200), row("ATL", 300), row("PHX", 400)]
+    }
+
+    #[test]
+    fn exported_state_reattaches_by_column_id() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        type_search(&tf, "miles", ">150");
+        let state = tf.export_state();
+        assert_eq!(state.columns["city"].unselected_values, [ScalarValue::Str("LAX".to_string())]);
+        assert_eq!(state.columns["miles"].search_field, ">150");
+
+        // a table registering the columns in another order, plus one the state doesn't know
+        let restored = TableFilter::new(&Rc::new(RefCell::new(flights())));
+        restored.column_filter(Box::new(U32ColumnFilter::new("miles", Rc::clone(&restored), Box::new(|r: &Row| r.miles), Box::new(|r: &Row| r.miles.to_string()))));
+        restored.column_filter(Box::new(StringColumnFilter::new("gate", Rc::clone(&restored), Box::new(|_: &Row| "A1".to_string()))));
+        restored.column_filter(Box::new(StringColumnFilter::new("city", Rc::clone(&restored), Box::new(|r: &Row| r.city.to_string()))));
+        restored.import_state(&state);
+        assert_eq!(restored.export_state().columns["city"], state.columns["city"]);
+        assert_eq!(restored.export_state().columns["miles"], state.columns["miles"]);
+        assert_eq!(*restored.evaluate_array(), *tf.evaluate_array());
+        assert!(!restored.is_active_for_id("gate"));
+    }
+
+    #[test]
+    fn query_string_round_trips_delimiters_in_ids_and_values() {
+        let cities = ["São Paulo", "a=b&c", "100% off", "Zürich, CH", "", "PHX"];
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(cities.iter().zip(1..).map(|(city, miles)| row(city, miles)).collect())))
+            .string_ref("city, région=&%", |r: &Row| r.city)
+            .u32("miles", |r| r.miles)
+            .u32("miles.search", |r| r.miles * 10)
+            .build();
+        let column = |search_field: &str, unselected_values: Vec<ScalarValue>| ColumnState { search_field: search_field.to_string(), unselected_values };
+        let state = TableFilterState { columns: BTreeMap::from([
+            ("city, région=&%".to_string(), column("São,\"a=b&c\"", strs(&["", "100% off", "Zürich, CH", "a=b&c"]))),
+            ("miles".to_string(), column("", vec![ScalarValue::U32(2)])),
+            ("miles.search".to_string(), column("10..30", vec![ScalarValue::U32(60)])),
+        ]) };
+        tf.import_state(&state);
+        let query = tf.to_query_string();
+        // one parameter per unselected list and search field, none split by the delimiters inside them
+        assert_eq!(query.split('&').count(), 5);
+        assert!(query.split('&').all(|param| param.matches('=').count() == 1));
+
+        tf.reset_all();
+        tf.apply_query_string(&format!("gate=A1&{}&gate.search=B", query));
+        assert_eq!(tf.export_state(), state);
+
+        // a query written by hand may leave dots and non-ASCII unescaped
+        tf.apply_query_string("miles.search=10..20&city,%20région%3D%26%25=PHX");
+        assert_eq!(tf.export_state().columns["miles.search"], column("", vec![]));
+        assert_eq!(tf.export_state().columns["miles"], column("10..20", vec![]));
+        assert_eq!(tf.export_state().columns["city, région=&%"], column("", strs(&["PHX"])));
+    }
+
+    #[test]
+    fn malformed_percent_escapes_decode_leniently() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%+1%4"), "%zz%+1%4");
+        assert_eq!(percent_decode("%C3%A9%2C"), "é,");
+        // a truncated UTF-8 sequence
+        assert_eq!(percent_decode("S%C3o"), "S\u{FFFD}o");
+
+        let tf = table_filter(flights());
+        tf.apply_query_string("city=%C3,PHX&miles.search=%");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX"]));
+        assert_eq!(tf.export_state().columns["miles"].search_field, "%");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn exported_state_round_trips_through_json() {
+        let tf = table_filter(flights());
+        tf.exclude_values_for_id("miles", [ScalarValue::U32(200), ScalarValue::U32(400)]);
+        let state = tf.export_state();
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<TableFilterState>(&json).unwrap(), state);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn persisted_state_restores_without_stale_values() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let memory_id = Id::new("city").with("column_state");
+        ctx.data_mut(|data| data.insert_persisted(memory_id, ColumnState {
+            search_field: "L".to_string(),
+            unselected_values: vec![ScalarValue::Str("LAX".to_string()), ScalarValue::Str("SEA".to_string())]
+        }));
+
+        run_frame(&ctx, &tf, "city", egui::RawInput::default());
+        let restored = ColumnState { search_field: "L".to_string(), unselected_values: vec![ScalarValue::Str("LAX".to_string())] };
+        assert_eq!(tf.export_state().columns["city"], restored);
+        assert_eq!(ctx.data_mut(|data| data.get_persisted::<ColumnState>(memory_id)), Some(restored));
+
+        // later changes are saved on the next frame rather than restored over
+        tf.set_selected_values_for_id("city", [ScalarValue::Str("ATL".to_string())]);
+        run_frame(&ctx, &tf, "city", egui::RawInput::default());
+        assert_eq!(tf.selected_values_for_id("city"), [ScalarValue::Str("ATL".to_string())]);
+        assert_eq!(ctx.data_mut(|data| data.get_persisted::<ColumnState>(memory_id)).unwrap().unselected_values.len(), 2);
+    }
+
+    // the cities of the rows in the order a sort leaves them
+    fn sorted_cities(rows: &[Row], sort: impl FnOnce(&mut [&Row])) -> Vec<&'static str> {
+        let mut refs = rows.iter().collect::<Vec<_>>();
+        sort(&mut refs);
+        refs.iter().map(|r| r.city).collect()
+    }
+
+    #[test]
+    fn sort_by_id_orders_strings_numbers_and_dates() {
+        let tf = table_filter(Vec::new());
+        tf.column_filter(Box::new(NaiveDateColumnFilter::new("day", Rc::clone(&tf), "%m/%d/%Y".to_string(), Box::new(|r: &Row| NaiveDate::from_yo_opt(2026, 400 - r.miles).unwrap()))));
+        let rows = [row("PHX", 300), row("ATL", 100), row("LAX", 250)];
+
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_by_id("city", SortDirection::Ascending, refs)), ["ATL", "LAX", "PHX"]);
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_by_id("city", SortDirection::Descending, refs)), ["PHX", "LAX", "ATL"]);
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_by_id("miles", SortDirection::Ascending, refs)), ["ATL", "LAX", "PHX"]);
+        // the days count down as the miles go up
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_by_id("day", SortDirection::Ascending, refs)), ["PHX", "LAX", "ATL"]);
+
+        // the header button cycles none -> ascending -> descending -> none
+        tf.cycle_sort_for_id("miles", false);
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_rows(refs)), ["ATL", "LAX", "PHX"]);
+        tf.cycle_sort_for_id("miles", false);
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_rows(refs)), ["PHX", "LAX", "ATL"]);
+        tf.cycle_sort_for_id("miles", false);
+        assert_eq!(sorted_cities(&rows, |refs| tf.sort_rows(refs)), ["PHX", "ATL", "LAX"]);
+    }
+
+    #[test]
+    fn secondary_sort_keys_break_ties() {
+        let tf = table_filter(Vec::new());
+        let rows = [row("PHX", 300), row("ATL", 200), row("PHX", 100), row("ATL", 400)];
+        let miles = |rows: &[Row], sort: &dyn Fn(&mut [&Row])| {
+            let mut refs = rows.iter().collect::<Vec<_>>();
+            sort(&mut refs);
+            refs.iter().map(|r| r.miles).collect::<Vec<_>>()
+        };
+
+        tf.cycle_sort_for_id("city", false);
+        tf.cycle_sort_for_id("miles", true);
+        assert_eq!(miles(&rows, &|refs| tf.sort_rows(refs)), [200, 400, 100, 300]);
+        tf.cycle_sort_for_id("miles", true);
+        assert_eq!(miles(&rows, &|refs| tf.sort_rows(refs)), [400, 200, 300, 100]);
+        assert_eq!(tf.sort_keys(), vec![
+            SortState { id: "city".to_string(), direction: SortDirection::Ascending },
+            SortState { id: "miles".to_string(), direction: SortDirection::Descending },
+        ]);
+
+        // without shift the clicked column cycles on as the only key
+        tf.cycle_sort_for_id("city", false);
+        assert_eq!(tf.sort_keys(), vec![SortState { id: "city".to_string(), direction: SortDirection::Descending }]);
+        // ties keep their order from before the sort
+        assert_eq!(miles(&rows, &|refs| tf.sort_rows(refs)), [300, 100, 200, 400]);
+    }
+
+    #[test]
+    fn sort_comparator_overrides_value_order() {
+        let tf = table_filter(Vec::new());
+        let gates = [row("A10", 0), row("A9", 0), row("A2", 0), row("B1", 0)];
+        assert_eq!(sorted_cities(&gates, |refs| tf.sort_by_id("city", SortDirection::Ascending, refs)), ["A10", "A2", "A9", "B1"]);
+
+        tf.sort_comparator_for_id("city", Box::new(|a: &Row, b: &Row| crate::column_filters::natural_cmp(a.city, b.city)));
+        assert_eq!(sorted_cities(&gates, |refs| tf.sort_by_id("city", SortDirection::Ascending, refs)), ["A2", "A9", "A10", "B1"]);
+        assert_eq!(sorted_cities(&gates, |refs| tf.sort_by_id("city", SortDirection::Descending, refs)), ["B1", "A10", "A9", "A2"]);
+    }
+
+    #[test]
+    fn export_csv_quotes_per_rfc_4180() {
+        let tf = table_filter(vec![
+            row("Phoenix, AZ", 100),
+            row("\"Big\" Apple", 200),
+            row("Line\nBreak", 300),
+            row("LAX", 400),
+        ]);
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+
+        let mut csv = Vec::new();
+        tf.export_csv(&tf.backing_data.borrow(), &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(),
+            "city,miles\r\n\"Phoenix, AZ\",100\r\n\"\"\"Big\"\" Apple\",200\r\n\"Line\nBreak\",300\r\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn export_json_keeps_numbers_unquoted() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("PHX".to_string()));
+
+        let json = tf.export_json(&tf.backing_data.borrow());
+        assert_eq!(json, serde_json::json!([
+            { "city": "LAX", "miles": 200 },
+            { "city": "ATL", "miles": 300 },
+        ]));
+        assert_eq!(json.to_string(), r#"[{"city":"LAX","miles":200},{"city":"ATL","miles":300}]"#);
+    }
+
+    #[derive(egui_table_filter_derive::TableFilter)]
+    struct DerivedRow {
+        #[filter(string)]
+        city: String,
+        #[filter(u32)]
+        miles: u32
+    }
+
+    #[test]
+    fn derive_registers_a_column_per_marked_field() {
+        let data = Rc::new(RefCell::new(vec![
+            DerivedRow { city: "PHX".to_string(), miles: 100 },
+            DerivedRow { city: "LAX".to_string(), miles: 200 },
+        ]));
+        let tf = DerivedRow::table_filter(&data);
+        assert_eq!(tf.column_filters.borrow().iter().map(|cf| cf.id().to_string()).collect::<Vec<_>>(), ["city", "miles"]);
+
+        tf.exclude_value_for_id("miles", ScalarValue::U32(200));
+        assert_eq!(*tf.evaluate_array(), [true, false]);
+    }
+
+    #[test]
+    fn excluded_column_keeps_its_own_values_selectable() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true, false]);
+        // LAX stays selectable in the city popup, only the miles filter greys out rows
+        assert_eq!(tf.evaluate_array_excluding_id("city"), vec![true, true, true, false]);
+        assert_eq!(tf.evaluate_array_excluding_id("miles"), vec![true, false, true, true]);
+        // an unknown id excludes nothing
+        assert_eq!(tf.evaluate_array_excluding_id("gate"), *tf.evaluate_array());
+    }
+
+    #[test]
+    fn columns_combine_with_and_or_or() {
+        let tf = table_filter(flights());
+        tf.combine(Combine::Or);
+        assert_eq!(*tf.evaluate_array(), vec![true; 4]);
+
+        tf.exclude_value_for_id("city", ScalarValue::Str("PHX".to_string()));
+        // inactive columns don't let every row through
+        assert_eq!(*tf.evaluate_array(), vec![false, true, true, false]);
+
+        tf.set_selected_values_for_id("miles", [ScalarValue::U32(100)]);
+        assert_eq!(*tf.evaluate_array(), vec![true, true, true, false]);
+        assert!(tf.backing_data.borrow().iter().map(|r| tf.evaluate(r)).eq([true, true, true, false]));
+        assert_eq!(tf.evaluate_array_excluding_id("city"), vec![true; 4]);
+
+        tf.combine(Combine::And);
+        assert_eq!(*tf.evaluate_array(), vec![false; 4]);
+        assert!(tf.backing_data.borrow().iter().all(|r| !tf.evaluate(r)));
+    }
+
+    #[test]
+    fn activeness_follows_the_selection() {
+        let tf = table_filter(flights());
+        assert!(tf.active_column_ids().is_empty());
+
+        tf.exclude_value_for_id("miles", ScalarValue::U32(200));
+        assert!(tf.is_active_for_id("miles"));
+        assert_eq!(tf.active_column_ids(), ["miles"]);
+
+        tf.set_selected_values_for_id("city", [ScalarValue::Str("ATL".to_string())]);
+        assert_eq!(tf.active_column_ids(), ["city", "miles"]);
+
+        // selecting every value again makes the column inactive
+        tf.set_selected_values_for_id("miles", [100, 200, 300, 400].map(ScalarValue::U32));
+        assert!(!tf.is_active_for_id("miles"));
+        assert_eq!(tf.active_column_ids(), ["city"]);
+        assert!(with_column(&tf, "city", |cf| cf.is_active()));
+    }
+
+    #[test]
+    fn reset_all_clears_every_column() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+        type_search(&tf, "miles", ">100");
+
+        tf.reset_for_id("city");
+        assert_eq!(tf.active_column_ids(), ["miles"]);
+
+        tf.reset_all();
+        assert!(tf.active_column_ids().is_empty());
+        assert!(tf.export_state().columns.values().all(|column| *column == ColumnState::default()));
+        assert_eq!(*tf.evaluate_array(), vec![true; 4]);
+
+        // the old name still clears every column
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+        #[allow(deprecated)]
+        tf.reset();
+        assert!(tf.active_column_ids().is_empty());
+    }
+
+    #[test]
+    fn resetting_one_column_leaves_the_others() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+
+        tf.reset_for_id("miles");
+        assert!(!tf.is_active_for_id("miles"));
+        assert_eq!(selected(&tf, "city"), ["ATL", "PHX"]);
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn apply_keeps_checkboxes_toggled_within_the_search() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHL", 200), row("LAX", 300)]);
+        let ctx = egui::Context::default();
+        open_popup(&ctx, &tf, "city");
+        let search = |pattern: &str| type_search(&tf, "city", pattern);
+
+        // unticking PHX in the search results
+        search("PH");
+        let centers = run_frame(&ctx, &tf, "city", egui::RawInput::default());
+        let centers = click_text(&ctx, &tf, "city", &centers, "PHX", egui::Modifiers::NONE);
+        click_text(&ctx, &tf, "city", &centers, "APPLY", egui::Modifiers::NONE);
+        assert_eq!(selected(&tf, "city"), ["PHL"]);
+
+        // the toggle counts only while the popup is open
+        run_frame(&ctx, &tf, "city", egui::RawInput::default());
+        open_popup(&ctx, &tf, "city");
+        search("PH");
+        with_column(&tf, "city", |cf| cf.column_filter_state().apply_requested.set(true));
+        run_frame(&ctx, &tf, "city", egui::RawInput::default());
+        assert_eq!(selected(&tf, "city"), ["PHL", "PHX"]);
+    }
+
+    #[test]
+    fn degenerate_tables_evaluate_without_panicking() {
+        let no_columns = TableFilter::new(&Rc::new(RefCell::new(flights())));
+        assert_eq!(*no_columns.evaluate_array(), vec![true; 4]);
+        assert_eq!(no_columns.evaluate_array_excluding_id("city"), vec![true; 4]);
+
+        let no_rows = table_filter(Vec::new());
+        no_rows.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert!(no_rows.evaluate_array().is_empty());
+        assert!(no_rows.evaluate_array_excluding_id("city").is_empty());
+        assert_eq!(no_rows.counts(), (0, 0));
+    }
+
+    #[test]
+    fn edited_cell_refilters_after_mark_dirty() {
+        struct Flight {
+            gate: RefCell<String>
+        }
+        let flight = |gate: &str| Flight { gate: RefCell::new(gate.to_string()) };
+        let data = Rc::new(RefCell::new(vec![flight("A1"), flight("B2")]));
+        let tf = TableFilter::builder(&data)
+            .string("gate", |f: &Flight| f.gate.borrow().clone())
+            .build();
+        tf.exclude_value_for_id("gate", ScalarValue::Str("B2".to_string()));
+        assert_eq!(*tf.evaluate_array(), vec![true, false]);
+
+        *data.borrow()[0].gate.borrow_mut() = "B2".to_string();
+        // the cached result stands until the edit is reported
+        assert_eq!(*tf.evaluate_array(), vec![true, false]);
+        tf.mark_dirty();
+        assert_eq!(*tf.evaluate_array(), vec![false, false]);
+    }
+
+    #[test]
+    fn edited_value_is_listed_after_mark_dirty() {
+        let tf = table_filter(vec![row("PHX", 100), row("LAX", 200), row("PHX", 300)]);
+        let ctx = egui::Context::default();
+        let listed = |centers: &[(String, egui::Pos2)], city: &str| centers.iter().any(|(text, _)| text == city);
+        assert!(!listed(&open_popup(&ctx, &tf, "city"), "SEA"));
+
+        tf.backing_data().borrow_mut()[2].city = "SEA";
+        // the data kept its length, so the unique values stand until the edit is reported
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        assert!(!listed(&open_popup(&ctx, &tf, "city"), "SEA"));
+
+        tf.mark_dirty();
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX", "SEA"]));
+        assert!(listed(&open_popup(&ctx, &tf, "city"), "SEA"));
+    }
+
+    #[test]
+    fn visible_counts_count_rows_passing_the_other_columns() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHX", 100), row("LAX", 200), row("PHX", 300), row("ATL", 100)]);
+        tf.exclude_value_for_id("miles", ScalarValue::U32(300));
+        // the city column's own selection doesn't change its counts
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+
+        let counts = with_column(&tf, "city", |cf| visible_counts(cf, &cf.selectable_value_bool_array()));
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[&ScalarValue::Str("PHX".to_string())], 2);
+        assert_eq!(counts[&ScalarValue::Str("LAX".to_string())], 1);
+        assert_eq!(counts[&ScalarValue::Str("ATL".to_string())], 1);
+    }
+
+    // the popup footer's count for a column, as it is before APPLY
+    fn kept_rows(tf: &TableFilter<Row>, id: &str, search: &str) -> usize {
+        with_column(tf, id, |cf| {
+            let filter_array = cf.selectable_value_bool_array();
+            cached_kept_row_count(cf, &filter_array, &visible_counts(cf, &filter_array), &search.to_string())
+        })
+    }
+
+    #[test]
+    fn footer_counts_the_rows_the_tentative_selection_keeps() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHX", 100), row("LAX", 200), row("PHX", 300), row("ATL", 100)]);
+        tf.exclude_value_for_id("miles", ScalarValue::U32(300));
+        assert_eq!(kept_rows(&tf, "city", ""), 4);
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        assert_eq!(kept_rows(&tf, "city", ""), 3);
+        // a pending search narrows the count before it's applied, and a search matching nothing keeps nothing
+        assert_eq!(kept_rows(&tf, "city", "L"), 1);
+        assert_eq!(kept_rows(&tf, "city", "ATL"), 0);
+        assert_eq!(kept_rows(&tf, "city", "SEA"), 0);
+        // the miles column counts its own values against the city selection
+        assert_eq!(kept_rows(&tf, "miles", ""), 3);
+        assert_eq!(kept_rows(&tf, "miles", ">150"), 1);
+
+        // counted again once the data changes
+        tf.backing_data().borrow_mut()[4].city = "PHX";
+        tf.mark_dirty();
+        assert_eq!(kept_rows(&tf, "city", ""), 4);
+
+        assert_eq!(kept_rows(&table_filter(vec![]), "city", ""), 0);
+    }
+
+    #[test]
+    fn footer_counts_rows_of_multi_valued_columns() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![row("PHX/LAX", 1), row("LAX", 2), row("ATL/PHX", 3)])))
+            .multi_value("city", |r: &Row| r.city.split('/').map(str::to_string).collect())
+            .u32("miles", |r| r.miles)
+            .build();
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(kept_rows(&tf, "city", ""), 2);
+        tf.exclude_value_for_id("miles", ScalarValue::U32(3));
+        assert_eq!(kept_rows(&tf, "city", ""), 1);
+    }
+
+    #[test]
+    fn footer_count_follows_checkbox_toggles_before_apply() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "city");
+        assert!(centers.iter().any(|(text, _)| text == "4 of 4 rows"));
+
+        click_text(&ctx, &tf, "city", &centers, "PHX", Modifiers::NONE);
+        let centers = run_frame(&ctx, &tf, "city", Default::default());
+        assert!(centers.iter().any(|(text, _)| text == "2 of 4 rows"));
+    }
+
+    fn strs(values: &[&str]) -> Vec<ScalarValue> {
+        values.iter().map(|v| ScalarValue::Str(v.to_string())).collect()
+    }
+
+    #[test]
+    fn selected_values_round_trip() {
+        let tf = table_filter(flights());
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX", "PHX"]));
+
+        tf.set_selected_values_for_id("city", strs(&["PHX", "ATL"]));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "PHX"]));
+        assert_eq!(tf.export_state().columns["city"].unselected_values, strs(&["LAX"]));
+
+        // values missing from the data are ignored
+        tf.set_selected_values_for_id("miles", [ScalarValue::U32(200), ScalarValue::U32(999)]);
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(200)]);
+        assert_eq!(*tf.evaluate_array(), vec![false, false, false, false]);
+
+        tf.set_selected_values_for_id("miles", tf.selected_values_for_id("miles").into_iter().chain([ScalarValue::U32(100)]));
+        assert_eq!(*tf.evaluate_array(), vec![true, false, false, false]);
+        assert!(tf.selected_values_for_id("gate").is_empty());
+    }
+
+    #[test]
+    fn apply_search_selects_the_matching_values() {
+        let tf = table_filter(vec![row("PHX", 100), row("PHL", 200), row("LAX", 300), row("ATL", 400)]);
+        tf.apply_search_for_id("city", "PH,A");
+        assert_eq!(tf.export_state().columns["city"].unselected_values, strs(&["LAX"]));
+
+        tf.apply_search_for_id("miles", "150..300");
+        let unselected_miles = tf.export_state().columns["miles"].unselected_values.clone();
+        assert_eq!(unselected_miles, [ScalarValue::U32(100), ScalarValue::U32(400)]);
+        assert_eq!(*tf.evaluate_array(), vec![false, true, false, false]);
+
+        // the search field is cleared once applied
+        assert!(tf.export_state().columns.values().all(|column| column.search_field.is_empty()));
+    }
+
+    #[test]
+    fn apply_search_handles_empty_data_invalid_patterns_and_delimiters() {
+        // nothing to select on an empty table, and unknown ids are ignored
+        let empty = table_filter(vec![]);
+        empty.apply_search_for_id("city", "PH");
+        empty.apply_search_for_id("gate", "A1");
+        assert!(empty.selected_values_for_id("city").is_empty());
+        assert!(empty.active_column_ids().is_empty());
+
+        let tf = table_filter(vec![row("Dallas, TX", 100), row("a=b&c", 200), row("", 300), row("PHX", 400)]);
+        // a malformed term selects nothing, the valid alternatives still select their values
+        tf.apply_search_for_id("miles", ">abc");
+        assert!(tf.selected_values_for_id("miles").is_empty());
+        tf.apply_search_for_id("miles", "1..x,300..400");
+        assert_eq!(tf.selected_values_for_id("miles"), [300, 400].map(ScalarValue::U32));
+
+        // values holding the delimiters match when quoted or escaped, and "" selects blanks
+        tf.apply_search_for_id("city", r#"=a=b&c,"Dallas, TX",="""#);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["", "Dallas, TX", "a=b&c"]));
+        tf.apply_search_for_id("city", r"=Dallas\, TX");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["Dallas, TX"]));
+        // an empty pattern leaves the selection as it is
+        tf.apply_search_for_id("city", "");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["Dallas, TX"]));
+    }
+
+    // the ids on_change was called with, each along with whether its column was active by then
+    fn change_log(tf: &Rc<TableFilter<Row>>) -> Rc<RefCell<Vec<(String, bool)>>> {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let weak = Rc::downgrade(tf);
+        let callback_log = Rc::clone(&log);
+        tf.on_change(move |id| {
+            let active = weak.upgrade().is_some_and(|tf| tf.is_active_for_id(id));
+            callback_log.borrow_mut().push((id.to_string(), active));
+        });
+        log
+    }
+
+    #[test]
+    fn on_change_fires_once_per_change_after_it_is_made() {
+        let tf = table_filter(flights());
+        let log = change_log(&tf);
+
+        tf.set_selected_values_for_id("city", strs(&["ATL", "PHX"]));
+        tf.apply_search_for_id("miles", ">100");
+        tf.set_selected_values_for_id("city", strs(&["ATL", "LAX", "PHX"]));
+        // evaluating isn't a change
+        tf.evaluate_array();
+
+        assert_eq!(*log.borrow(), [
+            ("city".to_string(), true),
+            ("miles".to_string(), true),
+            ("city".to_string(), false),
+        ]);
+    }
+
+    #[test]
+    fn changes_from_code_notify_each_column_they_change() {
+        let tf = table_filter(flights());
+        tf.set_selected_values_for_id("city", strs(&["ATL", "PHX"]));
+        tf.apply_search_for_id("miles", ">100");
+        tf.save_preset("both");
+        let query = tf.to_query_string();
+        let log = change_log(&tf);
+        let drain = || log.borrow_mut().drain(..).map(|(id, _)| id).collect::<Vec<_>>();
+
+        tf.reset_all();
+        assert_eq!(drain(), ["city", "miles"]);
+        // nothing left to reset
+        tf.reset_all();
+        assert!(drain().is_empty());
+        assert!(tf.undo());
+        assert_eq!(drain(), ["city", "miles"]);
+        assert!(tf.redo());
+        assert_eq!(drain(), ["city", "miles"]);
+        assert!(tf.load_preset("both"));
+        assert_eq!(drain(), ["city", "miles"]);
+        tf.reset_for_id("miles");
+        assert_eq!(drain(), ["miles"]);
+        tf.apply_query_string(&query);
+        assert_eq!(drain(), ["miles"]);
+        tf.import_state(&TableFilterState::default());
+        assert!(drain().is_empty());
+
+        // each is one undoable step
+        assert!(tf.undo());
+        assert_eq!(tf.selected_values_for_id("miles").len(), 4);
+    }
+
+    #[test]
+    fn indicator_tooltip_lists_the_hidden_values() {
+        let tf = table_filter((1..=14).map(|miles| row("PHX", miles)).collect());
+        tf.set_selected_values_for_id("miles", [13, 14].map(ScalarValue::U32));
+        assert_eq!(with_column(&tf, "miles", indicator_tooltip), 
+            "2 of 14 values selected\nHiding 1, 2, 3, 4, 5, 6, 7, 8, 9, 10 (+2 more)");
+
+        tf.set_selected_values_for_id("miles", (2..=14).map(ScalarValue::U32));
+        assert_eq!(with_column(&tf, "miles", indicator_tooltip), "13 of 14 values selected\nHiding 1");
+        assert_eq!(ActiveIndicator::default().glyph, "⛛");
+    }
+
+    #[test]
+    fn describe_active_summarizes_the_selection_and_search() {
+        let tf = table_filter(flights());
+        assert_eq!(tf.describe_active_for_id("city").unwrap(), "3 of 3 values selected");
+
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(tf.describe_active_for_id("city").unwrap(), "2 of 3 values selected");
+
+        type_search(&tf, "city", "PH");
+        assert_eq!(tf.describe_active_for_id("city").unwrap(), "2 of 3 values selected\nsearch: PH");
+
+        tf.set_selected_values_for_id("miles", Vec::new());
+        assert_eq!(tf.describe_active_for_id("miles").unwrap(), "0 of 4 values selected");
+        assert_eq!(tf.describe_active_for_id("gate"), None);
+    }
+
+    #[test]
+    fn quoted_terms_keep_their_commas() {
+        assert_eq!(split_terms(r#""Dallas, TX",ATL"#), ["Dallas, TX", "ATL"]);
+        assert_eq!(split_terms(r#"\"Big\" Apple,a\,b,ATL,"#), [r#""Big" Apple"#, "a,b", "ATL"]);
+        assert_eq!(split_terms(r#"in(100,200),>500,A\*"#), ["in(100,200)", ">500", r"A\*"]);
+        // quoted text is taken as is, blanks included
+        assert_eq!(split_terms(r#"" ATL ","""#), [" ATL ", ""]);
+
+        let tf = table_filter(vec![row("Dallas, TX", 100), row("Dallas", 200), row("ATL", 300)]);
+        tf.apply_search_for_id("city", r#""Dallas, TX",ATL"#);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "Dallas, TX"]));
+        tf.apply_search_for_id("miles", r#""100",>250"#);
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true]);
+    }
+
+    #[test]
+    fn search_settles_once_typing_pauses_or_on_enter() {
+        // still typing, the list waits out the rest of the debounce
+        assert_eq!(debounce_wait(10.1, 10.0, 0.15, false).map(|wait| (wait * 100.0).round()), Some(5.0));
+        assert_eq!(debounce_wait(10.0, 10.0, 0.15, false), Some(0.15));
+        // idle for the whole window
+        assert_eq!(debounce_wait(10.15, 10.0, 0.15, false), None);
+        assert_eq!(debounce_wait(12.0, 10.0, 0.15, false), None);
+        // Enter or APPLY doesn't wait
+        assert_eq!(debounce_wait(10.0, 10.0, 0.15, true), None);
+        // never edited, or no debounce at all
+        assert_eq!(debounce_wait(0.0, f64::NEG_INFINITY, 0.15, false), None);
+        assert_eq!(debounce_wait(10.0, 10.0, 0.0, false), None);
+    }
+
+    #[test]
+    fn typed_search_relists_after_the_debounce_and_enter_applies_at_once() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let at = |time: f64, events: Vec<egui::Event>| run_frame(&ctx, &tf, "city", egui::RawInput { time: Some(time), events, ..Default::default() });
+        let listed = |centers: &[(String, egui::Pos2)]| ["ATL", "LAX", "PHX"].into_iter()
+            .filter(|city| centers.iter().any(|(text, _)| text == city))
+            .collect::<Vec<_>>();
+        let key = |key| egui::Event::Key { key, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+
+        Popup::open_id(&ctx, Id::new("city"));
+        at(0.0, vec![]);
+        let centers = at(0.0, vec![]);
+        assert_eq!(listed(&centers), ["ATL", "LAX", "PHX"]);
+
+        // focus the search field below its label and type
+        let label = centers.iter().find(|(text, _)| text == "Search...").unwrap().1;
+        let field = label + egui::vec2(0.0, 20.0);
+        let button = |pressed| egui::Event::PointerButton { pos: field, button: egui::PointerButton::Primary, pressed, modifiers: Modifiers::NONE };
+        at(0.5, vec![egui::Event::PointerMoved(field), button(true)]);
+        at(0.5, vec![button(false)]);
+        at(1.0, vec![egui::Event::Text("P".to_string())]);
+        assert_eq!(listed(&at(1.1, vec![])), ["ATL", "LAX", "PHX"]);
+        assert_eq!(listed(&at(1.2, vec![])), ["PHX"]);
+
+        // Enter applies what was typed without waiting
+        at(2.0, vec![key(Key::Backspace), egui::Event::Text("L".to_string()), key(Key::Enter)]);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX"]));
+    }
+
+    #[test]
+    fn popup_lays_out_only_the_values_in_view() {
+        let tf = table_filter((0..1000).map(|i| row("PHX", i)).collect());
+        let ctx = egui::Context::default();
+        let drawn = |centers: Vec<(String, egui::Pos2)>| centers.into_iter()
+            .filter_map(|(text, _)| text.parse::<u32>().ok())
+            .collect::<Vec<_>>();
+
+        let listed = drawn(open_popup(&ctx, &tf, "miles"));
+        assert!(!listed.is_empty() && listed.len() < 50);
+        assert_eq!(listed[..3], [0, 1, 2]);
+
+        // a search shortens the list to the values matching it
+        tf.search_debounce_for_id("miles", 0.0);
+        type_search(&tf, "miles", ">=990");
+        assert_eq!(drawn(run_frame(&ctx, &tf, "miles", Default::default())), (990..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn arrow_keys_move_through_the_list_and_space_toggles() {
+        let tf = table_filter(flights());
+        tf.search_debounce_for_id("city", 0.0);
+        let ctx = egui::Context::default();
+        open_popup(&ctx, &tf, "city");
+        let press = |keys: &[Key]| {
+            for key in keys {
+                let event = egui::Event::Key { key: *key, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+                run_frame(&ctx, &tf, "city", egui::RawInput { events: vec![event], ..Default::default() });
+            }
+        };
+
+        // the first Down lands on the first value
+        press(&[Key::ArrowDown, Key::Space]);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        // Down stops at the last value
+        press(&[Key::ArrowDown, Key::ArrowDown, Key::ArrowDown, Key::Space]);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX"]));
+        press(&[Key::ArrowUp, Key::Space]);
+        assert!(tf.selected_values_for_id("city").is_empty());
+
+        // Up past the first value returns to the search field, where Space toggles nothing
+        press(&[Key::ArrowUp, Key::ArrowUp, Key::ArrowUp, Key::Space]);
+        assert!(tf.selected_values_for_id("city").is_empty());
+
+        // focus on the last value moves onto the last one left once a search shortens the list
+        press(&[Key::ArrowDown, Key::ArrowDown, Key::ArrowDown]);
+        type_search(&tf, "city", "L");
+        press(&[Key::Space]);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX"]));
+    }
+
+    #[test]
+    fn range_sliders_follow_the_search_and_write_it_when_dragged() {
+        let tf = table_filter(flights());
+        tf.range_slider_for_id("miles", true);
+        tf.search_debounce_for_id("miles", 0.0);
+        type_search(&tf, "miles", "200..300");
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "miles");
+        // each bound drawn by its slider and again by the value it lists
+        let drawn = |centers: &[(String, egui::Pos2)], value: &str| centers.iter().filter(|(text, _)| text == value).count();
+        assert_eq!(["100", "200", "300", "400"].map(|value| drawn(&centers, value)), [0, 2, 2, 0]);
+
+        // pressing the far left of the min slider takes it down to the lowest value
+        let popup = ctx.memory(|memory| memory.area_rect(Id::new("miles"))).unwrap();
+        let min_label = centers.iter().find(|(text, _)| text == "min").unwrap().1;
+        let pos = egui::pos2(popup.left() + 10.0, min_label.y);
+        let button = |pressed| egui::Event::PointerButton { pos, button: egui::PointerButton::Primary, pressed, modifiers: Modifiers::NONE };
+        run_frame(&ctx, &tf, "miles", egui::RawInput { events: vec![egui::Event::PointerMoved(pos), button(true)], ..Default::default() });
+        run_frame(&ctx, &tf, "miles", egui::RawInput { events: vec![button(false)], ..Default::default() });
+        assert_eq!(tf.export_state().columns["miles"].search_field, "100..300");
+
+        // and typing a search the sliders can't show leaves them spanning every value
+        type_search(&tf, "miles", ">150");
+        let centers = run_frame(&ctx, &tf, "miles", Default::default());
+        assert_eq!(["100", "200", "300", "400"].map(|value| drawn(&centers, value)), [1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn date_pickers_follow_the_search_and_write_it_when_a_day_is_picked() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .date("day", |r| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Days::new(r.miles as u64 / 100), "%m/%d/%Y")
+            .build();
+        tf.search_debounce_for_id("day", 0.0);
+        let ctx = egui::Context::default();
+        let search = |pattern: &str| type_search(&tf, "day", pattern);
+        let picked = |centers: &[(String, egui::Pos2)]| centers.iter()
+            .filter_map(|(text, _)| text.strip_suffix(" 📆").map(str::to_string))
+            .collect::<Vec<_>>();
+
+        assert_eq!(picked(&open_popup(&ctx, &tf, "day")), ["2024-01-02", "2024-01-05"]);
+        search("01/03/2024..01/04/2024");
+        assert_eq!(picked(&run_frame(&ctx, &tf, "day", Default::default())), ["2024-01-03", "2024-01-04"]);
+        search("=01/03/2024");
+        assert_eq!(picked(&run_frame(&ctx, &tf, "day", Default::default())), ["2024-01-03", "2024-01-03"]);
+        // searches the pickers can't show leave them spanning every day
+        for pattern in ["=a", "02/30/2024..", ">01/03/2024"] {
+            search(pattern);
+            assert_eq!(picked(&run_frame(&ctx, &tf, "day", Default::default())), ["2024-01-02", "2024-01-05"]);
+        }
+
+        // picking a day from the calendar of either picker, the other keeping its end of the days
+        let pick = |picker: &str, day: &str| {
+            let centers = run_frame(&ctx, &tf, "day", Default::default());
+            click_text(&ctx, &tf, "day", &centers, picker, Modifiers::NONE);
+            let centers = run_frame(&ctx, &tf, "day", Default::default());
+            // the day's cell in the first week holding it, right of the week numbers
+            let monday = centers.iter().find(|(text, _)| text == "Mo").unwrap().1;
+            let pos = centers.iter()
+                .filter(|(text, pos)| text == day && pos.x > monday.x - 10.0 && pos.y > monday.y)
+                .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y))
+                .unwrap().1;
+            let centers = click_at(&ctx, &tf, "day", pos, Modifiers::NONE);
+            click_text(&ctx, &tf, "day", &centers, "Save", Modifiers::NONE);
+            // clicks in the calendar, which lies outside the popup, leave the popup open
+            assert!(Popup::is_id_open(&ctx, Id::new("day")));
+            tf.export_state().columns["day"].search_field.clone()
+        };
+        search("");
+        assert_eq!(pick("2024-01-05 📆", "4"), "01/02/2024..01/04/2024");
+        // the same day in both writes an exact day
+        assert_eq!(pick("2024-01-02 📆", "4"), "=01/04/2024");
+    }
+
+    // the cities listed_rows() lists for the search, in order
+    fn listed_for(tf: &TableFilter<Row>, search: &str) -> Vec<&'static str> {
+        let data = tf.backing_data.borrow();
+        with_column(tf, "city", |cf| listed_rows(cf, &search.to_string()).into_iter().map(|i| data[i].city).collect())
+    }
+
+    #[test]
+    fn listed_rows_follow_the_search() {
+        let tf = table_filter(flights());
+        assert_eq!(listed_for(&tf, ""), ["ATL", "LAX", "PHX"]);
+        assert_eq!(listed_for(&tf, "A"), ["ATL", "LAX"]);
+        assert_eq!(listed_for(&tf, "=PHX"), ["PHX"]);
+        assert!(listed_for(&tf, "DEN").is_empty());
+        // an invalid numeric term matches nothing rather than everything
+        assert!(with_column(&tf, "miles", |cf| listed_rows(cf, &">y".to_string())).is_empty());
+
+        let empty = table_filter(vec![]);
+        assert!(listed_for(&empty, "").is_empty());
+        assert!(listed_for(&empty, "A").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_listed_rows_rank_the_best_match_first() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![row("LHA", 1), row("XLA", 2), row("LAX", 3), row("PHX", 4)])))
+            .fuzzy_string("city", |r| r.city.to_string())
+            .build();
+        assert_eq!(listed_for(&tf, ""), ["LAX", "LHA", "PHX", "XLA"]);
+        assert_eq!(listed_for(&tf, "la"), ["LAX", "LHA", "XLA"]);
+    }
+
+    #[test]
+    fn settled_listed_rows_keep_the_old_search_until_it_settles() {
+        let tf = table_filter(flights());
+        let settled_listed = |settled| with_column(&tf, "city", |cf| settled_listed_rows(cf, settled).1);
+        assert_eq!(settled_listed(true), "");
+        type_search(&tf, "city", "L");
+        assert_eq!(settled_listed(false), "");
+        assert_eq!(settled_listed(true), "L");
+
+        // changed data relists straight away, even while typing
+        type_search(&tf, "city", "LA");
+        tf.backing_data.borrow_mut().push(row("LAS", 500));
+        tf.mark_dirty();
+        assert_eq!(settled_listed(false), "LA");
+        let data = tf.backing_data.borrow();
+        let rows = with_column(&tf, "city", |cf| settled_listed_rows(cf, false).0);
+        assert_eq!(rows.into_iter().map(|i| data[i].city).collect::<Vec<_>>(), ["LAS", "LAX"]);
+    }
+
+    #[test]
+    fn unused_variants_are_listed_after_the_rows_when_they_match() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .enumeration("region", |r: &Row| if r.city == "ATL" { "East" } else { "West" }, ["East", "West", "North", "Northeast"])
+            .build();
+        let extra = |search: &str| with_column(&tf, "region", |cf| listed_extra(cf, &search.to_string()))
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>();
+        assert_eq!(extra(""), ["North", "Northeast"]);
+        assert_eq!(extra("Northe"), ["Northeast"]);
+        assert!(extra("West").is_empty());
+        // plain string columns list every value from the rows
+        assert!(with_column(&table_filter(flights()), "city", |cf| listed_extra(cf, &String::new())).is_empty());
+    }
+
+    #[test]
+    fn set_selected_ticks_or_clears_the_given_values() {
+        let tf = table_filter(flights());
+        with_column(&tf, "city", |cf| set_selected(cf, strs(&["ATL", "PHX"]), false));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX"]));
+        with_column(&tf, "city", |cf| set_selected(cf, strs(&["PHX", "DEN"]), true));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        with_column(&tf, "city", |cf| set_selected(cf, vec![], false));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+    }
+
+    #[test]
+    fn focus_moves_between_the_search_field_and_the_rows() {
+        // the first Down leaves the search field for the first row
+        assert_eq!(move_focus(None, 3, true, false), Some(0));
+        assert_eq!(move_focus(Some(0), 3, true, false), Some(1));
+        assert_eq!(move_focus(Some(2), 3, true, false), Some(2));
+        // Up from the first row goes back to the search field
+        assert_eq!(move_focus(Some(1), 3, false, true), Some(0));
+        assert_eq!(move_focus(Some(0), 3, false, true), None);
+        assert_eq!(move_focus(None, 3, false, true), None);
+        // a list shortened by the search pulls focus onto its last row, an empty one drops it
+        assert_eq!(move_focus(Some(5), 3, false, false), Some(2));
+        assert_eq!(move_focus(Some(1), 0, true, false), None);
+        assert_eq!(move_focus(None, 0, true, false), None);
+    }
+
+    #[test]
+    fn shift_click_takes_the_rows_from_the_previous_click() {
+        let last = (4, "PH".to_string());
+        assert_eq!(clicked_rows(None, 2, "PH", true, 6), 2..=2);
+        assert_eq!(clicked_rows(Some(&last), 2, "PH", false, 6), 2..=2);
+        assert_eq!(clicked_rows(Some(&last), 2, "PH", true, 6), 2..=4);
+        assert_eq!(clicked_rows(Some(&last), 5, "PH", true, 6), 4..=5);
+        // the anchor is past the end of a list that has since shrunk
+        assert_eq!(clicked_rows(Some(&last), 1, "PH", true, 3), 1..=2);
+        // searched differently since
+        assert_eq!(clicked_rows(Some(&last), 2, "P", true, 6), 2..=2);
+    }
+
+    #[test]
+    fn range_sliders_read_and_write_the_search() {
+        let domain = (100, 400);
+        assert_eq!(slider_bounds("", domain), (100, 400));
+        assert_eq!(slider_bounds("150..300", domain), (150, 300));
+        assert_eq!(slider_bounds("..300", domain), (100, 300));
+        assert_eq!(slider_bounds("150..", domain), (150, 400));
+        assert_eq!(slider_bounds("-5..0", domain), (-5, 0));
+        // searches the sliders can't show leave them spanning the domain
+        assert_eq!(slider_bounds("150..300,500", domain), (100, 400));
+        assert_eq!(slider_bounds(">150", domain), (100, 400));
+        assert_eq!(slider_bounds("a..b", domain), (100, 400));
+
+        assert_eq!(slider_search(100, 400, domain), "");
+        assert_eq!(slider_search(100, 300, domain), "100..300");
+        assert_eq!(slider_search(250, 250, domain), "250..250");
+        assert_eq!(slider_bounds(&slider_search(150, 300, domain), domain), (150, 300));
+    }
+
+    #[test]
+    fn date_pickers_read_and_write_the_search() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let domain = (day(1), day(31));
+        let pattern = "%Y-%m-%d";
+        assert_eq!(picked_dates("", pattern, domain), domain);
+        assert_eq!(picked_dates("2024-03-05..2024-03-09", pattern, domain), (day(5), day(9)));
+        assert_eq!(picked_dates("..2024-03-09", pattern, domain), (day(1), day(9)));
+        assert_eq!(picked_dates("2024-03-05 ..", pattern, domain), (day(5), day(31)));
+        assert_eq!(picked_dates("=2024-03-05", pattern, domain), (day(5), day(5)));
+        // searches the pickers can't show leave them spanning the domain
+        assert_eq!(picked_dates("=", pattern, domain), (day(1), day(1)));
+        assert_eq!(picked_dates("=a", pattern, domain), domain);
+        assert_eq!(picked_dates("2024-02-30..2024-03-09", pattern, domain), domain);
+        assert_eq!(picked_dates("2024-03-05..2024-03-09,2024-03-20", pattern, domain), domain);
+        assert_eq!(picked_dates("03/05/2024..03/09/2024", pattern, domain), domain);
+
+        assert_eq!(picked_search(day(1), day(31), pattern, domain), "");
+        assert_eq!(picked_search(day(5), day(5), pattern, domain), "=2024-03-05");
+        assert_eq!(picked_search(day(1), day(9), pattern, domain), "2024-03-01..2024-03-09");
+        assert_eq!(picked_search(day(5), day(9), "%d/%m/%Y", domain), "05/03/2024..09/03/2024");
+        assert_eq!(picked_dates(&picked_search(day(5), day(9), pattern, domain), pattern, domain), (day(5), day(9)));
+    }
+
+    #[test]
+    fn closing_without_a_snapshot_only_clears_the_popup_state() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        type_search(&tf, "city", "LA");
+        with_column(&tf, "city", |cf| {
+            cf.column_filter_state().focused_row.set(Some(1));
+            popup_closed(cf, CloseAction::DiscardOnClose);
+            assert_eq!(cf.column_filter_state().focused_row.get(), None);
+        });
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        assert_eq!(tf.export_state().columns["city"].search_field, "LA");
+
+        // with one, keeping the popup open until APPLY leaves the changes pending
+        with_column(&tf, "city", |cf| {
+            cf.take_snapshot();
+            set_selected(cf, strs(&["LAX"]), false);
+            popup_closed(cf, CloseAction::KeepOpenUntilApply);
+        });
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+        assert_eq!(tf.export_state().columns["city"].search_field, "LA");
+    }
+
+    // the cities the popup lists, in the order it lists them
+    fn listed_cities(tf: &TableFilter<Row>) -> Vec<&'static str> {
+        let data = tf.backing_data.borrow();
+        let mut listed = Vec::new();
+        with_column(tf, "city", |cf| {
+            let mut listed_data = cf.unique_rows().iter().map(|i| &data[*i]).collect::<Vec<_>>();
+            let counts = visible_counts(cf, &cf.selectable_value_bool_array());
+            arrange_listed(cf, &mut listed_data, &counts, false);
+            listed = listed_data.iter().map(|r| r.city).collect();
+        });
+        listed
+    }
+
+    #[test]
+    fn frequency_order_lists_common_values_first() {
+        let tf = table_filter(vec![row("LAX", 100), row("PHX", 200), row("ATL", 100), row("PHX", 300), row("PHX", 100), row("LAX", 300)]);
+        assert_eq!(listed_cities(&tf), ["ATL", "LAX", "PHX"]);
+
+        tf.value_order_for_id("city", ValueOrder::Frequency);
+        assert_eq!(listed_cities(&tf), ["PHX", "LAX", "ATL"]);
+
+        // counted over the rows passing the other columns, ties keeping their sorted order
+        tf.set_selected_values_for_id("miles", [ScalarValue::U32(100)]);
+        assert_eq!(listed_cities(&tf), ["ATL", "LAX", "PHX"]);
+        tf.set_selected_values_for_id("miles", [ScalarValue::U32(300)]);
+        assert_eq!(listed_cities(&tf), ["LAX", "PHX", "ATL"]);
+    }
+
+    #[test]
+    fn reopened_popup_keeps_its_scroll_offset() {
+        let tf = table_filter((0..100).map(|i| row(["ATL", "LAX", "ORD", "PHX"][i % 4], i as u32)).collect());
+        let ctx = egui::Context::default();
+        with_column(&tf, "miles", |cf| cf.column_filter_state().scroll_offset.set(40.0));
+
+        popup_frame(&ctx, &tf, "miles", true);
+        popup_frame(&ctx, &tf, "miles", true);
+        popup_frame(&ctx, &tf, "miles", false);
+        popup_frame(&ctx, &tf, "miles", true);
+        with_column(&tf, "miles", |cf| assert_eq!(cf.column_filter_state().scroll_offset.get(), 40.0));
+    }
+
+    #[test]
+    fn popup_size_follows_the_column_overrides() {
+        let tf = table_filter((0..100).map(|i| row(["Dallas/Fort Worth International", "Phoenix Sky Harbor International"][i % 2], i as u32)).collect());
+        // a fresh context each time, as egui sizes an area from the popup width only when first shown
+        let popup_rect = |id: &str| {
+            let ctx = egui::Context::default();
+            open_popup(&ctx, &tf, id);
+            ctx.memory(|memory| memory.area_rect(Id::new(id))).unwrap()
+        };
+        let (city, miles) = (popup_rect("city"), popup_rect("miles"));
+
+        tf.popup_width_for_id("city", 320.0);
+        tf.popup_max_height_for_id("miles", 120.0);
+        let (wide_city, short_miles) = (popup_rect("city"), popup_rect("miles"));
+        assert!(wide_city.width() >= 320.0 && wide_city.width() > city.width());
+        assert_eq!((miles.height() - short_miles.height()).round(), DEFAULT_POPUP_MAX_HEIGHT - 120.0);
+        // the other dimension keeps its default
+        assert_eq!(wide_city.height(), city.height());
+        assert_eq!(short_miles.width(), miles.width());
+    }
+
+    #[test]
+    fn only_selected_hides_unselected_values_from_the_list() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(listed_cities(&tf), ["ATL", "LAX", "PHX"]);
+
+        with_column(&tf, "city", |cf| cf.column_filter_state().only_selected.set(true));
+        assert_eq!(listed_cities(&tf), ["ATL", "PHX"]);
+        // only the list changes, not the selection
+        assert_eq!(tf.export_state().columns["city"].unselected_values, strs(&["LAX"]));
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn computed_bool_column_derives_long_haul_from_miles() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![row("PHX", 300), row("JFK", 2100), row("LAX", 1500), row("ATL", 1501)])))
+            .computed_bool("long_haul", |r: &Row| r.miles > 1500, ("Yes", "No"))
+            .build();
+        with_column(&tf, "long_haul", |cf| {
+            let data = tf.backing_data.borrow();
+            assert_eq!((cf.get_value(&data[1]), cf.get_string_value(&data[1])), (ScalarValue::Bool(true), "Yes".to_string()));
+            assert_eq!((cf.get_value(&data[2]), cf.get_string_value(&data[2])), (ScalarValue::Bool(false), "No".to_string()));
+        });
+
+        tf.set_selected_values_for_id("long_haul", [ScalarValue::Bool(true)]);
+        assert_eq!(*tf.evaluate_array(), vec![false, true, false, true]);
+        tf.set_selected_values_for_id("long_haul", [ScalarValue::Bool(false)]);
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true, false]);
+        assert_eq!(tf.describe_active_for_id("long_haul").unwrap(), "1 of 2 values selected");
+    }
+
+    #[test]
+    fn matching_indices_agree_with_the_mask() {
+        let cities = ["ATL", "LAX", "ORD", "PHX"];
+        let tf = table_filter((0..50).map(|i| row(cities[i % 4], i as u32 * 37 % 500)).collect());
+        tf.exclude_value_for_id("city", ScalarValue::Str("ORD".to_string()));
+        tf.apply_search_for_id("miles", "100..400");
+
+        let mask = tf.evaluate_array();
+        let expected = (0..mask.len()).filter(|i| mask[*i]).collect::<Vec<_>>();
+        assert!(!expected.is_empty() && expected.len() < mask.len());
+        assert_eq!(tf.matching_indices(), expected);
+        assert_eq!(tf.matching_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(tf.counts(), (50, expected.len()));
+    }
+
+    #[test]
+    fn filterable_table_draws_headers_and_the_filtered_rows() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let draw = || {
+            let output = ctx.run(Default::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    crate::filterable_table::FilterableTable::new(&tf)
+                        .column("CITY", "city", egui_extras::Column::auto(), |ui, r| { ui.label(r.city); })
+                        .column("MILES", "miles", egui_extras::Column::remainder(), |ui, r| { ui.label(format!("{} mi", r.miles)); })
+                        .show(ui);
+                });
+            });
+            let mut centers = vec![];
+            output.shapes.iter().for_each(|clipped| text_centers(&clipped.shape, &mut centers));
+            centers.into_iter().map(|(text, _)| text).collect::<Vec<_>>()
+        };
+        let cells = |texts: Vec<String>| texts.into_iter().filter(|text| text.ends_with(" mi")).collect::<Vec<_>>();
+
+        let texts = draw();
+        assert!(texts.iter().any(|text| text == "CITY") && texts.iter().any(|text| text == "MILES"));
+        assert_eq!(cells(texts), ["100 mi", "200 mi", "300 mi", "400 mi"]);
+
+        // the body follows the filters and the sort
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.cycle_sort_for_id("miles", false);
+        tf.cycle_sort_for_id("miles", false);
+        assert_eq!(cells(draw()), ["400 mi", "300 mi", "100 mi"]);
+
+        tf.apply_search_for_id("city", "DEN");
+        let texts = draw();
+        assert!(texts.iter().any(|text| text == "CITY"));
+        assert!(cells(texts).is_empty());
+    }
+
+    #[test]
+    fn body_rows_lays_out_only_the_rows_in_view() {
+        let tf = table_filter((0..100_000).map(|i| row(if i % 2 == 0 { "PHX" } else { "LAX" }, i)).collect());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.cycle_sort_for_id("miles", false);
+        tf.cycle_sort_for_id("miles", false);
+
+        let ctx = egui::Context::default();
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0))),
+            ..Default::default()
+        };
+        let mut added = Vec::new();
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                egui_extras::TableBuilder::new(ui)
+                    .column(egui_extras::Column::remainder())
+                    .body(|body| tf.body_rows(body, 18.0, |mut table_row, r| {
+                        table_row.col(|ui| { ui.label(r.city); });
+                        added.push(r.miles);
+                    }));
+            });
+        });
+
+        assert!(!added.is_empty() && added.len() < 100);
+        // the filtered rows in their sort order, descending miles
+        assert_eq!(added[..3], [99_998, 99_996, 99_994]);
+    }
+
+    #[test]
+    fn builder_registers_typed_columns_in_call_order() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .u32("miles", |r| r.miles)
+            .string("city", |r| r.city.to_string())
+            .date("day", |r| NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Days::new(r.miles as u64 / 100), "%m/%d/%Y")
+            .computed_bool("long_haul", |r| r.miles > 250, ("Long", "Short"))
+            .filter(|tf| Box::new(StringColumnFilter::new("gate", tf, Box::new(|r: &Row| format!("{}1", r.city)))))
+            .build();
+        let first = &flights()[0];
+        assert_eq!(tf.value_for_id("miles", first), Some((ScalarValue::U32(100), "100".to_string())));
+        assert_eq!(tf.value_for_id("city", first), Some((ScalarValue::Str("PHX".to_string()), "PHX".to_string())));
+        assert_eq!(tf.value_for_id("day", first).map(|(_, text)| text), Some("01/02/2024".to_string()));
+        assert_eq!(tf.value_for_id("long_haul", first).map(|(_, text)| text), Some("Short".to_string()));
+        assert_eq!(tf.value_for_id("gate", first).map(|(_, text)| text), Some("PHX1".to_string()));
+        assert_eq!(tf.value_for_id("orig", first), None);
+
+        // every column filters the table, in the order they were added
+        tf.apply_search_for_id("long_haul", "Long");
+        tf.apply_search_for_id("gate", "=LAX1");
+        tf.apply_search_for_id("day", "01/03/2024..");
+        tf.apply_search_for_id("city", "A");
+        tf.apply_search_for_id("miles", "<350");
+        assert_eq!(tf.active_column_ids(), ["miles", "city", "day", "long_haul", "gate"]);
+        assert!(tf.matching_indices().is_empty());
+        tf.reset_for_id("gate");
+        assert_eq!(tf.matching_indices(), [2]);
+
+        // an empty builder keeps every row
+        let empty = TableFilter::builder(&Rc::new(RefCell::new(flights()))).build();
+        assert_eq!(empty.matching_indices(), [0, 1, 2, 3]);
+    }
+
+    // a column written outside the crate's filters, implementing only the required methods
+    struct FirstLetterFilter {
+        column_filter_state: ColumnFilterState<Row>
+    }
+
+    impl ColumnFilter<Row> for FirstLetterFilter {
+        fn id(&self) -> &str { "first_letter" }
+        fn get_value(&self, t: &Row) -> ScalarValue { ScalarValue::Str(t.city[..1].to_string()) }
+        fn column_filter_state(&self) -> &ColumnFilterState<Row> { &self.column_filter_state }
+    }
+
+    #[test]
+    fn custom_column_filter_plugs_into_the_builder() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![row("PHX", 100), row("LAX", 200), row("ATL", 300), row("LAS", 400)])))
+            .filter(|tf| Box::new(FirstLetterFilter { column_filter_state: ColumnFilterState::new(&tf) }))
+            .u32("miles", |r| r.miles)
+            .build();
+        assert_eq!(tf.selected_values_for_id("first_letter"), strs(&["A", "L", "P"]));
+
+        tf.exclude_value_for_id("first_letter", ScalarValue::Str("P".to_string()));
+        assert_eq!(tf.matching_indices(), [1, 2, 3]);
+        tf.apply_search_for_id("first_letter", "L");
+        assert_eq!(tf.matching_indices(), [1, 3]);
+        assert!(tf.predicate_for_id("first_letter")(&row("LGA", 0)));
+
+        // its popup lists the values like any other column's
+        tf.reset_for_id("first_letter");
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "first_letter");
+        assert!(["A", "L", "P"].iter().all(|value| centers.iter().any(|(text, _)| text == value)));
+        click_text(&ctx, &tf, "first_letter", &centers, "A", Modifiers::NONE);
+        assert_eq!(tf.selected_values_for_id("first_letter"), strs(&["L", "P"]));
+    }
+
+    #[test]
+    fn string_matching_columns_match_plain_terms_by_their_mode() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .string_matching("suffix", |r: &Row| r.city.to_string(), MatchMode::Suffix)
+            .string("auto", |r: &Row| r.city.to_string())
+            .build();
+        tf.apply_search_for_id("suffix", "X");
+        assert_eq!(tf.matching_indices(), [0, 1, 3]);
+        tf.apply_search_for_id("auto", "A");
+        assert_eq!(tf.matching_indices(), [1]);
+
+        // = and globs keep their meaning in any mode, and nothing matching selects nothing
+        tf.reset_all();
+        tf.apply_search_for_id("suffix", "=PH");
+        assert!(tf.matching_indices().is_empty());
+        tf.apply_search_for_id("suffix", "P*");
+        assert_eq!(tf.matching_indices(), [0, 3]);
+    }
+
+    #[test]
+    fn borrowed_string_column_filters_like_an_owned_one() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .string("owned", |r: &Row| r.city.to_string())
+            .string_ref("borrowed", |r: &Row| r.city)
+            .build();
+        let data = tf.backing_data.borrow();
+        with_column(&tf, "borrowed", |cf| assert_eq!(cf.get_str_ref(&data[0]), Some("PHX")));
+        with_column(&tf, "owned", |cf| assert_eq!(cf.get_str_ref(&data[0]), None));
+        drop(data);
+
+        for id in ["owned", "borrowed"] {
+            tf.reset_all();
+            tf.exclude_value_for_id(id, ScalarValue::Str("PHX".to_string()));
+            assert_eq!(*tf.evaluate_array(), vec![false, true, true, false]);
+            assert_eq!(tf.backing_data.borrow().iter().map(|r| tf.evaluate(r)).collect::<Vec<_>>(), vec![false, true, true, false]);
+            tf.apply_search_for_id(id, "A");
+            assert_eq!(tf.selected_values_for_id(id), strs(&["ATL", "LAX"]));
+        }
+    }
+
+    #[test]
+    fn eval_arrays_are_reused_until_their_column_or_the_data_changes() {
+        let tf = table_filter(flights());
+        let evals = |id: &str| with_column(&tf, id, |cf| cf.get_eval_bool_array());
+        let (city, miles, all) = (evals("city"), evals("miles"), tf.evaluate_array());
+        assert!(Rc::ptr_eq(&city, &evals("city")));
+        assert!(Rc::ptr_eq(&all, &tf.evaluate_array()));
+
+        // only the changed column is evaluated again
+        tf.exclude_value_for_id("miles", ScalarValue::U32(200));
+        assert!(Rc::ptr_eq(&city, &evals("city")));
+        assert!(!Rc::ptr_eq(&miles, &evals("miles")));
+        assert_eq!(*evals("miles"), [true, false, true, true]);
+        assert_eq!(*tf.evaluate_array(), [true, false, true, true]);
+        assert_eq!(tf.evaluate_array_excluding_id("miles"), [true, true, true, true]);
+
+        // and again once the value is selected back
+        let miles = evals("miles");
+        tf.set_selected_values_for_id("miles", [100, 200, 300, 400].map(ScalarValue::U32));
+        assert!(!Rc::ptr_eq(&miles, &evals("miles")));
+
+        // edited rows evaluate every column again
+        tf.backing_data.borrow_mut()[0].city = "LAX";
+        tf.mark_dirty();
+        assert!(!Rc::ptr_eq(&city, &evals("city")));
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(*tf.evaluate_array(), [false, false, true, true]);
+    }
+
+    #[test]
+    fn batch_notifies_once_per_column_and_recomputes_once() {
+        let tf = table_filter(flights());
+        let before = tf.evaluate_array();
+        let log = change_log(&tf);
+
+        tf.apply_batch(|batch| {
+            batch.set_selected_values_for_id("city", strs(&["PHX", "ATL"]));
+            batch.apply_search_for_id("miles", ">150");
+            batch.set_selected_values_for_id("city", strs(&["PHX", "LAX"]));
+            // nothing is evaluated or notified inside the batch
+            assert!(log.borrow().is_empty());
+        });
+        assert_eq!(*log.borrow(), [("city".to_string(), true), ("miles".to_string(), true)]);
+
+        assert!(tf.is_dirty());
+        let after = tf.evaluate_array();
+        assert!(!Rc::ptr_eq(&before, &after));
+        assert_eq!(*after, vec![false, true, false, true]);
+        assert!(!tf.is_dirty());
+        assert!(Rc::ptr_eq(&after, &tf.evaluate_array()));
+
+        // the batch is undone as one step
+        assert!(tf.undo());
+        assert!(tf.active_column_ids().is_empty());
+    }
+
+    #[test]
+    fn popup_operations_run_without_a_ui() {
+        let tf = table_filter(flights());
+        let unselected = || tf.export_state().columns["miles"].unselected_values.clone();
+        let miles = |values: &[u32]| values.iter().copied().map(ScalarValue::U32).collect::<Vec<_>>();
+
+        assert!(tf.with_column_for_id("miles", |cf| {
+            assert_eq!(cf.search_matches(&">250".to_string()), [
+                (ScalarValue::U32(100), false),
+                (ScalarValue::U32(200), false),
+                (ScalarValue::U32(300), true),
+                (ScalarValue::U32(400), true),
+            ]);
+            cf.select_none(miles(&[100, 200, 300]));
+        }).is_some());
+        assert_eq!(unselected(), miles(&[100, 200, 300]));
+
+        tf.with_column_for_id("miles", |cf| cf.select_all(miles(&[200])));
+        assert_eq!(unselected(), miles(&[100, 300]));
+
+        tf.with_column_for_id("miles", |cf| cf.invert_selection(miles(&[100, 200, 300, 400])));
+        assert_eq!(unselected(), miles(&[200, 400]));
+
+        tf.with_column_for_id("miles", |cf| cf.reset());
+        assert!(unselected().is_empty());
+    }
+
+    #[test]
+    fn scalar_values_display_each_variant() {
+        let ipv6 = std::net::IpAddr::V6(std::net::Ipv6Addr::LOCALHOST);
+        let formatted = [
+            (ScalarValue::Str("PHX".to_string()), "PHX"),
+            (ScalarValue::U8(255), "255"),
+            (ScalarValue::I8(-128), "-128"),
+            (ScalarValue::U32(4_000_000_000), "4000000000"),
+            (ScalarValue::USize(7), "7"),
+            (ScalarValue::I32(-42), "-42"),
+            (ScalarValue::Bool(true), "true"),
+            (ScalarValue::IpAddr([10, 0, 0, 1].into()), "10.0.0.1"),
+            (ScalarValue::IpAddr(ipv6), "::1"),
+            (ScalarValue::Fields(vec!["AA100".to_string(), "BA2001".to_string()]), "AA100 / BA2001"),
+        ];
+        for (value, expected) in formatted {
+            assert_eq!(value.to_string(), expected);
+        }
+
+        // columns without a string mapper display through it
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .u32("miles", |r: &Row| r.miles)
+            .build();
+        assert_eq!(with_column(&tf, "miles", |cf| cf.get_string_value(&row("PHX", 1500))), "1500");
+    }
+
+    #[test]
+    fn scalar_values_have_a_total_order() {
+        let ip = |s: &str| ScalarValue::IpAddr(s.parse().unwrap());
+        // within a variant
+        assert!(ScalarValue::Str("A10".to_string()) < ScalarValue::Str("A9".to_string()));
+        assert!(ScalarValue::I32(-5) < ScalarValue::I32(3));
+        assert!(ScalarValue::Bool(false) < ScalarValue::Bool(true));
+        assert!(ip("9.0.0.1") < ip("10.0.0.1"));
+
+        // across variants: bools, numbers by value, IP addresses, then strings
+        let mut values = vec![
+            ScalarValue::Str("0".to_string()),
+            ip("::1"),
+            ip("10.0.0.1"),
+            ScalarValue::U32(300),
+            ScalarValue::USize(2),
+            ScalarValue::I32(2),
+            ScalarValue::U8(2),
+            ScalarValue::I8(-1),
+            ScalarValue::Bool(true),
+        ];
+        values.sort();
+        assert_eq!(values, [
+            ScalarValue::Bool(true),
+            ScalarValue::I8(-1),
+            ScalarValue::U8(2),
+            ScalarValue::USize(2),
+            ScalarValue::I32(2),
+            ScalarValue::U32(300),
+            ip("10.0.0.1"),
+            ip("::1"),
+            ScalarValue::Str("0".to_string()),
+        ]);
+
+        // consistent with Eq: equal numbers of different variants aren't equal
+        assert_ne!(ScalarValue::U8(2).cmp(&ScalarValue::I32(2)), Ordering::Equal);
+        assert_eq!(ScalarValue::U32(2).cmp(&ScalarValue::U32(2)), Ordering::Equal);
+    }
+
+    #[test]
+    fn large_usize_values_dont_wrap() {
+        assert!(ScalarValue::USize(usize::MAX) > ScalarValue::USize(0));
+        assert!(ScalarValue::USize(usize::MAX) > ScalarValue::I32(-1));
+        assert!(ScalarValue::I32(-1) < ScalarValue::USize(0));
+        // too large for the range slider's i64 domain, so it's left out rather than wrapped
+        assert_eq!(ScalarValue::USize(usize::MAX).as_i64(), None);
+        assert_eq!(ScalarValue::USize(usize::MAX).as_i128(), Some(usize::MAX as i128));
+    }
+
+    #[test]
+    fn presets_save_overwrite_and_load() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.save_preset("no LAX");
+        tf.apply_search_for_id("miles", ">250");
+        tf.save_preset("long");
+        assert_eq!(tf.preset_names(), ["long", "no LAX"]);
+
+        // loading replaces every column, including ones the preset leaves unfiltered
+        assert!(tf.load_preset("no LAX"));
+        assert_eq!(tf.active_column_ids(), ["city"]);
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true, true]);
+
+        tf.reset_all();
+        tf.apply_search_for_id("city", "ATL");
+        tf.save_preset("long");
+        assert!(tf.load_preset("long"));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL"]));
+        assert!(!tf.is_active_for_id("miles"));
+
+        tf.remove_preset("long");
+        assert!(!tf.load_preset("long"));
+        assert_eq!(tf.preset_names(), ["no LAX"]);
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_changes_across_columns() {
+        let tf = table_filter(flights());
+        assert!(!tf.can_undo() && !tf.undo());
+
+        tf.set_selected_values_for_id("city", strs(&["ATL", "PHX"]));
+        tf.apply_search_for_id("miles", ">250");
+        tf.set_selected_values_for_id("city", strs(&["PHX"]));
+        let unselected = |id: &str| tf.export_state().columns[id].unselected_values.clone();
+
+        assert!(tf.undo());
+        assert_eq!(unselected("city"), strs(&["LAX"]));
+        assert!(tf.undo());
+        assert!(unselected("miles").is_empty());
+        assert!(tf.redo());
+        assert_eq!(unselected("miles"), [ScalarValue::U32(100), ScalarValue::U32(200)]);
+        assert!(tf.can_redo());
+
+        // a new change drops what could be redone
+        tf.set_selected_values_for_id("city", strs(&["ATL"]));
+        assert!(!tf.can_redo() && !tf.redo());
+        assert_eq!(unselected("city"), strs(&["LAX", "PHX"]));
+
+        tf.history_depth(1);
+        assert!(tf.undo());
+        assert!(!tf.undo());
+        assert_eq!(unselected("city"), strs(&["LAX"]));
+        assert_eq!(unselected("miles"), [ScalarValue::U32(100), ScalarValue::U32(200)]);
+    }
+
+    #[test]
+    fn summary_bar_shows_only_while_a_column_is_active() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let summary_shapes = || ctx.run(egui::RawInput::default(), |ctx| {
+            egui::Area::new(Id::new("summary")).show(ctx, |ui| tf.summary_ui(ui));
+        }).shapes.len();
+
+        assert_eq!(summary_shapes(), 0);
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(100));
+        assert!(summary_shapes() > 0);
+        // drawing the chips leaves the filters as they are
+        assert_eq!(tf.active_column_ids(), ["city", "miles"]);
+
+        tf.reset_all();
+        assert_eq!(summary_shapes(), 0);
+    }
+
+    #[test]
+    fn blanks_are_labeled_and_placed_as_configured() {
+        let tf = table_filter(vec![row("PHX", 100), row("", 200), row("ATL", 300)]);
+        let data = &tf.backing_data;
+        let listed_texts = || {
+            let mut texts = vec![];
+            tf.with_column_for_id("city", |cf| texts = listed_cities(&tf).into_iter()
+                .map(|city| cf.listed_text(data.borrow().iter().find(|r| r.city == city).unwrap()))
+                .collect());
+            texts
+        };
+        assert_eq!(listed_texts(), ["(blank)", "ATL", "PHX"]);
+
+        tf.blank_label_for_id("city", "N/A");
+        tf.blanks_position_for_id("city", BlanksPosition::Last);
+        assert_eq!(listed_texts(), ["ATL", "PHX", "N/A"]);
+        tf.blanks_position_for_id("city", BlanksPosition::First);
+        assert_eq!(listed_texts(), ["N/A", "ATL", "PHX"]);
+
+        // still first with the values listed in descending order
+        tf.value_direction_for_id("city", SortDirection::Descending);
+        assert_eq!(listed_texts(), ["N/A", "PHX", "ATL"]);
+        tf.blanks_position_for_id("city", BlanksPosition::Last);
+        assert_eq!(listed_texts(), ["PHX", "ATL", "N/A"]);
+    }
+
+    #[test]
+    fn filter_to_and_exclude_a_cell_value() {
+        let tf = table_filter(flights());
+        let data = tf.backing_data();
+        // what the cell's context menu passes along
+        let (phx, _) = tf.value_for_id("city", &data.borrow()[0]).unwrap();
+
+        tf.exclude_value_for_id("city", phx.clone());
+        assert_eq!(*tf.evaluate_array(), vec![false, true, true, false]);
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        assert_eq!(*tf.evaluate_array(), vec![false, true, false, false]);
+
+        // replaces the column's selection, the other columns are left alone
+        tf.exclude_value_for_id("miles", ScalarValue::U32(400));
+        tf.filter_to_value_for_id("city", phx);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+        assert_eq!(*tf.evaluate_array(), vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn custom_search_operators_take_precedence_over_built_ins() {
+        let tf = table_filter(vec![row("PHX", 100), row("LAX", 1200), row("ATL", 2500), row("lax", 300)]);
+        tf.search_operator_for_id("city", "~", |value, operand| value.to_lowercase().contains(&operand.to_lowercase()));
+        tf.apply_search_for_id("city", "~La,P");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX", "lax"]));
+
+        // `>>` goes to its own operator rather than the built-in `>`, the longest prefix winning
+        tf.search_operator_for_id("miles", ">>", |value, operand| value.len() > operand.len());
+        tf.apply_search_for_id("miles", ">>100");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(1200), ScalarValue::U32(2500)]);
+        tf.apply_search_for_id("miles", ">200");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(300), ScalarValue::U32(1200), ScalarValue::U32(2500)]);
+        // custom operands aren't checked as numbers, built-in ones still are
+        assert!(tf.with_column_for_id("miles", |cf| assert_eq!(cf.search_error_for(">>x,>y").unwrap(), "Invalid number: >y")).is_some());
+
+        // registering a prefix again replaces its operator
+        tf.search_operator_for_id("city", "~", |value, operand| value == operand);
+        tf.apply_search_for_id("city", "~lax");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["lax"]));
+    }
+
+    #[test]
+    fn semicolon_locale_reads_comma_decimals() {
+        let european = SearchLocale { term_separator: ';', decimal_separator: ',', date_pattern: None };
+        assert_eq!(localize_pattern("1.234,56;>2,5;1..3", european, true).unwrap(), "1234.56,>2.5,1..3");
+        assert_eq!(localize_pattern("Dallas, TX;ATL", european, false).unwrap(), r#""Dallas, TX",ATL"#);
+        assert_eq!(localize_pattern("ATL", SearchLocale::default(), false), None);
+
+        let tf = table_filter(vec![row("Dallas, TX", 1200), row("Dallas", 100), row("ATL", 300)]);
+        tf.search_locale(european);
+        tf.apply_search_for_id("city", "Dallas, TX;ATL");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "Dallas, TX"]));
+        tf.apply_search_for_id("miles", "1.200;<200");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(100), ScalarValue::U32(1200)]);
+
+        // comma decimals compare by value, and commas inside a set still separate its values
+        tf.apply_search_for_id("miles", ">299,5");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(300), ScalarValue::U32(1200)]);
+        assert_eq!(with_column(&tf, "miles", |cf| cf.search_error_for(">299,5")), None);
+        tf.apply_search_for_id("miles", "in(100,300)");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(100), ScalarValue::U32(300)]);
+        assert_eq!(localize_pattern("!in(1,2);2,5", european, true).unwrap(), r#""!in(1,2)",2.5"#);
+    }
+
+    #[test]
+    fn decimal_operands_compare_by_value() {
+        let tf = table_filter(vec![row("ATL", 2), row("LAX", 3), row("PHX", 5)]);
+        tf.apply_search_for_id("miles", ">2.5");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(3), ScalarValue::U32(5)]);
+        tf.apply_search_for_id("miles", "<=2.9");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(2)]);
+        tf.apply_search_for_id("miles", "=3.0,4.5..5.5");
+        assert_eq!(tf.selected_values_for_id("miles"), [ScalarValue::U32(3), ScalarValue::U32(5)]);
+        tf.apply_search_for_id("miles", "=2.5");
+        assert!(tf.selected_values_for_id("miles").is_empty());
+        assert_eq!(with_column(&tf, "miles", |cf| cf.search_error_for(">2.")).unwrap(), "Invalid number: >2.");
+    }
+
+    #[test]
+    fn locale_date_pattern_parses_alongside_the_column_pattern() {
+        let tf = table_filter(vec![row("ATL", 1), row("LAX", 32), row("PHX", 60)]);
+        tf.column_filter(Box::new(NaiveDateColumnFilter::new("day", Rc::clone(&tf), "%m/%d/%Y".to_string(), Box::new(|r: &Row| NaiveDate::from_yo_opt(2026, r.miles).unwrap()))));
+        let search_error = |pattern: &str| with_column(&tf, "day", |cf| cf.search_error_for(pattern));
+        assert_eq!(search_error(">15.01.2026").unwrap(), "Invalid date: >15.01.2026");
+
+        tf.search_locale(SearchLocale { term_separator: ';', decimal_separator: ',', date_pattern: Some("%d.%m.%Y") });
+        tf.apply_search_for_id("day", ">15.01.2026;<03/01/2026");
+        assert_eq!(search_error(">15.01.2026;<03/01/2026"), None);
+        assert_eq!(tf.selected_values_for_id("day"), [ScalarValue::I32(NaiveDate::from_yo_opt(2026, 32).unwrap().to_epoch_days())]);
+    }
+
+    #[test]
+    fn in_memory_data_source_pages_the_sorted_unique_values() {
+        let tf = table_filter(flights());
+        let source = InMemoryDataSource::new(Rc::downgrade(&tf));
+        let cities = |page| source.unique_values("city", page).into_iter().map(|(_, s)| s).collect::<Vec<_>>();
+
+        assert_eq!(source.unique_value_count("city"), 3);
+        assert_eq!(cities(0..2), ["ATL", "LAX"]);
+        assert_eq!(cities(2..10), ["PHX"]);
+        assert!(cities(5..10).is_empty());
+        assert_eq!(source.unique_values("miles", 0..1), [(ScalarValue::U32(100), "100".to_string())]);
+        assert_eq!(source.unique_value_count("gate"), 0);
+    }
+
+    #[test]
+    fn data_source_column_selects_from_source_values() {
+        let tf = table_filter(vec![row("PHX", 100), row("LAX", 200), row("PHX", 300)]);
+        tf.data_source_for_id("city", Rc::new(InMemoryDataSource::new(Rc::downgrade(&tf))));
+
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        assert_eq!(tf.describe_active_for_id("city").unwrap(), "2 of 2 values selected");
+
+        tf.set_selected_values_for_id("city", strs(&["PHX"]));
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+        assert_eq!(tf.describe_active_for_id("city").unwrap(), "1 of 2 values selected");
+        assert_eq!(*tf.evaluate_array(), vec![true, false, true]);
+
+        tf.filter_to_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(*tf.evaluate_array(), vec![false, true, false]);
+
+        tf.apply_query_string("city=PHX");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX"]));
+    }
+
+    // a source of 500 numbered cities, recording each page asked of it
+    struct NumberedCities {
+        pages: RefCell<Vec<Range<usize>>>
+    }
+
+    impl FilterDataSource for NumberedCities {
+        fn unique_value_count(&self, _id: &str) -> usize {
+            500
+        }
+        fn unique_values(&self, _id: &str, page: Range<usize>) -> Vec<(ScalarValue, String)> {
+            self.pages.borrow_mut().push(page.clone());
+            page.map(|i| format!("C{:03}", i))
+                .map(|city| (ScalarValue::Str(city.clone()), city))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn data_source_is_paged_and_cached_until_the_data_changes() {
+        let tf = table_filter(flights());
+        let source = Rc::new(NumberedCities { pages: RefCell::new(vec![]) });
+        tf.data_source_for_id("city", Rc::clone(&source) as Rc<dyn FilterDataSource>);
+        let state_values = |range| {
+            let mut values = vec![];
+            tf.with_column_for_id("city", |cf| values = source_values(cf.column_filter_state(), "city", &*source, range));
+            values.into_iter().map(|(_, text)| text).collect::<Vec<_>>()
+        };
+
+        assert_eq!(state_values(195..205), ["C195", "C196", "C197", "C198", "C199", "C200", "C201", "C202", "C203", "C204"]);
+        assert_eq!(*source.pages.borrow(), [0..200, 200..400]);
+        state_values(0..10);
+        assert_eq!(source.pages.borrow().len(), 2);
+
+        // the popup search asks the source for the matching values through the default paging
+        tf.apply_search_for_id("city", "C49");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["C490", "C491", "C492", "C493", "C494", "C495", "C496", "C497", "C498", "C499"]));
+        assert_eq!(source.pages.borrow()[2..], [0..500, 400..500]);
+
+        tf.mark_dirty();
+        state_values(0..10);
+        assert_eq!(source.pages.borrow().last(), Some(&(0..200)));
+    }
+
+    #[test]
+    fn provider_search_is_asked_once_and_polled_until_it_answers() {
+        let tf = table_filter(flights());
+        let poll = |search: &str| with_column(&tf, "city", |cf| poll_provider_search(cf, &search.to_string()));
+        assert_eq!(poll("P"), None);
+
+        let senders = Rc::new(RefCell::new(vec![]));
+        let asked = senders.clone();
+        tf.search_provider_for_id("city", move |query| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            asked.borrow_mut().push((query.to_string(), sender));
+            receiver
+        });
+        // nothing to ask without a search
+        assert_eq!(poll(""), None);
+        assert_eq!(poll("P"), Some(false));
+        assert_eq!(poll("P"), Some(false));
+        assert_eq!(senders.borrow().len(), 1);
+
+        senders.borrow()[0].1.send(vec!["LAX".to_string()]).unwrap();
+        assert_eq!(poll("P"), Some(true));
+        assert_eq!(poll("P"), None);
+        assert_eq!(senders.borrow().len(), 1);
+
+        // a new search is asked for again, and a provider hanging up answers it too
+        assert_eq!(poll("A"), Some(false));
+        senders.borrow_mut().pop();
+        assert_eq!(poll("A"), Some(true));
+        assert_eq!(senders.borrow().iter().map(|(query, _)| query.as_str()).collect::<Vec<_>>(), ["P"]);
+    }
+
+    #[test]
+    fn search_provider_answers_replace_the_local_matches() {
+        let tf = table_filter(flights());
+        let ctx = egui::Context::default();
+        let queries = Rc::new(RefCell::new(vec![]));
+        let asked = queries.clone();
+        tf.search_provider_for_id("city", move |query| {
+            asked.borrow_mut().push(query.to_string());
+            let (sender, receiver) = std::sync::mpsc::channel();
+            sender.send(vec!["LAX".to_string()]).unwrap();
+            receiver
+        });
+        tf.search_debounce_for_id("city", 0.0);
+        type_search(&tf, "city", "P");
+
+        popup_frame(&ctx, &tf, "city", true);
+        popup_frame(&ctx, &tf, "city", true);
+        assert_eq!(*queries.borrow(), ["P"]);
+        tf.apply_search_for_id("city", "P");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX"]));
+
+        // a provider that hangs up without answering leaves the local search in place
+        tf.reset_for_id("city");
+        tf.search_provider_for_id("city", |_| std::sync::mpsc::channel::<Vec<String>>().1);
+        type_search(&tf, "city", "P");
+        popup_frame(&ctx, &tf, "city", true);
+        tf.apply_search_for_id("city", "P");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+    }
+
+    #[test]
+    fn counts_track_the_columns_combination_and_global_search() {
+        let tf = table_filter(flights());
+        assert_eq!(tf.counts(), (4, 4));
+
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(tf.counts(), (4, 3));
+
+        tf.set_selected_values_for_id("miles", [ScalarValue::U32(300)]);
+        assert_eq!(tf.counts(), (4, 1));
+        tf.combine(Combine::Or);
+        assert_eq!(tf.counts(), (4, 3));
+
+        tf.combine(Combine::And);
+        tf.reset_all();
+        tf.global_search("phx");
+        assert_eq!(tf.counts(), (4, 2));
+        tf.global_search("");
+        assert_eq!(tf.counts(), (4, 4));
+    }
+
+    #[test]
+    fn global_search_ignores_case_in_every_column() {
+        let tf = table_filter(vec![row("Phoenix", 100), row("LAX", 200), row("ÉPINAL", 300), row("Atlanta", 1400)]);
+        let global = |query: &str| {
+            tf.global_search(query);
+            tf.matching_indices()
+        };
+        assert_eq!(global("PHOE"), [0]);
+        assert_eq!(global("épi"), [2]);
+        // several terms match prefixes, = whole values, in any column
+        assert_eq!(global("at,lax,14"), [1, 3]);
+        assert_eq!(global("=lax,=phoenix"), [0, 1]);
+        assert_eq!(global("=lax,=phoe"), [1]);
+        assert_eq!(global("!an"), [0, 1, 2]);
+        assert_eq!(global(" , "), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn bool_popup_hides_the_search_and_ignores_enter() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![row("PHX", 300), row("JFK", 2100)])))
+            .string("city", |r: &Row| r.city.to_string())
+            .bool("long_haul", |r: &Row| r.miles > 1500)
+            .build();
+        let ctx = egui::Context::default();
+        let press_enter = |id: &str| {
+            let enter = egui::Event::Key { key: Key::Enter, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+            run_frame(&ctx, &tf, id, egui::RawInput { events: vec![enter], ..Default::default() });
+        };
+
+        assert!(tf.with_column_for_id("long_haul", |cf| assert!(!cf.shows_search())).is_some());
+        assert!(tf.with_column_for_id("city", |cf| assert!(cf.shows_search())).is_some());
+
+        type_search(&tf, "city", "PHX");
+        popup_frame(&ctx, &tf, "city", true);
+        press_enter("city");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+
+        // a leftover pattern in the hidden field isn't applied by Enter
+        type_search(&tf, "long_haul", "true");
+        popup_frame(&ctx, &tf, "long_haul", true);
+        press_enter("long_haul");
+        assert!(!tf.is_active_for_id("long_haul"));
+
+        tf.show_search_for_id("long_haul", true);
+        press_enter("long_haul");
+        assert_eq!(tf.selected_values_for_id("long_haul"), [ScalarValue::Bool(true)]);
+    }
+
+    // opens the city popup, excludes ATL and types "LA", then clicks well outside the popup,
+    // returning the selection, the search field and whether the popup is still open
+    fn click_outside_city_popup(close_action: CloseAction) -> (Vec<ScalarValue>, String, bool) {
+        let tf = table_filter(flights());
+        tf.close_action_for_id("city", close_action);
+        let ctx = egui::Context::default();
+        popup_frame(&ctx, &tf, "city", true);
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        type_search(&tf, "city", "LA");
+
+        let outside = egui::pos2(700.0, 500.0);
+        let click = |pressed| egui::Event::PointerButton { pos: outside, button: egui::PointerButton::Primary, pressed, modifiers: Modifiers::NONE };
+        for events in [vec![egui::Event::PointerMoved(outside), click(true)], vec![click(false)], vec![], vec![]] {
+            run_frame(&ctx, &tf, "city", egui::RawInput { events, ..Default::default() });
+        }
+        let search_field = tf.export_state().columns["city"].search_field.clone();
+        (tf.selected_values_for_id("city"), search_field, Popup::is_id_open(&ctx, Id::new("city")))
+    }
+
+    #[test]
+    fn close_actions_apply_discard_or_keep_the_popup_open() {
+        assert_eq!(click_outside_city_popup(CloseAction::ApplyOnClose), (strs(&["LAX"]), String::new(), false));
+        assert_eq!(click_outside_city_popup(CloseAction::DiscardOnClose), (strs(&["ATL", "LAX", "PHX"]), String::new(), false));
+        assert_eq!(click_outside_city_popup(CloseAction::KeepOpenUntilApply), (strs(&["LAX", "PHX"]), "LA".to_string(), true));
+    }
+
+    #[test]
+    fn popup_style_colors_values_and_falls_back_to_the_theme() {
+        let dark = FilterPopupStyle {
+            selected_color: Some(Color32::LIGHT_GREEN),
+            unselected_color: Some(Color32::DARK_GRAY),
+            absent_color: Some(Color32::BLACK),
+            ..Default::default()
+        };
+        let ctx = egui::Context::default();
+        ctx.set_visuals(egui::Visuals::dark());
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let theme = FilterPopupStyle::default();
+                assert_eq!(theme.value_color(ui, true, true), ui.visuals().text_color());
+                assert_eq!(theme.value_color(ui, false, true), ui.visuals().text_color());
+                assert_eq!(theme.value_color(ui, true, false), ui.visuals().weak_text_color());
+
+                assert_eq!(dark.value_color(ui, true, true), Color32::LIGHT_GREEN);
+                assert_eq!(dark.value_color(ui, false, true), Color32::DARK_GRAY);
+                assert_eq!(dark.value_color(ui, true, false), Color32::BLACK);
+            });
+        });
+
+        // a styled popup draws its list like any other
+        let tf = table_filter(flights());
+        tf.set_popup_style(dark);
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        popup_frame(&ctx, &tf, "city", true);
+        popup_frame(&ctx, &tf, "city", true);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "PHX"]));
+    }
+
+    #[test]
+    fn relabeled_buttons_draw_their_labels_and_enter_still_applies() {
+        let tf = table_filter(flights());
+        tf.set_popup_labels(PopupLabels {
+            apply: "✔ OK".to_string(),
+            reset: "Zurücksetzen".to_string(),
+            ..Default::default()
+        });
+        let ctx = egui::Context::default();
+        let texts = open_popup(&ctx, &tf, "city").into_iter().map(|(text, _)| text).collect::<Vec<_>>();
+        assert!(texts.iter().any(|t| t == "✔ OK"));
+        assert!(texts.iter().any(|t| t == "Zurücksetzen"));
+        assert!(texts.iter().any(|t| t == "NONE"));
+        assert!(!texts.iter().any(|t| t == "APPLY" || t == "RESET"));
+
+        type_search(&tf, "city", "PHX");
+        let enter = egui::Event::Key { key: Key::Enter, physical_key: None, pressed: true, repeat: false, modifiers: Modifiers::NONE };
+        run_frame(&ctx, &tf, "city", egui::RawInput { events: vec![enter], ..Default::default() });
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["PHX"]));
+    }
+
+    #[test]
+    fn linked_origin_and_destination_combine_with_or() {
+        struct Leg {
+            orig: &'static str,
+            dest: &'static str,
+            miles: u32
+        }
+        let legs = |linked: bool| {
+            let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![
+                Leg { orig: "PHX", dest: "LAX", miles: 100 },
+                Leg { orig: "ATL", dest: "PHX", miles: 200 },
+                Leg { orig: "LAX", dest: "ATL", miles: 300 },
+                Leg { orig: "ORD", dest: "ATL", miles: 400 },
+            ])))
+                .string("orig", |l: &Leg| l.orig.to_string())
+                .string("dest", |l: &Leg| l.dest.to_string())
+                .u32("miles", |l: &Leg| l.miles)
+                .build();
+            if linked {
+                tf.link_columns(LinkedColumnGroup::new(&["orig", "dest"], Combine::Or));
+            }
+            tf
+        };
+
+        let unlinked = legs(false);
+        unlinked.set_selected_values_for_id("orig", strs(&["PHX"]));
+        assert_eq!(unlinked.matching_indices(), [0]);
+
+        let tf = legs(true);
+        tf.set_selected_values_for_id("orig", strs(&["PHX"]));
+        assert_eq!(tf.selected_values_for_id("dest"), strs(&["PHX"]));
+        assert_eq!(tf.matching_indices(), [0, 1]);
+
+        // the group as a whole is still ANDed with the other columns
+        tf.exclude_value_for_id("miles", ScalarValue::U32(100));
+        assert_eq!(tf.matching_indices(), [1]);
+
+        tf.set_selected_values_for_id("dest", strs(&["ATL", "LAX"]));
+        assert_eq!(tf.selected_values_for_id("orig"), strs(&["ATL", "LAX", "ORD"]));
+        assert_eq!(tf.matching_indices(), [1, 2, 3]);
+        assert_eq!(tf.matching_iter().collect::<Vec<_>>(), [1, 2, 3]);
+
+        // linking columns already evaluated regroups them
+        unlinked.link_columns(LinkedColumnGroup::new(&["orig", "dest"], Combine::Or));
+        unlinked.set_selected_values_for_id("orig", strs(&["PHX"]));
+        assert_eq!(unlinked.matching_indices(), [0, 1]);
+        assert_eq!(unlinked.matching_iter().collect::<Vec<_>>(), [0, 1]);
+    }
+
+    #[test]
+    fn malformed_operands_surface_as_a_search_error() {
+        let tf = table_filter(flights());
+        assert_eq!(tf.search_error_for_id("miles"), None);
+
+        type_search(&tf, "miles", ">abc");
+        assert_eq!(tf.search_error_for_id("miles").unwrap(), "Invalid number: >abc");
+        type_search(&tf, "miles", "1..x,>=5,!>2o");
+        assert_eq!(tf.search_error_for_id("miles").unwrap(), "Invalid number: 1..x, !>2o");
+        type_search(&tf, "miles", ">5,10..20,abc");
+        assert_eq!(tf.search_error_for_id("miles"), None);
+
+        type_search(&tf, "city", ">abc");
+        assert_eq!(tf.search_error_for_id("city"), None);
+        assert_eq!(tf.search_error_for_id("gate"), None);
+
+        // evaluation doesn't fail, a malformed term just matches nothing
+        tf.apply_search_for_id("miles", ">abc");
+        assert!(tf.matching_indices().is_empty());
+    }
+
+    #[test]
+    fn default_unselected_values_filter_from_the_start_and_on_reset() {
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(flights())))
+            .string("city", |r: &Row| r.city.to_string())
+            .filter(|tf| Box::new(BoolColumnFilter::from_mapper("long_haul", tf, Box::new(|r: &Row| r.miles > 250))
+                .default_unselected([ScalarValue::Bool(true)])))
+            .build();
+        assert!(tf.is_active_for_id("long_haul"));
+        assert_eq!(tf.active_column_ids(), ["long_haul"]);
+        assert_eq!(tf.matching_indices(), [0, 1]);
+
+        tf.set_selected_values_for_id("long_haul", [ScalarValue::Bool(false), ScalarValue::Bool(true)]);
+        assert_eq!(tf.matching_indices(), [0, 1, 2, 3]);
+        tf.reset_for_id("long_haul");
+        assert_eq!(tf.selected_values_for_id("long_haul"), [ScalarValue::Bool(false)]);
+        assert_eq!(tf.matching_indices(), [0, 1]);
+
+        tf.default_unselected_for_id("city", strs(&["LAX"]));
+        assert_eq!(tf.matching_indices(), [0]);
+        tf.set_selected_values_for_id("city", strs(&["ATL", "LAX", "PHX"]));
+        tf.set_selected_values_for_id("long_haul", [ScalarValue::Bool(false), ScalarValue::Bool(true)]);
+        tf.reset_all();
+        assert_eq!(tf.matching_indices(), [0]);
+    }
+
+    #[test]
+    fn reset_mode_picks_the_baseline_reset_returns_to() {
+        let tf = table_filter(flights());
+        tf.default_unselected_for_id("city", strs(&["LAX"]));
+        tf.default_unselected_for_id("miles", [ScalarValue::U32(400)]);
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        tf.apply_search_for_id("miles", "<250");
+
+        tf.reset_for_id("city");
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "PHX"]));
+
+        tf.reset_mode_for_id("city", ResetMode::Empty);
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        tf.reset_for_id("city");
+        assert!(!tf.is_active_for_id("city"));
+
+        // CLEAR ALL resets each column by its own mode
+        tf.exclude_value_for_id("city", ScalarValue::Str("ATL".to_string()));
+        tf.reset_all();
+        assert!(!tf.is_active_for_id("city"));
+        assert_eq!(tf.matching_indices(), [0, 1, 2]);
+
+        // without defaults both modes select everything
+        let plain = table_filter(flights());
+        plain.exclude_value_for_id("miles", ScalarValue::U32(100));
+        plain.reset_for_id("miles");
+        assert!(!plain.is_active_for_id("miles"));
+    }
+
+    #[test]
+    fn descending_direction_lists_the_latest_dates_first() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![day(9), day(1), day(20), day(9), day(15)])))
+            .date("day", |d: &NaiveDate| *d, "%m/%d/%Y")
+            .build();
+        let data = tf.backing_data();
+        let listed_days = || {
+            let data = data.borrow();
+            let mut listed = vec![];
+            tf.with_column_for_id("day", |cf| {
+                let mut listed_data = cf.unique_rows().iter().map(|i| &data[*i]).collect::<Vec<_>>();
+                let counts = visible_counts(cf, &cf.selectable_value_bool_array());
+                arrange_listed(cf, &mut listed_data, &counts, false);
+                listed = listed_data.iter().map(|d| cf.get_string_value(d)).collect();
+            });
+            listed
+        };
+        assert_eq!(listed_days(), ["03/01/2026", "03/09/2026", "03/15/2026", "03/20/2026"]);
+
+        tf.value_direction_for_id("day", SortDirection::Descending);
+        assert_eq!(listed_days(), ["03/20/2026", "03/15/2026", "03/09/2026", "03/01/2026"]);
+
+        // with frequency order the direction flips the counts, rarest first
+        tf.value_order_for_id("day", ValueOrder::Frequency);
+        assert_eq!(listed_days().last().unwrap(), "03/09/2026");
+        tf.value_direction_for_id("day", SortDirection::Ascending);
+        assert_eq!(listed_days().first().unwrap(), "03/09/2026");
+    }
+
+    #[test]
+    fn shift_click_sets_the_range_from_the_previous_click() {
+        let tf = table_filter(["ATL", "DEN", "LAX", "ORD", "PHX"].into_iter().map(|city| row(city, 100)).collect());
+        let ctx = egui::Context::default();
+        let click = |centers: &[(String, egui::Pos2)], text: &str, modifiers: Modifiers| click_text(&ctx, &tf, "city", centers, text, modifiers);
+        let centers = open_popup(&ctx, &tf, "city");
+
+        let centers = click(&centers, "DEN", Modifiers::NONE);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX", "ORD", "PHX"]));
+        let centers = click(&centers, "PHX", Modifiers::SHIFT);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL"]));
+
+        // a shift-click takes the state of the row clicked, here checking LAX and ORD back
+        let centers = click(&centers, "LAX", Modifiers::NONE);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX"]));
+        click(&centers, "ORD", Modifiers::SHIFT);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "LAX", "ORD"]));
+    }
+
+    #[test]
+    fn multi_field_search_matches_the_secondary_field() {
+        // flight number and codeshare number
+        type Flight = (&'static str, &'static str);
+        let fields: Vec<StrMapper<Flight>> = vec![Box::new(|f| f.0.to_string()), Box::new(|f| f.1.to_string())];
+        let tf = TableFilter::builder(&Rc::new(RefCell::new(vec![("AA100", "BA2001"), ("AA200", "BA2002"), ("DL300", "AF3003"), ("AA100", "BA2001")])))
+            .multi_field_string("flight", fields)
+            .build();
+        assert_eq!(tf.value_for_id("flight", &("AA100", "BA2001")).unwrap().1, "AA100 / BA2001");
+
+        tf.apply_search_for_id("flight", "BA");
+        assert_eq!(tf.matching_indices(), [0, 1, 3]);
+        let fields = |a: &str, b: &str| ScalarValue::Fields(vec![a.to_string(), b.to_string()]);
+        assert_eq!(tf.selected_values_for_id("flight"), [fields("AA100", "BA2001"), fields("AA200", "BA2002")]);
+
+        tf.apply_search_for_id("flight", "=AF3003");
+        assert_eq!(tf.matching_indices(), [2]);
+        tf.apply_search_for_id("flight", "DL");
+        assert_eq!(tf.matching_indices(), [2]);
+
+        // terms match within one field, never across the separator
+        tf.apply_search_for_id("flight", "1 / B");
+        assert!(tf.matching_indices().is_empty());
+
+        // combinations displayed alike are still told apart
+        tf.backing_data().borrow_mut().extend([("A / B", "C"), ("A", "B / C")]);
+        tf.mark_dirty();
+        tf.reset_all();
+        tf.filter_to_value_for_id("flight", fields("A", "B / C"));
+        assert_eq!(tf.matching_indices(), [5]);
+    }
+
+    #[test]
+    fn prune_stale_drops_values_the_new_data_lacks() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        tf.exclude_value_for_id("miles", ScalarValue::U32(200));
+
+        *tf.backing_data().borrow_mut() = vec![row("PHX", 100), row("ATL", 200)];
+        tf.mark_dirty();
+        // LAX is gone, yet the column still reads as filtering
+        assert!(tf.is_active_for_id("city"));
+        assert_eq!(tf.matching_indices(), [0]);
+
+        assert_eq!(tf.prune_stale(), ["city"]);
+        assert!(!tf.is_active_for_id("city"));
+        assert_eq!(tf.active_column_ids(), ["miles"]);
+        assert_eq!(tf.matching_indices(), [0]);
+        assert!(tf.prune_stale().is_empty());
+    }
+
+    #[test]
+    fn select_all_checkbox_is_indeterminate_while_only_some_are_selected() {
+        let tf = table_filter(flights());
+        let cities = strs(&["ATL", "LAX", "PHX"]);
+        let master = || {
+            let mut state = (false, false);
+            tf.with_column_for_id("city", |cf| state = tri_state(cf, &cities));
+            state
+        };
+        assert_eq!(master(), (true, false));
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert_eq!(master(), (false, true));
+        tf.set_selected_values_for_id("city", []);
+        assert_eq!(master(), (false, false));
+
+        // clicking it while partial selects every listed value
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "city");
+        click_text(&ctx, &tf, "city", &centers, "(Select All)", Modifiers::NONE);
+        assert!(!tf.is_active_for_id("city"));
+        assert_eq!(master(), (true, false));
+    }
+
+    #[test]
+    fn predicate_filters_rows_outside_the_table() {
+        let tf = table_filter(flights());
+        tf.exclude_value_for_id("city", ScalarValue::Str("PHX".to_string()));
+        let predicate = tf.predicate_for_id("city");
+
+        // e.g. a background job holding its own copy of the rows
+        let rows = [row("LAX", 1), row("PHX", 2), row("SEA", 3), row("ATL", 4)];
+        let kept = rows.iter().filter(|r| predicate(r)).map(|r| r.city).collect::<Vec<_>>();
+        assert_eq!(kept, ["LAX", "SEA", "ATL"]);
+
+        // later changes in the table don't reach a predicate taken before them
+        tf.exclude_value_for_id("city", ScalarValue::Str("LAX".to_string()));
+        assert!(predicate(&row("LAX", 1)));
+        assert!(!tf.predicate_for_id("city")(&row("LAX", 1)));
+
+        // an unknown column keeps every row
+        assert!(tf.predicate_for_id("gate")(&row("PHX", 1)));
+
+        // a column filter held directly
+        let city = StringColumnFilter::new("city", Rc::clone(&tf), Box::new(|r: &Row| r.city.to_string()));
+        city.exclude_value(ScalarValue::Str("SEA".to_string()));
+        let predicate = city.as_predicate();
+        city.exclude_value(ScalarValue::Str("ATL".to_string()));
+        assert_eq!(rows.iter().filter(|r| predicate(r)).map(|r| r.city).collect::<Vec<_>>(), ["LAX", "PHX", "ATL"]);
+    }
+
+    #[test]
+    fn selecting_a_parent_cascades_to_its_children() {
+        let tf = table_filter(vec![row("PHX", 100), row("LAX", 200), row("ATL", 300), row("JFK", 400), row("PHX", 500)]);
+        let region = |v: &ScalarValue| match v.to_string().as_str() {
+            "PHX" | "LAX" => "West",
+            _ => "East",
+        };
+        // a column without parents ignores parent selections
+        tf.set_parent_selected_for_id("city", "West", false);
+        assert!(!tf.is_active_for_id("city"));
+
+        tf.parent_of_for_id("city", region);
+        tf.set_parent_selected_for_id("city", "West", false);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["ATL", "JFK"]));
+        assert_eq!(tf.matching_indices(), [2, 3]);
+        tf.set_parent_selected_for_id("city", "East", false);
+        assert!(tf.selected_values_for_id("city").is_empty());
+        tf.set_parent_selected_for_id("city", "West", true);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+        assert_eq!(tf.matching_indices(), [0, 1, 4]);
+        tf.set_parent_selected_for_id("city", "North", true);
+        assert_eq!(tf.selected_values_for_id("city"), strs(&["LAX", "PHX"]));
+
+        // each parent selection is one undoable step
+        assert!(tf.undo());
+        assert!(tf.selected_values_for_id("city").is_empty());
+
+        // the popup's parent checkbox does the same
+        tf.set_parent_selected_for_id("city", "West", true);
+        let ctx = egui::Context::default();
+        let centers = open_popup(&ctx, &tf, "city");
+        click_text(&ctx, &tf, "city", &centers, "East", Modifiers::NONE);
+        assert!(!tf.is_active_for_id("city"));
     }
 }